@@ -585,7 +585,7 @@ mod tests {
     use rocksdb::Options;
     use typed_store::{
         rocks,
-        rocks::{MetricConf, ReadWriteOptions, errors::typed_store_err_from_rocks_err},
+        rocks::{MetricConf, ReadWriteOptions},
     };
     use walrus_sui::client::retry_client::retriable_rpc_client::LazyFallibleRpcClientBuilder;
     use walrus_utils::{backoff::ExponentialBackoffConfig, tests::global_test_lock};
@@ -650,9 +650,7 @@ mod tests {
             )?
         };
         if database.cf_handle("checkpoint_store").is_none() {
-            database
-                .create_cf("checkpoint_store", &Options::default())
-                .map_err(typed_store_err_from_rocks_err)?;
+            database.create_cf("checkpoint_store", &Options::default())?;
         }
         let checkpoint_store = DBMap::reopen(
             &database,
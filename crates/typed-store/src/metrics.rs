@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     sync::{
         Arc,
         atomic::{AtomicU64, Ordering},
@@ -22,10 +22,24 @@ use prometheus::{
 };
 use rocksdb::{PerfContext, PerfMetric, PerfStatsLevel, perf::set_perf_stats};
 use tap::TapFallible;
+use tokio::time::Instant;
 
 thread_local! {
     static PER_THREAD_ROCKS_PERF_CONTEXT: std::cell::RefCell<rocksdb::PerfContext> =
         RefCell::new(PerfContext::default());
+    /// The perf stats level last set through [`set_perf_level`] on this thread.
+    static PER_THREAD_PERF_LEVEL: Cell<PerfStatsLevel> =
+        const { Cell::new(PerfStatsLevel::Disable) };
+}
+
+/// Sets RocksDB's perf stats level of the calling thread, returning the level previously set
+/// through this function.
+///
+/// RocksDB does not expose the current level, so levels set by calling
+/// [`rocksdb::perf::set_perf_stats`] directly are not known here.
+pub(crate) fn set_perf_level(level: PerfStatsLevel) -> PerfStatsLevel {
+    set_perf_stats(level);
+    PER_THREAD_PERF_LEVEL.with(|current| current.replace(level))
 }
 
 const LATENCY_SEC_BUCKETS: &[f64] = &[
@@ -35,6 +49,9 @@ const LATENCY_SEC_BUCKETS: &[f64] = &[
     0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1., 2.5, 5., 10.,
 ];
 
+/// The period over which adaptive sampling measures the load and adjusts its rate.
+const ADAPTIVE_SAMPLING_PERIOD: Duration = Duration::from_secs(1);
+
 #[derive(Debug, Clone)]
 /// A struct for sampling based on number of operations or duration.
 /// Sampling happens if the duration expires and after number of operations
@@ -45,6 +62,49 @@ pub struct SamplingInterval {
     pub after_num_ops: u64,
     /// Counter for keeping track of previous sample
     pub counter: Arc<AtomicU64>,
+    /// The adaptive rate, if the interval was created with `new_adaptive`
+    adaptive: Option<AdaptiveRate>,
+}
+
+#[derive(Debug, Clone)]
+struct AdaptiveRate {
+    target_samples_per_sec: u64,
+    /// The number of operations skipped between samples, adjusted to the observed load
+    num_ops: Arc<AtomicU64>,
+    /// The instant relative to which the start of the current period is measured
+    epoch: Instant,
+    /// The start of the current period, in nanoseconds since `epoch`
+    period_start_nanos: Arc<AtomicU64>,
+}
+
+impl AdaptiveRate {
+    /// Adjusts the number of skipped operations to the load observed in the current period, if
+    /// the period elapsed, and starts a new period
+    fn adapt(&self, counter: &AtomicU64) {
+        let now_nanos = u64::try_from(self.epoch.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        let period_start_nanos = self.period_start_nanos.load(Ordering::Relaxed);
+        let elapsed_nanos = now_nanos.saturating_sub(period_start_nanos);
+        if u128::from(elapsed_nanos) < ADAPTIVE_SAMPLING_PERIOD.as_nanos()
+            || self
+                .period_start_nanos
+                .compare_exchange(
+                    period_start_nanos,
+                    now_nanos,
+                    Ordering::SeqCst,
+                    Ordering::Relaxed,
+                )
+                .is_err()
+        {
+            return;
+        }
+        let ops = u128::from(counter.swap(0, Ordering::SeqCst));
+        let ops_per_sec = ops * Duration::from_secs(1).as_nanos() / u128::from(elapsed_nanos);
+        let num_ops = (ops_per_sec / u128::from(self.target_samples_per_sec)).saturating_sub(1);
+        self.num_ops.store(
+            u64::try_from(num_ops).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
 }
 
 impl Default for SamplingInterval {
@@ -73,15 +133,52 @@ impl SamplingInterval {
             once_every_duration,
             after_num_ops,
             counter,
+            adaptive: None,
+        }
+    }
+    /// Create a new sampling interval that aims for `target_samples_per_sec` samples per second
+    ///
+    /// The number of operations between samples is adjusted once per second, by the first
+    /// operation after the second elapsed, to the number of operations observed since the last
+    /// adjustment, so the rate follows changes in load with a delay of one second. Until the
+    /// first adjustment, every operation is sampled.
+    pub fn new_adaptive(target_samples_per_sec: u64) -> Self {
+        SamplingInterval {
+            once_every_duration: Duration::ZERO,
+            after_num_ops: 0,
+            counter: Arc::new(AtomicU64::new(0)),
+            adaptive: Some(AdaptiveRate {
+                target_samples_per_sec: target_samples_per_sec.max(1),
+                num_ops: Arc::new(AtomicU64::new(0)),
+                epoch: Instant::now(),
+                period_start_nanos: Arc::new(AtomicU64::new(0)),
+            }),
         }
     }
     /// Create a new sampling interval from the current one
     pub fn new_from_self(&self) -> SamplingInterval {
-        SamplingInterval::new(self.once_every_duration, self.after_num_ops)
+        match &self.adaptive {
+            Some(adaptive) => SamplingInterval::new_adaptive(adaptive.target_samples_per_sec),
+            None => SamplingInterval::new(self.once_every_duration, self.after_num_ops),
+        }
+    }
+    /// Get the number of operations currently skipped between two samples
+    pub fn effective_num_ops(&self) -> u64 {
+        match &self.adaptive {
+            Some(adaptive) => {
+                adaptive.adapt(&self.counter);
+                adaptive.num_ops.load(Ordering::Relaxed)
+            }
+            None => self.after_num_ops,
+        }
     }
     /// Sample the metrics
     pub fn sample(&self) -> bool {
-        if self.once_every_duration.is_zero() {
+        if let Some(adaptive) = &self.adaptive {
+            adaptive.adapt(&self.counter);
+            let num_ops = adaptive.num_ops.load(Ordering::Relaxed);
+            self.counter.fetch_add(1, Ordering::Relaxed) % (num_ops + 1) == 0
+        } else if self.once_every_duration.is_zero() {
             self.counter.fetch_add(1, Ordering::Relaxed) % (self.after_num_ops + 1) == 0
         } else {
             self.counter.fetch_add(1, Ordering::Relaxed) == 0
@@ -354,6 +451,9 @@ pub struct OperationMetrics {
     pub rocksdb_get_value_bytes: HistogramVec,
     /// Rocksdb contains_key latency in seconds
     pub rocksdb_contains_key_latency_seconds: HistogramVec,
+    /// Latency in seconds of operations sampled at the intervals of the `MetricConf`, by
+    /// operation
+    pub rocksdb_sampled_op_latency_seconds: HistogramVec,
     /// Number of times key_may_exist_cf returned true per CF (potential positives)
     pub rocksdb_bloom_filter_may_exist_true_total: IntCounterVec,
     /// Number of times key_may_exist_cf returned true but get
@@ -419,6 +519,10 @@ pub struct OperationMetrics {
     pub rocksdb_num_bytes_read: IntGaugeVec,
     /// Number of bytes written
     pub rocksdb_num_bytes_written: IntGaugeVec,
+    /// Number of blocking reads currently running on behalf of async DB maps
+    pub rocksdb_async_reads_in_flight: IntGaugeVec,
+    /// Time async reads waited for a free blocking-pool slot in seconds
+    pub rocksdb_async_read_wait_seconds: HistogramVec,
 }
 
 impl OperationMetrics {
@@ -507,6 +611,14 @@ impl OperationMetrics {
                 registry
             )
             .unwrap(),
+            rocksdb_sampled_op_latency_seconds: register_histogram_vec_with_registry!(
+                "rocksdb_sampled_op_latency_seconds",
+                "Rocksdb latency in seconds of sampled operations",
+                &["cf_name", "op"],
+                LATENCY_SEC_BUCKETS.to_vec(),
+                registry,
+            )
+            .unwrap(),
             rocksdb_bloom_filter_may_exist_true_total: register_int_counter_vec_with_registry!(
                 "rocksdb_bloom_filter_may_exist_true_total",
                 "Number of times key_may_exist_cf returned true (potential positives)",
@@ -730,6 +842,21 @@ impl OperationMetrics {
                 registry,
             )
             .unwrap(),
+            rocksdb_async_reads_in_flight: register_int_gauge_vec_with_registry!(
+                "rocksdb_async_reads_in_flight",
+                "Number of blocking reads currently running on behalf of async DB maps",
+                &["cf_name"],
+                registry,
+            )
+            .unwrap(),
+            rocksdb_async_read_wait_seconds: register_histogram_vec_with_registry!(
+                "rocksdb_async_read_wait_seconds",
+                "Time async reads waited for a free blocking-pool slot in seconds",
+                &["cf_name"],
+                LATENCY_SEC_BUCKETS.to_vec(),
+                registry,
+            )
+            .unwrap(),
         }
     }
 }
@@ -740,7 +867,7 @@ pub struct RocksDBPerfContext;
 
 impl Default for RocksDBPerfContext {
     fn default() -> Self {
-        set_perf_stats(PerfStatsLevel::EnableTime);
+        set_perf_level(PerfStatsLevel::EnableTime);
         PER_THREAD_ROCKS_PERF_CONTEXT.with(|perf_context| {
             perf_context.borrow_mut().reset();
         });
@@ -750,7 +877,7 @@ impl Default for RocksDBPerfContext {
 
 impl Drop for RocksDBPerfContext {
     fn drop(&mut self) {
-        set_perf_stats(PerfStatsLevel::Disable);
+        set_perf_level(PerfStatsLevel::Disable);
     }
 }
 
@@ -1039,7 +1166,7 @@ impl ReadPerfContextMetrics {
     /// Report the metrics for the read performance
     pub fn report_metrics(&self, cf_name: &str) {
         PER_THREAD_ROCKS_PERF_CONTEXT.with(|perf_context_cell| {
-            set_perf_stats(PerfStatsLevel::Disable);
+            set_perf_level(PerfStatsLevel::Disable);
             let perf_context = perf_context_cell.borrow();
             self.user_key_comparison_count
                 .with_label_values(&[cf_name])
@@ -1220,7 +1347,7 @@ impl WritePerfContextMetrics {
     /// Report the metrics for the write performance
     pub fn report_metrics(&self, db_name: &str) {
         PER_THREAD_ROCKS_PERF_CONTEXT.with(|perf_context_cell| {
-            set_perf_stats(PerfStatsLevel::Disable);
+            set_perf_level(PerfStatsLevel::Disable);
             let perf_context = perf_context_cell.borrow();
             self.write_wal_nanos
                 .with_label_values(&[db_name])
@@ -1261,6 +1388,8 @@ pub struct DBMetrics {
     pub read_perf_ctx_metrics: ReadPerfContextMetrics,
     /// The metrics for the write performance
     pub write_perf_ctx_metrics: WritePerfContextMetrics,
+    /// The registry the metrics are registered with
+    registry: Registry,
 }
 
 static ONCE: OnceCell<Arc<DBMetrics>> = OnceCell::new();
@@ -1272,8 +1401,20 @@ impl DBMetrics {
             cf_metrics: ColumnFamilyMetrics::new(registry),
             read_perf_ctx_metrics: ReadPerfContextMetrics::new(registry),
             write_perf_ctx_metrics: WritePerfContextMetrics::new(registry),
+            registry: registry.clone(),
         }
     }
+    /// Get the registry the metrics are registered with, e.g., to scrape them
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+    /// Record the latency of a sampled operation on a column family
+    pub fn observe_sampled_op_latency(&self, cf_name: &str, op: &str, latency: Duration) {
+        self.op_metrics
+            .rocksdb_sampled_op_latency_seconds
+            .with_label_values(&[cf_name, op])
+            .observe(latency.as_secs_f64());
+    }
     /// Initialize the DBMetrics instance
     pub fn init(registry: &Registry) -> &'static Arc<DBMetrics> {
         // Initialize this before creating any instance of DBMap
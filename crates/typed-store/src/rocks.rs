@@ -1,26 +1,85 @@
 // Copyright (c) Walrus Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+/// Async access to a DBMap through a bounded pool of blocking threads.
+pub mod async_map;
+
+/// Incremental backups of a database through RocksDB's backup engine.
+pub mod backup;
+
+/// Scoped settings for bulk loads into a column family.
+pub mod bulk_load;
+
+/// A map with an in-memory cache of recently read values.
+pub mod cached_map;
+
+/// A `HashMap::entry`-style API for atomic read-modify-write of a single key.
+pub mod entry;
+
+/// Pluggable encodings of the keys and values of a map.
+pub mod codec;
+
 /// Error types and utilities for RocksDB operations.
 pub mod errors;
 
+/// Notifications about write stalls, flushes, and background errors of a database.
+pub mod events;
+
+/// A write batch with read-your-own-writes semantics.
+pub mod indexed_batch;
+
+/// A map with secondary indexes maintained in the same atomic batches.
+pub mod indexed_map;
+
+/// Typed merge operators for RocksDB column families.
+pub mod merge_operator;
+
+/// Keys and values stored as raw bytes, without any encoding.
+pub mod raw_bytes;
+
 /// Safe iterator utilities for RocksDB.
 pub(crate) mod safe_iter;
 
+/// A set of keys stored in a column family with empty values.
+pub mod set;
+
+/// Bulk loading of maps through externally written SST files.
+pub mod sst;
+
+/// A write batch that writes itself whenever it reaches a memory budget.
+pub mod streaming_batch;
+
+/// Typed optimistic transactions spanning multiple column families.
+pub mod transaction;
+
 use std::{
     borrow::Borrow,
-    collections::HashSet,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, hash_map::DefaultHasher},
     env,
     ffi::CStr,
     fmt,
+    fs,
+    hash::{Hash, Hasher},
+    io::{BufRead, BufReader, Read, Write},
     marker::PhantomData,
     ops::{Bound, RangeBounds},
     path::{Path, PathBuf},
-    sync::Arc,
-    time::Duration,
+    sync::{
+        Arc,
+        Condvar,
+        Mutex,
+        MutexGuard,
+        PoisonError,
+        RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
 };
 
 use bincode::Options;
+use futures::Stream;
+use rayon::{iter::ParallelIterator, slice::ParallelSlice};
 use rocksdb::{
     AsColumnFamilyRef,
     BlockBasedOptions,
@@ -32,34 +91,49 @@ use rocksdb::{
     DBPinnableSlice,
     DBWithThreadMode,
     Error,
+    IngestExternalFileOptions,
     LiveFile,
     MultiThreaded,
     OptimisticTransactionDB,
     OptimisticTransactionOptions,
+    PerfContext,
+    PerfMetric,
+    PerfStatsLevel,
     ReadOptions,
+    SliceTransform,
     Transaction,
+    WaitForCompactOptions,
     WriteBatch,
     WriteBatchWithTransaction,
     WriteOptions,
     backup::BackupEngine,
     checkpoint::Checkpoint,
     properties::{self, num_files_at_level},
-    statistics::Ticker,
+    statistics::{Histogram, HistogramData, StatsLevel, Ticker},
 };
-use serde::{Serialize, de::DeserializeOwned};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use tap::TapFallible;
 use tokio::sync::oneshot;
 
 use crate::{
     TypedStoreError,
-    metrics::{DBMetrics, RocksDBPerfContext, SamplingInterval},
+    metrics::{DBMetrics, RocksDBPerfContext, SamplingInterval, set_perf_level},
     rocks::{
+        bulk_load::{BulkLoadGuard, BulkLoadState},
+        codec::{Codec, MapCodec},
+        entry::Entry,
         errors::{
             typed_store_err_from_bcs_err,
             typed_store_err_from_bincode_err,
+            typed_store_err_from_json_err,
             typed_store_err_from_rocks_err,
         },
+        events::{DbEvent, DbEventCallback},
+        indexed_batch::IndexedBatch,
+        merge_operator::MergeOperator,
         safe_iter::{IterContext, SafeIter, SafeRevIter},
+        sst::IngestOptions,
+        streaming_batch::StreamingBatch,
     },
     traits::{Map, TableSummary},
 };
@@ -76,6 +150,20 @@ const DEFAULT_DB_WAL_SIZE: usize = 1024;
 
 const ENV_VAR_DB_PARALLELISM: &str = "DB_PARALLELISM";
 
+// Number of lock stripes used to serialize read-modify-write operations per database.
+const NUM_KEY_LOCK_STRIPES: usize = 1024;
+
+/// The number of keys looked up by a single task of [`DBMap::par_multi_get`].
+const PAR_MULTI_GET_CHUNK_SIZE: usize = 1024;
+
+/// The default number of operations per batch for [`DBMap::multi_insert_chunked`] and
+/// [`DBMap::multi_remove_chunked`].
+pub const DEFAULT_WRITE_CHUNK_SIZE: usize = 10_000;
+
+/// The reserved column family holding metadata of the maps of a database, such as their schema
+/// versions, see [`DBMap::open_with_migration`].
+pub const METADATA_CF_NAME: &str = "__typed_store_metadata";
+
 // TODO: remove this after Rust rocksdb has the TOTAL_BLOB_FILES_SIZE property built-in.
 const ROCKSDB_PROPERTY_TOTAL_BLOB_FILES_SIZE: &CStr =
     unsafe { CStr::from_bytes_with_nul_unchecked("rocksdb.total-blob-file-size\0".as_bytes()) };
@@ -169,6 +257,303 @@ impl DbBehavior for rocksdb::OptimisticTransactionDB<MultiThreaded> {
     }
 }
 
+/// Striped locks that serialize atomic read-modify-write operations with each other and with
+/// all other writes of the same key.
+///
+/// Besides the stripe of a key, writes of single keys hold the lock of its column family shared,
+/// while range deletes hold it exclusively, so that range deletes exclude the writes of their
+/// column family only. The locks are reentrant, so that an operation holding the lock of a key
+/// can write the key through methods that lock it again. Column family locks are always taken
+/// before stripes, in the order of the column family names.
+struct KeyLocks {
+    stripes: Vec<KeyLockStripe>,
+    /// The locks of the column families, created on first use.
+    cfs: RwLock<HashMap<String, Arc<CfLock>>>,
+}
+
+impl KeyLocks {
+    fn new() -> Self {
+        Self {
+            stripes: (0..NUM_KEY_LOCK_STRIPES)
+                .map(|_| KeyLockStripe::default())
+                .collect(),
+            cfs: RwLock::default(),
+        }
+    }
+
+    /// Locks the stripe guarding `key` in column family `cf`.
+    fn lock(&self, cf: &str, key: &[u8]) -> KeyLockGuard<'_> {
+        let mut batch_locks = BatchKeyLocks::default();
+        batch_locks.record_key(cf, self.stripe(cf, key));
+        self.lock_batch(&batch_locks)
+    }
+
+    /// Locks the stripes guarding all `keys` in column family `cf`.
+    fn lock_all(&self, cf: &str, keys: &[Vec<u8>]) -> KeyLockGuard<'_> {
+        let mut batch_locks = BatchKeyLocks::default();
+        for key in keys {
+            batch_locks.record_key(cf, self.stripe(cf, key));
+        }
+        self.lock_batch(&batch_locks)
+    }
+
+    /// Locks the column families and stripes a batch writes.
+    ///
+    /// The locks are taken in a fixed order, so that concurrent callers cannot deadlock.
+    fn lock_batch(&self, batch_locks: &BatchKeyLocks) -> KeyLockGuard<'_> {
+        let cfs = batch_locks
+            .cfs
+            .iter()
+            .map(|(cf, &exclusive)| self.cf_lock(cf).lock(exclusive))
+            .collect();
+        let stripes = batch_locks
+            .stripes
+            .iter()
+            .map(|&stripe| self.stripes[stripe].lock())
+            .collect();
+        KeyLockGuard {
+            _stripes: stripes,
+            _cfs: cfs,
+        }
+    }
+
+    fn stripe(&self, cf: &str, key: &[u8]) -> usize {
+        let mut hasher = DefaultHasher::new();
+        cf.hash(&mut hasher);
+        key.hash(&mut hasher);
+        (hasher.finish() % self.stripes.len() as u64) as usize
+    }
+
+    fn cf_lock(&self, cf: &str) -> Arc<CfLock> {
+        // The map only grows by single insertions, so a poisoned lock carries no inconsistent
+        // state.
+        if let Some(lock) = self
+            .cfs
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(cf)
+        {
+            return lock.clone();
+        }
+        self.cfs
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(cf.to_owned())
+            .or_default()
+            .clone()
+    }
+}
+
+/// A reentrant lock guarding one stripe of keys.
+#[derive(Default)]
+struct KeyLockStripe {
+    /// The thread holding the lock and the number of guards it holds.
+    holder: Mutex<Option<(ThreadId, usize)>>,
+    released: Condvar,
+}
+
+impl KeyLockStripe {
+    /// Locks the stripe, waiting until no other thread holds it.
+    fn lock(&self) -> StripeGuard<'_> {
+        let thread = thread::current().id();
+        let mut holder = self.holder();
+        loop {
+            match *holder {
+                None => {
+                    *holder = Some((thread, 1));
+                    break;
+                }
+                Some((owner, ref mut count)) if owner == thread => {
+                    *count += 1;
+                    break;
+                }
+                Some(_) => {}
+            }
+            holder = self
+                .released
+                .wait(holder)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        StripeGuard {
+            stripe: self,
+            _not_send: PhantomData,
+        }
+    }
+
+    fn unlock(&self) {
+        let mut holder = self.holder();
+        if let Some((_, count)) = holder.as_mut() {
+            *count -= 1;
+            if *count == 0 {
+                *holder = None;
+                self.released.notify_one();
+            }
+        }
+    }
+
+    fn holder(&self) -> MutexGuard<'_, Option<(ThreadId, usize)>> {
+        // The holder is only updated in single assignments, so a poisoned lock carries no
+        // inconsistent state.
+        self.holder.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A held lock of a stripe of keys, released when dropped.
+struct StripeGuard<'a> {
+    stripe: &'a KeyLockStripe,
+    /// Keeps the guard on the thread that holds the reentrant lock.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for StripeGuard<'_> {
+    fn drop(&mut self) {
+        self.stripe.unlock();
+    }
+}
+
+/// A reentrant reader-writer lock of a column family, held shared by writes of single keys and
+/// exclusively by range deletes.
+///
+/// A thread holding the lock exclusively may also take it shared, and a thread that is the only
+/// one holding it shared may also take it exclusively.
+#[derive(Default)]
+struct CfLock {
+    state: Mutex<CfLockState>,
+    released: Condvar,
+}
+
+#[derive(Default)]
+struct CfLockState {
+    /// The thread holding the lock exclusively and the number of exclusive guards it holds.
+    writer: Option<(ThreadId, usize)>,
+    /// The threads holding the lock shared and the numbers of shared guards they hold.
+    readers: HashMap<ThreadId, usize>,
+}
+
+impl CfLock {
+    /// Locks the column family, waiting until no other thread holds it exclusively and, if
+    /// `exclusive`, until no other thread holds it at all.
+    fn lock(self: Arc<Self>, exclusive: bool) -> CfLockGuard {
+        let thread = thread::current().id();
+        let mut state = self.state();
+        loop {
+            let no_other_writer = state.writer.is_none_or(|(owner, _)| owner == thread);
+            if exclusive {
+                if no_other_writer && state.readers.keys().all(|&reader| reader == thread) {
+                    let (_, count) = state.writer.get_or_insert((thread, 0));
+                    *count += 1;
+                    break;
+                }
+            } else if no_other_writer {
+                *state.readers.entry(thread).or_default() += 1;
+                break;
+            }
+            state = self
+                .released
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        drop(state);
+        CfLockGuard {
+            lock: self,
+            exclusive,
+            _not_send: PhantomData,
+        }
+    }
+
+    fn unlock(&self, exclusive: bool) {
+        let thread = thread::current().id();
+        let mut state = self.state();
+        let released = if exclusive {
+            match state.writer.as_mut() {
+                Some((_, count)) => {
+                    *count -= 1;
+                    *count == 0
+                }
+                None => false,
+            }
+        } else {
+            match state.readers.get_mut(&thread) {
+                Some(count) => {
+                    *count -= 1;
+                    *count == 0
+                }
+                None => false,
+            }
+        };
+        if released {
+            if exclusive {
+                state.writer = None;
+            } else {
+                state.readers.remove(&thread);
+            }
+            self.released.notify_all();
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, CfLockState> {
+        // The state is updated before any call that may panic, so a poisoned lock carries no
+        // inconsistent state.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A held lock of a column family, released when dropped.
+struct CfLockGuard {
+    lock: Arc<CfLock>,
+    exclusive: bool,
+    /// Keeps the guard on the thread that holds the reentrant lock.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for CfLockGuard {
+    fn drop(&mut self) {
+        self.lock.unlock(self.exclusive);
+    }
+}
+
+/// Held key locks of column families and stripes, released when dropped.
+struct KeyLockGuard<'a> {
+    // Declared first to release the stripes before the column family locks taken before them.
+    _stripes: Vec<StripeGuard<'a>>,
+    _cfs: Vec<CfLockGuard>,
+}
+
+/// The key locks that writing a batch takes, see [`KeyLocks`].
+#[derive(Debug, Default)]
+struct BatchKeyLocks {
+    /// The written column families, mapped to whether the batch deletes ranges of keys in them.
+    cfs: BTreeMap<String, bool>,
+    /// The stripes of the keys written by point operations.
+    stripes: BTreeSet<usize>,
+}
+
+impl BatchKeyLocks {
+    /// Records a write of a key of column family `cf` guarded by `stripe`.
+    fn record_key(&mut self, cf: &str, stripe: usize) {
+        if !self.cfs.contains_key(cf) {
+            self.cfs.insert(cf.to_owned(), false);
+        }
+        self.stripes.insert(stripe);
+    }
+
+    /// Records a range delete in column family `cf`.
+    fn record_range(&mut self, cf: &str) {
+        self.cfs.insert(cf.to_owned(), true);
+    }
+}
+
+/// The mode in which a database was opened.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AccessMode {
+    /// The database is opened as the primary instance and accepts writes.
+    ReadWrite,
+    /// The database is opened read-only and does not see writes made after it was opened.
+    ReadOnly,
+    /// The database follows a primary instance and can catch up with its writes.
+    Secondary,
+}
+
 /// A generic wrapper around RocksDB engines with common metadata and behavior.
 pub struct DBWrapper<T: DbBehavior> {
     /// The underlying rocksdb database.
@@ -179,6 +564,28 @@ pub struct DBWrapper<T: DbBehavior> {
     pub db_path: PathBuf,
     /// The database options.
     pub db_options: rocksdb::Options,
+    /// The mode in which the database was opened.
+    pub access_mode: AccessMode,
+    /// The locks serializing writes and atomic read-modify-write operations.
+    key_locks: KeyLocks,
+    /// Whether the database was shut down.
+    closed: AtomicBool,
+    /// The settings of the column families that were configured when opening the database.
+    cf_settings: Mutex<HashMap<String, ColumnFamilySettings>>,
+    /// The bulk loads in progress, by column family.
+    bulk_loads: Mutex<HashMap<String, BulkLoadState>>,
+    /// The names of the column families of the open database.
+    cf_names: Mutex<BTreeSet<String>>,
+    /// The locks serializing schema migrations, by column family.
+    schema_migration_locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+}
+
+/// Settings of a column family configured through the `open_cf_with_*` functions.
+#[derive(Debug, Default, Clone)]
+struct ColumnFamilySettings {
+    compression: Option<Compression>,
+    bloom_filter: Option<BloomFilter>,
+    comparator: Option<Comparator>,
 }
 
 impl<T: DbBehavior> fmt::Debug for DBWrapper<T> {
@@ -200,13 +607,27 @@ impl<T: DbBehavior> DBWrapper<T> {
         metric_conf: MetricConf,
         db_path: PathBuf,
         db_options: rocksdb::Options,
+        access_mode: AccessMode,
+        cf_names: impl IntoIterator<Item = String>,
     ) -> Self {
         DBMetrics::get().increment_num_active_dbs(&metric_conf.db_name);
+        // RocksDB always opens the default column family.
+        let cf_names = cf_names
+            .into_iter()
+            .chain([rocksdb::DEFAULT_COLUMN_FAMILY_NAME.to_owned()])
+            .collect();
         Self {
             underlying,
             metric_conf,
             db_path,
             db_options,
+            access_mode,
+            key_locks: KeyLocks::new(),
+            closed: AtomicBool::new(false),
+            cf_settings: Mutex::new(HashMap::new()),
+            bulk_loads: Mutex::new(HashMap::new()),
+            cf_names: Mutex::new(cf_names),
+            schema_migration_locks: Mutex::new(HashMap::new()),
         }
     }
 }
@@ -323,6 +744,171 @@ impl RocksDB {
         delegate_call!(self.db_options)
     }
 
+    /// Returns the current RocksDB statistics of the database, or `None` if statistics are not
+    /// enabled in its options, e.g., with [`DBMap::open_with_statistics`].
+    pub fn statistics_snapshot(&self) -> Option<StatisticsSnapshot> {
+        let options = self.db_options();
+        options.get_statistics()?;
+        Some(StatisticsSnapshot::from_options(options))
+    }
+
+    /// Returns the mode in which the database was opened.
+    pub fn access_mode(&self) -> AccessMode {
+        *delegate_call!(self.access_mode)
+    }
+
+    /// Returns the compression of the column family, if it was opened with
+    /// [`open_cf_with_compression`].
+    ///
+    /// Otherwise, the compression is determined by the options the database was opened with.
+    pub fn compression(&self, cf_name: &str) -> Option<Compression> {
+        self.cf_settings(cf_name).compression
+    }
+
+    /// Returns the bloom filter of the column family, if it was opened with one through
+    /// [`open_cf_with_bloom_filters`].
+    pub fn bloom_filter(&self, cf_name: &str) -> Option<BloomFilter> {
+        self.cf_settings(cf_name).bloom_filter
+    }
+
+    /// Returns the comparator of the column family, if it was opened or created with one through
+    /// [`open_cf_with_comparators`] or [`RocksDB::create_cf_with_comparator`].
+    pub fn comparator(&self, cf_name: &str) -> Option<Comparator> {
+        self.cf_settings(cf_name).comparator
+    }
+
+    fn cf_settings(&self, cf_name: &str) -> ColumnFamilySettings {
+        delegate_call!(self.cf_settings)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(cf_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Records the comparator of the column family.
+    fn record_comparator(&self, cf_name: &str, comparator: &Comparator) {
+        self.record_cf_settings(cf_name, |settings| {
+            settings.comparator = Some(comparator.clone())
+        });
+    }
+
+    /// Updates the recorded settings of the given column family only.
+    fn record_cf_settings(&self, cf_name: &str, update: impl FnOnce(&mut ColumnFamilySettings)) {
+        update(
+            delegate_call!(self.cf_settings)
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry(cf_name.to_string())
+                .or_default(),
+        );
+    }
+
+    /// Returns the lock serializing schema migrations of the column family, see
+    /// [`DBMap::migrate_schema`].
+    fn schema_migration_lock(&self, cf_name: &str) -> Arc<Mutex<()>> {
+        delegate_call!(self.schema_migration_locks)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(cf_name.to_owned())
+            .or_default()
+            .clone()
+    }
+
+    /// Returns the bulk loads in progress, by column family.
+    fn bulk_loads(&self) -> &Mutex<HashMap<String, BulkLoadState>> {
+        delegate_call!(self.bulk_loads)
+    }
+
+    /// Cancels all background flushes and compactions of the database, waiting for running jobs to
+    /// finish if `wait` is set.
+    ///
+    /// Useful to quiesce the database before taking a checkpoint or backup. Background work is not
+    /// resumed afterwards, so no automatic compactions happen for the rest of the database's
+    /// lifetime.
+    pub fn cancel_all_background_work(&self, wait: bool) {
+        delegate_call!(self.cancel_all_background_work(wait))
+    }
+
+    /// Waits until all pending and running background compactions of the database have finished.
+    pub fn wait_for_compactions(&self) -> Result<(), TypedStoreError> {
+        let opts = WaitForCompactOptions::default();
+        delegate_call!(self.wait_for_compact(&opts)).map_err(typed_store_err_from_rocks_err)
+    }
+
+    /// Stops all background work of the database and marks it as closed.
+    ///
+    /// Subsequent operations on the database and its maps fail with
+    /// [`TypedStoreError::DatabaseClosed`]. The files of the database are only released once the
+    /// last handle to it is dropped.
+    pub fn shutdown(&self) {
+        delegate_call!(self.closed).store(true, Ordering::SeqCst);
+        self.cancel_all_background_work(true);
+    }
+
+    /// Flushes the database, stops its background work, and closes it, releasing its directory
+    /// lock before returning.
+    ///
+    /// Unlike dropping the handle, this does not depend on when the last of several handles is
+    /// dropped, so the same path can be opened again right away. Fails with
+    /// [`TypedStoreError::DatabaseInUse`] if other handles to the database, including those held
+    /// by maps, still exist; the database is then left open and only the given handle is dropped.
+    pub fn close(self: Arc<Self>) -> Result<(), TypedStoreError> {
+        let db = Arc::try_unwrap(self)
+            .map_err(|db| TypedStoreError::DatabaseInUse(Arc::strong_count(&db) - 1))?;
+        if db.access_mode() == AccessMode::ReadWrite && !db.is_closed() {
+            db.flush_all()?;
+        }
+        db.shutdown();
+        // Dropping the last handle closes the database and releases its lock.
+        drop(db);
+        Ok(())
+    }
+
+    /// Returns true if the database was shut down.
+    pub fn is_closed(&self) -> bool {
+        delegate_call!(self.closed).load(Ordering::SeqCst)
+    }
+
+    /// Returns an error if the database was shut down.
+    fn ensure_open(&self) -> Result<(), TypedStoreError> {
+        if self.is_closed() {
+            Err(TypedStoreError::DatabaseClosed)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns an error if the database was not opened for writing.
+    fn ensure_writable(&self) -> Result<(), TypedStoreError> {
+        self.ensure_open()?;
+        match self.access_mode() {
+            AccessMode::ReadWrite => Ok(()),
+            AccessMode::ReadOnly | AccessMode::Secondary => Err(TypedStoreError::ReadOnlyDatabase),
+        }
+    }
+
+    /// Locks `key` in column family `cf` against other writes and atomic read-modify-write
+    /// operations of the key.
+    fn lock_key(&self, cf: &str, key: &[u8]) -> KeyLockGuard<'_> {
+        delegate_call!(self.key_locks).lock(cf, key)
+    }
+
+    /// Locks all `keys` in column family `cf`, see [`Self::lock_key`].
+    fn lock_keys(&self, cf: &str, keys: &[Vec<u8>]) -> KeyLockGuard<'_> {
+        delegate_call!(self.key_locks).lock_all(cf, keys)
+    }
+
+    /// Returns the key lock stripe guarding `key` in column family `cf`.
+    fn key_stripe(&self, cf: &str, key: &[u8]) -> usize {
+        delegate_call!(self.key_locks).stripe(cf, key)
+    }
+
+    /// Locks the keys a batch writes, see [`Self::lock_key`].
+    fn lock_batch_keys(&self, key_locks: &BatchKeyLocks) -> KeyLockGuard<'_> {
+        delegate_call!(self.key_locks).lock_batch(key_locks)
+    }
+
     /// Get a value from the database.
     pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Vec<u8>>, rocksdb::Error> {
         delegate_call!(self.get(key))
@@ -366,6 +952,22 @@ impl RocksDB {
         delegate_call!(self.property_int_value_cf(cf, name))
     }
 
+    /// Get the approximate on-disk size of the keys in `[start, end)` of a column family.
+    ///
+    /// Only data in SST files is accounted for; memtables are ignored.
+    pub fn approximate_size_cf(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        start: &[u8],
+        end: &[u8],
+    ) -> u64 {
+        let ranges: &[rocksdb::Range<'_>] = &[rocksdb::Range::new(start, end)];
+        delegate_call!(self.get_approximate_sizes_cf(cf, ranges))
+            .first()
+            .copied()
+            .unwrap_or_default()
+    }
+
     /// Get a pinned value from a specific column family.
     pub fn get_pinned_cf_opt<K: AsRef<[u8]>>(
         &self,
@@ -376,27 +978,140 @@ impl RocksDB {
         delegate_call!(self.get_pinned_cf_opt(cf, key, readopts))
     }
 
+    /// Lists the names of the column families of the database at `path`, which does not need to
+    /// be open.
+    pub fn list_column_families<P: AsRef<Path>>(path: P) -> Result<Vec<String>, TypedStoreError> {
+        rocksdb::DBWithThreadMode::<MultiThreaded>::list_cf(&rocksdb::Options::default(), path)
+            .map_err(typed_store_err_from_rocks_err)
+    }
+
+    /// Verifies the checksums of the data of all column families.
+    ///
+    /// See [`DBMap::verify_checksum`].
+    pub fn verify_all(&self) -> Result<(), TypedStoreError> {
+        for name in self.cf_names() {
+            if let Some(cf) = self.cf_handle(&name) {
+                self.verify_checksum_cf(&cf)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads all entries of the column family, verifying the checksum of every block read.
+    fn verify_checksum_cf(&self, cf: &impl AsColumnFamilyRef) -> Result<(), TypedStoreError> {
+        self.ensure_open()?;
+        let mut readopts = ReadOptions::default();
+        readopts.set_verify_checksums(true);
+        readopts.fill_cache(false);
+        readopts.set_total_order_seek(true);
+        let mut iter = self.raw_iterator_cf(cf, readopts);
+        iter.seek_to_first();
+        while iter.valid() {
+            iter.next();
+        }
+        iter.status().map_err(typed_store_err_from_rocks_err)
+    }
+
+    /// Repairs the database at `path`, which must not be open, so that it can be opened again.
+    ///
+    /// This is a last resort for databases that fail to open, e.g., because of a corrupt
+    /// MANIFEST, and is meant to be run before [`open_cf`]. The repair rebuilds the MANIFEST from
+    /// the SST files it finds and may lose data: unreadable files and unflushed writes that cannot
+    /// be recovered from the WAL are dropped, and deleted entries may reappear. Back up the
+    /// directory before running it. Fails if any of `cf_names` is missing from the repaired
+    /// database.
+    pub fn repair<P: AsRef<Path>>(path: P, cf_names: &[&str]) -> Result<(), TypedStoreError> {
+        let path = path.as_ref();
+        rocksdb::DBWithThreadMode::<MultiThreaded>::repair(&default_db_options().options, path)
+            .map_err(typed_store_err_from_rocks_err)?;
+        let recovered = Self::list_column_families(path)?;
+        match cf_names
+            .iter()
+            .find(|name| !recovered.iter().any(|cf| cf == **name))
+        {
+            Some(name) => Err(TypedStoreError::RocksDBError(format!(
+                "column family {name} was not recovered by the repair"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns true if the column family is registered with the open database.
+    pub fn cf_exists(&self, name: &str) -> bool {
+        self.cf_handle(name).is_some()
+    }
+
+    /// Returns the names of the column families of the open database, including those created and
+    /// excluding those dropped since it was opened.
+    ///
+    /// Unlike [`RocksDB::list_column_families`], this does not read the database's MANIFEST.
+    pub fn cf_names(&self) -> Vec<String> {
+        self.cf_name_set().iter().cloned().collect()
+    }
+
+    fn cf_name_set(&self) -> MutexGuard<'_, BTreeSet<String>> {
+        delegate_call!(self.cf_names)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
     /// Get a column family handle by name.
     pub fn cf_handle(&self, name: &str) -> Option<Arc<rocksdb::BoundColumnFamily<'_>>> {
         delegate_call!(self.cf_handle(name))
     }
 
     /// Create a new column family.
+    ///
+    /// The column family can be attached to a map with [`DBMap::reopen`] right away; it is
+    /// registered in the database's manifest and is opened along with all other existing column
+    /// families the next time the database is opened. `opts` are the options of the new column
+    /// family only. Fails with [`TypedStoreError::ReadOnlyDatabase`] if the database was not opened
+    /// for writing.
     pub fn create_cf<N: AsRef<str>>(
         &self,
         name: N,
         opts: &rocksdb::Options,
-    ) -> Result<(), rocksdb::Error> {
-        delegate_call!(self.create_cf(name, opts))
+    ) -> Result<(), TypedStoreError> {
+        let name = name.as_ref();
+        self.ensure_writable()?;
+        delegate_call!(self.create_cf(name, opts)).map_err(typed_store_err_from_rocks_err)?;
+        self.cf_name_set().insert(name.to_owned());
+        Ok(())
+    }
+
+    /// Creates a new column family like [`RocksDB::create_cf`], ordering its keys with the given
+    /// comparator.
+    ///
+    /// **The column family must be opened with the same comparator whenever the database is
+    /// reopened**, see [`Comparator`].
+    pub fn create_cf_with_comparator<N: AsRef<str>>(
+        &self,
+        name: N,
+        opts: &rocksdb::Options,
+        comparator: &Comparator,
+    ) -> Result<(), TypedStoreError> {
+        let name = name.as_ref();
+        let mut opts = opts.clone();
+        comparator.apply(&mut opts);
+        self.create_cf(name, &opts)?;
+        self.record_comparator(name, comparator);
+        Ok(())
     }
 
-    /// Drop a column family.
-    pub fn drop_cf(&self, name: &str) -> Result<(), rocksdb::Error> {
-        delegate_call!(self.drop_cf(name))
+    /// Drop a column family and all of its data.
+    ///
+    /// Afterwards, [`DBMap::reopen`] of the column family fails with
+    /// [`TypedStoreError::UnregisteredColumn`], as do operations on maps still attached to it.
+    /// Fails with [`TypedStoreError::ReadOnlyDatabase`] if the database was not opened for
+    /// writing.
+    pub fn drop_cf(&self, name: &str) -> Result<(), TypedStoreError> {
+        self.ensure_writable()?;
+        delegate_call!(self.drop_cf(name)).map_err(typed_store_err_from_rocks_err)?;
+        self.cf_name_set().remove(name);
+        Ok(())
     }
 
     /// Delete files in a range.
-    #[allow(dead_code)]
     pub fn delete_file_in_range<K: AsRef<[u8]>>(
         &self,
         cf: &impl AsColumnFamilyRef,
@@ -444,6 +1159,21 @@ impl RocksDB {
         ret
     }
 
+    /// Merge a value into a specific column family using its merge operator.
+    pub fn merge_cf<K, V>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        key: K,
+        value: V,
+        writeopts: &WriteOptions,
+    ) -> Result<(), rocksdb::Error>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        delegate_call!(self.merge_cf_opt(cf, key, value, writeopts))
+    }
+
     /// Check if a key may exist in a specific column family.
     pub fn key_may_exist_cf<K: AsRef<[u8]>>(
         &self,
@@ -465,6 +1195,7 @@ impl RocksDB {
         batch: RocksDBBatch,
         writeopts: &WriteOptions,
     ) -> Result<(), TypedStoreError> {
+        self.ensure_writable()?;
         sui_macros::fail_point!("batch-write-before");
         delegate_pair!(
             self,
@@ -527,14 +1258,77 @@ impl RocksDB {
         delegate_call!(self.flush()).map_err(|e| TypedStoreError::RocksDBError(e.into_string()))
     }
 
+    /// Flush the memtables of all column families to SST files.
+    pub fn flush_all(&self) -> Result<(), TypedStoreError> {
+        for name in self.cf_names() {
+            if let Some(cf) = self.cf_handle(&name) {
+                self.flush_cf(&cf).map_err(typed_store_err_from_rocks_err)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Create a checkpoint of the database.
+    ///
+    /// Fails with [`TypedStoreError::CheckpointExists`] if `path` already exists.
     pub fn checkpoint(&self, path: &Path) -> Result<(), TypedStoreError> {
+        self.checkpoint_with_options(path, false)
+    }
+
+    /// Create a checkpoint of the database, replacing an existing one at `path` if `overwrite` is
+    /// set.
+    ///
+    /// Without `overwrite`, an existing `path` results in [`TypedStoreError::CheckpointExists`].
+    /// With `overwrite`, the existing directory is only removed if it is empty or holds a RocksDB
+    /// checkpoint that was never opened as a database, e.g., one left behind by an earlier run;
+    /// anything else, including the directory of any opened database, results in
+    /// [`TypedStoreError::InvalidCheckpointDestination`] and is left untouched.
+    pub fn checkpoint_with_options(
+        &self,
+        path: &Path,
+        overwrite: bool,
+    ) -> Result<(), TypedStoreError> {
+        if path.exists() {
+            if !overwrite {
+                return Err(TypedStoreError::CheckpointExists(
+                    path.display().to_string(),
+                ));
+            }
+            self.remove_existing_checkpoint(path)?;
+        }
         let checkpoint = self.new_checkpoint()?;
         checkpoint
             .create_checkpoint(path)
             .map_err(|e| TypedStoreError::RocksDBError(e.to_string()))
     }
 
+    /// Removes the directory at `path` if it can safely be replaced by a new checkpoint.
+    fn remove_existing_checkpoint(&self, path: &Path) -> Result<(), TypedStoreError> {
+        let invalid = |reason: &str| {
+            TypedStoreError::InvalidCheckpointDestination(format!("{}: {reason}", path.display()))
+        };
+        let io_err = |e: std::io::Error| TypedStoreError::RocksDBError(e.to_string());
+
+        if !fs::symlink_metadata(path).map_err(io_err)?.is_dir() {
+            return Err(invalid("not a directory"));
+        }
+        let db_path = fs::canonicalize(self.path()).map_err(io_err)?;
+        if fs::canonicalize(path).map_err(io_err)? == db_path {
+            return Err(invalid("it is the directory of the database itself"));
+        }
+        let is_empty = fs::read_dir(path).map_err(io_err)?.next().is_none();
+        if !is_empty && !path.join("CURRENT").is_file() {
+            return Err(invalid("not a RocksDB checkpoint"));
+        }
+        // Checkpoints do not contain a lock file, which RocksDB creates when opening a database.
+        if path.join("LOCK").exists() {
+            return Err(invalid("it holds a database that was opened"));
+        }
+
+        tracing::info!(?path, "removing existing checkpoint directory");
+        fs::remove_dir_all(path).map_err(io_err)
+    }
+
     /// Create a new backup of the database.
     pub fn create_new_backup_flush(
         &self,
@@ -556,12 +1350,33 @@ impl RocksDB {
         delegate_call!(self.flush_cf(cf))
     }
 
-    /// Set options for a specific column family.
-    #[allow(dead_code)]
-    pub fn set_options_cf(
+    /// Ingest external SST files into a specific column family.
+    ///
+    /// Only the standard RocksDB engine supports ingesting external files.
+    pub fn ingest_external_file_cf(
         &self,
         cf: &impl AsColumnFamilyRef,
-        opts: &[(&str, &str)],
+        opts: &IngestExternalFileOptions,
+        files: Vec<&Path>,
+    ) -> Result<(), TypedStoreError> {
+        self.ensure_writable()?;
+        match self {
+            Self::DB(d) => d
+                .underlying
+                .ingest_external_file_cf_opts(cf, opts, files)
+                .map_err(typed_store_err_from_rocks_err),
+            Self::OptimisticTransactionDB(_) => Err(TypedStoreError::RocksDBError(
+                "ingesting external SST files requires the standard RocksDB engine".into(),
+            )),
+        }
+    }
+
+    /// Set options for a specific column family.
+    #[allow(dead_code)]
+    pub fn set_options_cf(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        opts: &[(&str, &str)],
     ) -> Result<(), rocksdb::Error> {
         delegate_call!(self.set_options_cf(cf, opts))
     }
@@ -594,6 +1409,45 @@ impl RocksDB {
             .new_from_self()
     }
 
+    /// Reports the operation to the slow operation callback if it took longer than the threshold
+    /// of the metric configuration.
+    fn report_if_slow(
+        &self,
+        op: &'static str,
+        cf_name: Option<&str>,
+        key_bytes: usize,
+        elapsed: Duration,
+    ) {
+        let metric_conf = delegate_call!(self.metric_conf);
+        if let (Some(threshold), Some(callback)) =
+            (metric_conf.slow_op_threshold, &metric_conf.slow_op_callback)
+            && elapsed > threshold
+        {
+            callback(SlowOpEvent {
+                op,
+                cf_name: cf_name.map(str::to_string),
+                key_bytes,
+                elapsed,
+            });
+        }
+    }
+
+    /// Returns whether point writes are verified by reading them back, see
+    /// [`MetricConf::with_read_after_write_verify`].
+    fn verifies_writes(&self) -> bool {
+        cfg!(debug_assertions) && delegate_call!(self.metric_conf).read_after_write_verify
+    }
+
+    /// Starts reporting events to the event callback of the metric configuration, if any.
+    fn start_event_listener(self: &Arc<Self>) {
+        let metric_conf = delegate_call!(self.metric_conf);
+        if let (Some(poll_interval), Some(callback)) =
+            (metric_conf.event_poll_interval, &metric_conf.event_callback)
+        {
+            events::spawn_event_listener(self, poll_interval, callback.clone());
+        }
+    }
+
     /// Get the name of the database.
     pub fn db_name(&self) -> String {
         let name = delegate_call!(self.metric_conf).db_name.clone();
@@ -619,6 +1473,17 @@ impl RocksDB {
         delegate_call!(self.live_files())
     }
 
+    /// Takes a snapshot of the database, shared by all of its column families.
+    pub fn snapshot(&self) -> DbSnapshot<'_> {
+        let inner = match self {
+            RocksDB::DB(d) => SnapshotInner::DB(d.underlying.snapshot()),
+            RocksDB::OptimisticTransactionDB(d) => {
+                SnapshotInner::OptimisticTransactionDB(d.underlying.snapshot())
+            }
+        };
+        DbSnapshot { db: self, inner }
+    }
+
     /// Create a new batch for the database.
     pub fn make_batch(&self) -> RocksDBBatch {
         match self {
@@ -641,6 +1506,88 @@ impl RocksDB {
     }
 }
 
+/// A consistent point-in-time view of a database, obtained through [`RocksDB::snapshot`].
+///
+/// All reads through the same snapshot observe the database at a single sequence number, across
+/// all column families, regardless of concurrent writes.
+pub struct DbSnapshot<'a> {
+    db: &'a RocksDB,
+    inner: SnapshotInner<'a>,
+}
+
+enum SnapshotInner<'a> {
+    DB(rocksdb::SnapshotWithThreadMode<'a, rocksdb::DBWithThreadMode<MultiThreaded>>),
+    OptimisticTransactionDB(
+        rocksdb::SnapshotWithThreadMode<'a, rocksdb::OptimisticTransactionDB<MultiThreaded>>,
+    ),
+}
+
+impl fmt::Debug for DbSnapshot<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DbSnapshot {{ db: {:?} }}", self.db.path())
+    }
+}
+
+impl DbSnapshot<'_> {
+    /// Makes reads with `readopts` go through the snapshot, which must belong to `db`.
+    fn set_on(&self, db: &RocksDB, readopts: &mut ReadOptions) -> Result<(), TypedStoreError> {
+        if !std::ptr::eq(self.db, db) {
+            return Err(TypedStoreError::RocksDBError(
+                "the snapshot was taken on a different database".into(),
+            ));
+        }
+        match &self.inner {
+            SnapshotInner::DB(snapshot) => readopts.set_snapshot(snapshot),
+            SnapshotInner::OptimisticTransactionDB(snapshot) => readopts.set_snapshot(snapshot),
+        }
+        Ok(())
+    }
+}
+
+/// A serialized value of type `V` pinned in RocksDB's memory, see [`DBMap::get_pinned_value`].
+pub struct PinnedValue<'a, V> {
+    slice: DBPinnableSlice<'a>,
+    /// The custom value codec of the map, if any.
+    value_codec: Option<Arc<dyn Codec<V>>>,
+}
+
+impl<V> fmt::Debug for PinnedValue<'_, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PinnedValue {{ len: {} }}", self.slice.len())
+    }
+}
+
+impl<V> PinnedValue<'_, V> {
+    /// Returns the serialized value.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.slice
+    }
+
+    /// Deserializes the value.
+    pub fn deserialize(&self) -> Result<V, TypedStoreError>
+    where
+        V: DeserializeOwned,
+    {
+        match &self.value_codec {
+            Some(codec) => codec.decode(&self.slice),
+            None => bcs::from_bytes(&self.slice).map_err(typed_store_err_from_bcs_err),
+        }
+    }
+
+    /// Deserializes the value into a type with the same serialized form as `V` that borrows from
+    /// the pinned memory, e.g., `&[u8]` for a `Vec<u8>`, avoiding any copy of the data.
+    ///
+    /// Only supported for maps with the default value codec.
+    pub fn deserialize_borrowed<'b, T: Deserialize<'b>>(&'b self) -> Result<T, TypedStoreError> {
+        if self.value_codec.is_some() {
+            return Err(TypedStoreError::SerializationError(
+                "borrowed deserialization requires the default value codec".into(),
+            ));
+        }
+        bcs::from_bytes(&self.slice).map_err(typed_store_err_from_bcs_err)
+    }
+}
+
 /// A batch of write operations for RocksDB, covering both standard and optimistic transaction DBs.
 pub enum RocksDBBatch {
     /// A write batch for a standard `rocksdb::DB`.
@@ -672,6 +1619,40 @@ impl RocksDBBatch {
         delegate_batch_call!(self.size_in_bytes())
     }
 
+    fn len(&self) -> usize {
+        delegate_batch_call!(self.len())
+    }
+
+    fn is_empty(&self) -> bool {
+        delegate_batch_call!(self.is_empty())
+    }
+
+    fn clear(&mut self) {
+        delegate_batch_call!(self.clear())
+    }
+
+    /// Returns an empty batch for the same kind of database.
+    fn empty(&self) -> Self {
+        match self {
+            Self::DB(_) => Self::DB(WriteBatch::default()),
+            Self::OptimisticTransactionDB(_) => {
+                Self::OptimisticTransactionDB(WriteBatchWithTransaction::<true>::default())
+            }
+        }
+    }
+
+    fn set_save_point(&mut self) {
+        delegate_batch_call!(self.set_save_point())
+    }
+
+    fn rollback_to_save_point(&mut self) -> Result<(), rocksdb::Error> {
+        delegate_batch_call!(self.rollback_to_save_point())
+    }
+
+    fn pop_save_point(&mut self) -> Result<(), rocksdb::Error> {
+        delegate_batch_call!(self.pop_save_point())
+    }
+
     /// Delete a key from the given column family within this batch.
     pub fn delete_cf<K: AsRef<[u8]>>(&mut self, cf: &impl AsColumnFamilyRef, key: K) {
         delegate_batch_call!(self.delete_cf(cf, key))
@@ -718,8 +1699,32 @@ impl RocksDBBatch {
     }
 }
 
+/// An operation that took longer than the slow operation threshold of its [`MetricConf`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowOpEvent {
+    /// The name of the operation, e.g., `get` or `batch_write`.
+    pub op: &'static str,
+    /// The column family the operation accessed, or `None` for batch writes, which may span
+    /// several column families.
+    pub cf_name: Option<String>,
+    /// The size of the serialized key, or of the whole batch for batch writes, in bytes.
+    pub key_bytes: usize,
+    /// The time the operation took.
+    pub elapsed: Duration,
+}
+
+/// A callback invoked with every operation slower than the threshold of a [`MetricConf`].
+pub type SlowOpCallback = Arc<dyn Fn(SlowOpEvent) + Send + Sync>;
+
+/// A single entry of a map in the newline-delimited JSON format of [`DBMap::export_ndjson`].
+#[derive(Serialize, Deserialize)]
+struct NdjsonRecord<K, V> {
+    key: K,
+    value: V,
+}
+
 /// A configuration for metrics.
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct MetricConf {
     /// The name of the database.
     pub db_name: String,
@@ -729,6 +1734,31 @@ pub struct MetricConf {
     pub write_sample_interval: SamplingInterval,
     /// The sampling interval for iterator operations.
     pub iter_sample_interval: SamplingInterval,
+    /// The duration above which gets, inserts, and batch writes are reported to the
+    /// `slow_op_callback`.
+    pub slow_op_threshold: Option<Duration>,
+    /// The callback invoked with operations slower than the `slow_op_threshold`.
+    pub slow_op_callback: Option<SlowOpCallback>,
+    /// The interval at which the database is polled for events for the `event_callback`.
+    pub event_poll_interval: Option<Duration>,
+    /// The callback invoked with write stalls, flushes, and background errors.
+    pub event_callback: Option<DbEventCallback>,
+    /// Whether every point write is read back and verified, in debug builds only.
+    pub read_after_write_verify: bool,
+}
+
+impl fmt::Debug for MetricConf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetricConf")
+            .field("db_name", &self.db_name)
+            .field("read_sample_interval", &self.read_sample_interval)
+            .field("write_sample_interval", &self.write_sample_interval)
+            .field("iter_sample_interval", &self.iter_sample_interval)
+            .field("slow_op_threshold", &self.slow_op_threshold)
+            .field("event_poll_interval", &self.event_poll_interval)
+            .field("read_after_write_verify", &self.read_after_write_verify)
+            .finish_non_exhaustive()
+    }
 }
 
 /// A configuration for metrics.
@@ -743,16 +1773,64 @@ impl MetricConf {
             read_sample_interval: SamplingInterval::default(),
             write_sample_interval: SamplingInterval::default(),
             iter_sample_interval: SamplingInterval::default(),
+            slow_op_threshold: None,
+            slow_op_callback: None,
+            event_poll_interval: None,
+            event_callback: None,
+            read_after_write_verify: false,
         }
     }
 
     /// Set the sampling interval for the database.
     pub fn with_sampling(self, read_interval: SamplingInterval) -> Self {
         Self {
-            db_name: self.db_name,
             read_sample_interval: read_interval,
             write_sample_interval: SamplingInterval::default(),
             iter_sample_interval: SamplingInterval::default(),
+            ..self
+        }
+    }
+
+    /// Report gets, inserts, and batch writes taking longer than `threshold` to `callback`.
+    ///
+    /// The callback runs synchronously on the thread of the slow operation, so it should return
+    /// quickly, e.g., by logging the event or sending it to a channel.
+    pub fn with_slow_op_callback(self, threshold: Duration, callback: SlowOpCallback) -> Self {
+        Self {
+            slow_op_threshold: Some(threshold),
+            slow_op_callback: Some(callback),
+            ..self
+        }
+    }
+
+    /// Report write stalls, completed flushes, and background errors of the database to
+    /// `callback`, see [`DbEvent`].
+    ///
+    /// The database is polled every `poll_interval` on the tokio runtime it is opened in, so
+    /// events are delayed by up to that interval. The callback runs on a runtime thread, so it
+    /// should return quickly. Without a runtime, no events are reported.
+    pub fn with_event_callback(self, poll_interval: Duration, callback: DbEventCallback) -> Self {
+        Self {
+            event_poll_interval: Some(poll_interval),
+            event_callback: Some(callback),
+            ..self
+        }
+    }
+
+    /// Read back every insert and remove of a single key right after writing it, failing with
+    /// [`TypedStoreError::VerifyFailed`] if the stored value does not match what was written.
+    ///
+    /// This is meant for debugging tests, e.g., to catch codec bugs or lost writes early. It
+    /// doubles the cost of point writes and has no effect in release builds. Batches are not
+    /// verified. The value is read back from the column family it was written to, so a write to
+    /// the wrong column family goes unnoticed. The key stays locked until it is read back, but
+    /// writes that bypass the key locks, e.g., through the raw [`RocksDB`] handle or from another
+    /// process, can still change it in between and cause a spurious
+    /// [`TypedStoreError::VerifyFailed`].
+    pub fn with_read_after_write_verify(self) -> Self {
+        Self {
+            read_after_write_verify: true,
+            ..self
         }
     }
 }
@@ -760,7 +1838,7 @@ const CF_METRICS_REPORT_PERIOD_SECS: u64 = 30;
 const METRICS_ERROR: i64 = -1;
 
 /// An interface to a rocksDB database, keyed by a columnfamily.
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct DBMap<K, V> {
     /// The rocksDB database.
     pub rocksdb: Arc<RocksDB>,
@@ -782,10 +1860,30 @@ pub struct DBMap<K, V> {
     iter_sample_interval: SamplingInterval,
     /// The cancel handle for the metrics task.
     _metrics_task_cancel_handle: Arc<oneshot::Sender<()>>,
+    /// The encodings of the keys and values.
+    codec: MapCodec<K, V>,
 }
 
 unsafe impl<K: Send, V: Send> Send for DBMap<K, V> {}
 
+impl<K, V> Clone for DBMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            rocksdb: self.rocksdb.clone(),
+            _phantom: PhantomData,
+            cf: self.cf.clone(),
+            opts: self.opts.clone(),
+            db_metrics: self.db_metrics.clone(),
+            get_sample_interval: self.get_sample_interval.clone(),
+            multiget_sample_interval: self.multiget_sample_interval.clone(),
+            write_sample_interval: self.write_sample_interval.clone(),
+            iter_sample_interval: self.iter_sample_interval.clone(),
+            _metrics_task_cancel_handle: self._metrics_task_cancel_handle.clone(),
+            codec: self.codec.clone(),
+        }
+    }
+}
+
 impl<K, V> DBMap<K, V> {
     pub(crate) fn new(
         db: Arc<RocksDB>,
@@ -793,7 +1891,8 @@ impl<K, V> DBMap<K, V> {
         opt_cf: &str,
         is_deprecated: bool,
     ) -> Self {
-        let db_cloned = db.clone();
+        // The task only holds a weak handle, so that it does not keep the database open.
+        let db_weak = Arc::downgrade(&db);
         let db_metrics = DBMetrics::get();
         let db_metrics_cloned = db_metrics.clone();
         let cf = opt_cf.to_string();
@@ -805,7 +1904,9 @@ impl<K, V> DBMap<K, V> {
                 loop {
                     tokio::select! {
                         _ = interval.tick() => {
-                            let db = db_cloned.clone();
+                            let Some(db) = db_weak.upgrade() else {
+                                break;
+                            };
                             let cf = cf.clone();
                             let db_metrics = db_metrics.clone();
                             if let Err(error) = tokio::task::spawn_blocking(move || {
@@ -831,6 +1932,7 @@ impl<K, V> DBMap<K, V> {
             multiget_sample_interval: db.multiget_sampling_interval(),
             write_sample_interval: db.write_sampling_interval(),
             iter_sample_interval: db.iter_sampling_interval(),
+            codec: MapCodec::default(),
         }
     }
 
@@ -858,6 +1960,286 @@ impl<K, V> DBMap<K, V> {
         Ok(DBMap::new(rocksdb, rw_options, cf_key, false))
     }
 
+    /// Opens a database like [`DBMap::open`], applying the given bottommost-level compression
+    /// settings to the column family only.
+    ///
+    /// Compressing the bottommost level with Zstd and a trained dictionary trades compaction CPU
+    /// for a smaller on-disk footprint of cold data. Dictionary training samples up to
+    /// `zstd_max_train_bytes` of data per bottommost compaction output, so compactions into the
+    /// last level become noticeably more CPU-intensive; reads are only marginally affected.
+    pub fn open_with_bottommost_compression<P: AsRef<Path>>(
+        path: P,
+        metric_conf: MetricConf,
+        db_options: Option<rocksdb::Options>,
+        opt_cf: Option<&str>,
+        rw_options: &ReadWriteOptions,
+        bottommost_compression: &BottommostCompressionConfig,
+    ) -> Result<Self, TypedStoreError> {
+        let cf_key = opt_cf.unwrap_or(rocksdb::DEFAULT_COLUMN_FAMILY_NAME);
+        let options = db_options.unwrap_or_else(|| default_db_options().options);
+        let mut cf_options = options.clone();
+        bottommost_compression.apply(&mut cf_options);
+        let rocksdb = open_cf_opts(path, Some(options), metric_conf, &[(cf_key, cf_options)])?;
+        Ok(DBMap::new(rocksdb, rw_options, cf_key, false))
+    }
+
+    /// Opens a database like [`DBMap::open`], installing the given merge operator on the column
+    /// family so that [`DBMap::merge`] can be used.
+    ///
+    /// The same merge operator must be installed whenever the database is opened again.
+    pub fn open_with_merge<P: AsRef<Path>>(
+        path: P,
+        metric_conf: MetricConf,
+        db_options: Option<rocksdb::Options>,
+        opt_cf: Option<&str>,
+        rw_options: &ReadWriteOptions,
+        merge_operator: MergeOperator<V>,
+    ) -> Result<Self, TypedStoreError> {
+        let mut options = db_options.unwrap_or_else(|| default_db_options().options);
+        merge_operator.apply(&mut options);
+        Self::open(path, metric_conf, Some(options), opt_cf, rw_options)
+    }
+
+    /// Opens a database like [`DBMap::open`], collecting RocksDB statistics at the given level.
+    ///
+    /// The statistics are reported by [`RocksDB::statistics_snapshot`]. Collecting them has a small
+    /// overhead on every operation, which is larger for levels that include timers.
+    pub fn open_with_statistics<P: AsRef<Path>>(
+        path: P,
+        metric_conf: MetricConf,
+        db_options: Option<rocksdb::Options>,
+        opt_cf: Option<&str>,
+        rw_options: &ReadWriteOptions,
+        stats_level: StatsLevel,
+    ) -> Result<Self, TypedStoreError> {
+        let mut options = db_options.unwrap_or_else(|| default_db_options().options);
+        options.enable_statistics();
+        options.set_statistics_level(stats_level);
+        Self::open(path, metric_conf, Some(options), opt_cf, rw_options)
+    }
+
+    /// Opens a database like [`DBMap::open`], with the given integrity checks, see
+    /// [`open_cf_with_integrity_checks`].
+    pub fn open_with_integrity_checks<P: AsRef<Path>>(
+        path: P,
+        metric_conf: MetricConf,
+        db_options: Option<rocksdb::Options>,
+        opt_cf: Option<&str>,
+        rw_options: &ReadWriteOptions,
+        integrity_checks: IntegrityChecks,
+    ) -> Result<Self, TypedStoreError> {
+        let cf_key = opt_cf.unwrap_or(rocksdb::DEFAULT_COLUMN_FAMILY_NAME);
+        let rocksdb = open_cf_with_integrity_checks(
+            path,
+            db_options,
+            metric_conf,
+            &[cf_key],
+            integrity_checks,
+        )?;
+        Ok(DBMap::new(rocksdb, rw_options, cf_key, false))
+    }
+
+    /// Opens a database like [`DBMap::open`] with the options of the given rate limiter, throttling
+    /// flushes and compactions with it.
+    ///
+    /// Databases opened with a limiter from [`RateLimiter::new`] also share its block cache. To
+    /// combine the limiter with other options, create it with [`RateLimiter::with_db_options`], or
+    /// modify [`RateLimiter::db_options`] and pass them to [`DBMap::open`].
+    pub fn open_with_rate_limiter<P: AsRef<Path>>(
+        path: P,
+        metric_conf: MetricConf,
+        opt_cf: Option<&str>,
+        rw_options: &ReadWriteOptions,
+        rate_limiter: &RateLimiter,
+    ) -> Result<Self, TypedStoreError> {
+        Self::open(
+            path,
+            metric_conf,
+            Some(rate_limiter.db_options()),
+            opt_cf,
+            rw_options,
+        )
+    }
+
+    /// Opens a database like [`DBMap::open`], encoding keys and values with the given codecs
+    /// instead of the default big-endian bincode and BCS encodings.
+    ///
+    /// The same codecs must be used whenever the database is opened again. Merge operators and
+    /// [`DBMap::append`] operate on BCS-encoded values and require the default value codec.
+    pub fn open_with_codec<P: AsRef<Path>>(
+        path: P,
+        metric_conf: MetricConf,
+        db_options: Option<rocksdb::Options>,
+        opt_cf: Option<&str>,
+        rw_options: &ReadWriteOptions,
+        key_codec: Arc<dyn Codec<K>>,
+        value_codec: Arc<dyn Codec<V>>,
+    ) -> Result<Self, TypedStoreError> {
+        let mut map = Self::open(path, metric_conf, db_options, opt_cf, rw_options)?;
+        map.codec = MapCodec::new(key_codec, value_codec);
+        Ok(map)
+    }
+
+    /// Opens a database like [`DBMap::open`] and brings the schema of the map's values to
+    /// `expected_version`, see [`DBMap::migrate_schema`].
+    ///
+    /// The schema version is stored in the [`METADATA_CF_NAME`] column family, which is created
+    /// if it does not exist.
+    pub fn open_with_migration<P: AsRef<Path>>(
+        path: P,
+        metric_conf: MetricConf,
+        db_options: Option<rocksdb::Options>,
+        opt_cf: Option<&str>,
+        rw_options: &ReadWriteOptions,
+        expected_version: u64,
+        migrate: impl FnOnce(&Self, &mut DBBatch) -> Result<(), TypedStoreError>,
+    ) -> Result<Self, TypedStoreError> {
+        let cf_key = opt_cf.unwrap_or(rocksdb::DEFAULT_COLUMN_FAMILY_NAME);
+        let rocksdb = open_cf(path, db_options, metric_conf, &[cf_key, METADATA_CF_NAME])?;
+        let map = DBMap::new(rocksdb, rw_options, cf_key, false);
+        map.migrate_schema(expected_version, migrate)?;
+        Ok(map)
+    }
+
+    /// Returns the schema version of the map's values stored in the metadata column family, or 0
+    /// if none is stored.
+    ///
+    /// Fails if the database was not opened with the [`METADATA_CF_NAME`] column family.
+    pub fn schema_version(&self) -> Result<u64, TypedStoreError> {
+        let metadata_cf = self.metadata_cf()?;
+        let stored = self
+            .rocksdb
+            .get_pinned_cf_opt(
+                &metadata_cf,
+                self.schema_version_key(),
+                &ReadOptions::default(),
+            )
+            .map_err(typed_store_err_from_rocks_err)?;
+        stored.map_or(Ok(0), |bytes| {
+            bcs::from_bytes(&bytes).map_err(typed_store_err_from_bcs_err)
+        })
+    }
+
+    /// Runs `migrate` and then stores `expected_version` as the schema version if the stored
+    /// version is older, see [`DBMap::schema_version`].
+    ///
+    /// The migration rewrites the entries from the stored schema version, which it can read with
+    /// [`DBMap::schema_version`], to `expected_version` by adding its writes to the given batch.
+    /// The batch is written together with the new version, so either both or neither are stored;
+    /// writes the migration makes directly through the map are not covered by this. If it fails,
+    /// nothing in the batch is written and the migration runs again on the next call. Migrations
+    /// of the same map within the process are serialized, so of concurrent calls only the first
+    /// one migrates; other processes cannot open the database concurrently. Fails with
+    /// [`TypedStoreError::SchemaVersionTooNew`] if the stored version is newer than
+    /// `expected_version`.
+    pub fn migrate_schema(
+        &self,
+        expected_version: u64,
+        migrate: impl FnOnce(&Self, &mut DBBatch) -> Result<(), TypedStoreError>,
+    ) -> Result<(), TypedStoreError> {
+        // Not a key lock, which `migrate` could deadlock on by locking keys in the same stripe.
+        let lock = self.rocksdb.schema_migration_lock(&self.cf);
+        let _guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let stored_version = self.schema_version()?;
+        match stored_version.cmp(&expected_version) {
+            std::cmp::Ordering::Equal => Ok(()),
+            std::cmp::Ordering::Greater => Err(TypedStoreError::SchemaVersionTooNew(
+                stored_version,
+                expected_version,
+            )),
+            std::cmp::Ordering::Less => {
+                self.rocksdb.ensure_writable()?;
+                let mut batch = self.batch();
+                migrate(self, &mut batch)?;
+                let version =
+                    bcs::to_bytes(&expected_version).map_err(typed_store_err_from_bcs_err)?;
+                let version_key = self.schema_version_key();
+                batch.record_key(METADATA_CF_NAME, version_key.as_bytes());
+                batch
+                    .batch
+                    .put_cf(&self.metadata_cf()?, version_key, version);
+                batch.write()
+            }
+        }
+    }
+
+    fn metadata_cf(&self) -> Result<Arc<rocksdb::BoundColumnFamily<'_>>, TypedStoreError> {
+        self.rocksdb.ensure_open()?;
+        self.rocksdb
+            .cf_handle(METADATA_CF_NAME)
+            .ok_or_else(|| TypedStoreError::UnregisteredColumn(METADATA_CF_NAME.to_owned()))
+    }
+
+    fn schema_version_key(&self) -> String {
+        format!("schema_version/{}", self.cf)
+    }
+
+    /// Opens an existing database in read-only mode, operating under a specific column family.
+    ///
+    /// See [`open_cf_opts_readonly`]; writes through the map fail with
+    /// [`TypedStoreError::ReadOnlyDatabase`].
+    #[tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(path = ?path.as_ref(), cf = ?opt_cf),
+        err
+    )]
+    pub fn open_readonly<P: AsRef<Path>>(
+        path: P,
+        metric_conf: MetricConf,
+        db_options: Option<rocksdb::Options>,
+        opt_cf: Option<&str>,
+        rw_options: &ReadWriteOptions,
+    ) -> Result<Self, TypedStoreError> {
+        let cf_key = opt_cf.unwrap_or(rocksdb::DEFAULT_COLUMN_FAMILY_NAME);
+        let options = db_options.unwrap_or_else(|| default_db_options().options);
+        let rocksdb = open_cf_opts_readonly(
+            path,
+            Some(options.clone()),
+            metric_conf,
+            &[(cf_key, options)],
+        )?;
+        Ok(DBMap::new(rocksdb, rw_options, cf_key, false))
+    }
+
+    /// Opens a secondary instance of the database at `primary_path`, operating under a specific
+    /// column family.
+    ///
+    /// See [`open_cf_opts_secondary`]; call [`DBMap::try_catch_up_with_primary`] to observe new
+    /// writes of the primary.
+    #[tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(primary_path = ?primary_path.as_ref(), cf = ?opt_cf),
+        err
+    )]
+    pub fn open_secondary<P: AsRef<Path>, S: AsRef<Path>>(
+        primary_path: P,
+        secondary_path: S,
+        metric_conf: MetricConf,
+        db_options: Option<rocksdb::Options>,
+        opt_cf: Option<&str>,
+        rw_options: &ReadWriteOptions,
+    ) -> Result<Self, TypedStoreError> {
+        let cf_key = opt_cf.unwrap_or(rocksdb::DEFAULT_COLUMN_FAMILY_NAME);
+        let options = db_options.unwrap_or_else(|| default_db_options().options);
+        let rocksdb = open_cf_opts_secondary(
+            primary_path,
+            secondary_path,
+            Some(options.clone()),
+            metric_conf,
+            &[(cf_key, options)],
+        )?;
+        Ok(DBMap::new(rocksdb, rw_options, cf_key, false))
+    }
+
+    /// Catches a secondary instance up with the current state of its primary.
+    pub fn try_catch_up_with_primary(&self) -> Result<(), TypedStoreError> {
+        self.rocksdb
+            .try_catch_up_with_primary()
+            .map_err(typed_store_err_from_rocks_err)
+    }
+
     /// Reopens an open database as a typed map operating under a specific column family.
     /// if no column family is passed, the default column family is used.
     ///
@@ -910,65 +2292,1219 @@ impl<K, V> DBMap<K, V> {
         Ok(DBMap::new(db.clone(), rw_options, &cf_key, is_deprecated))
     }
 
-    /// Get the column family name.
-    pub fn cf_name(&self) -> &str {
-        &self.cf
-    }
-
-    /// Create a new batch associated with a DB reference.
-    pub fn batch(&self) -> DBBatch {
-        let batch = match *self.rocksdb {
-            RocksDB::DB(_) => RocksDBBatch::DB(WriteBatch::default()),
-            RocksDB::OptimisticTransactionDB(_) => {
+    /// Opens a map on a column family of an open database like [`DBMap::reopen`], verifying that
+    /// the column family was opened with the given options.
+    ///
+    /// The options of a column family are fixed when the database is opened, so they are only
+    /// checked here, not applied: `cf_opts` are name-value pairs in the format of RocksDB's options
+    /// files, e.g., `("compression", "kZSTD")`, and are compared with the latest options file of
+    /// the database. Fails if an option is unknown or has a different value.
+    pub fn reopen_with_options(
+        db: &Arc<RocksDB>,
+        opt_cf: Option<&str>,
+        rw_options: &ReadWriteOptions,
+        cf_opts: &[(&str, &str)],
+        is_deprecated: bool,
+    ) -> Result<Self, TypedStoreError> {
+        let cf_key = opt_cf.unwrap_or(rocksdb::DEFAULT_COLUMN_FAMILY_NAME);
+        db.cf_handle(cf_key)
+            .ok_or_else(|| TypedStoreError::UnregisteredColumn(cf_key.to_owned()))?;
+        let stored_options = read_cf_options(db.path(), cf_key)?;
+        for (name, value) in cf_opts {
+            match stored_options.get(*name) {
+                Some(stored) if stored == value => (),
+                Some(stored) => {
+                    return Err(TypedStoreError::RocksDBError(format!(
+                        "option {name} of column family {cf_key} is {stored}, not {value}"
+                    )));
+                }
+                None => {
+                    return Err(TypedStoreError::RocksDBError(format!(
+                        "option {name} of column family {cf_key} not found"
+                    )));
+                }
+            }
+        }
+        Self::reopen(db, opt_cf, rw_options, is_deprecated)
+    }
+
+    /// Get the column family name.
+    pub fn cf_name(&self) -> &str {
+        &self.cf
+    }
+
+    /// Create a new batch associated with a DB reference.
+    pub fn batch(&self) -> DBBatch {
+        let batch = match *self.rocksdb {
+            RocksDB::DB(_) => RocksDBBatch::DB(WriteBatch::default()),
+            RocksDB::OptimisticTransactionDB(_) => {
                 RocksDBBatch::OptimisticTransactionDB(WriteBatchWithTransaction::<true>::default())
             }
         };
-        DBBatch::new(
-            &self.rocksdb,
-            batch,
-            self.opts.writeopts(),
-            &self.db_metrics,
-            &self.write_sample_interval,
-        )
+        DBBatch::new(
+            &self.rocksdb,
+            batch,
+            self.opts.writeopts(),
+            &self.db_metrics,
+            &self.write_sample_interval,
+        )
+    }
+
+    /// Create a new batch that can read back its own staged writes.
+    pub fn indexed_batch(&self) -> IndexedBatch {
+        IndexedBatch::new(self.batch())
+    }
+
+    /// Create a new batch that is written whenever its size reaches `budget_bytes`, see
+    /// [`StreamingBatch`].
+    pub fn streaming_batch(&self, budget_bytes: usize) -> StreamingBatch {
+        StreamingBatch::new(self.batch(), budget_bytes)
+    }
+
+    /// Compact the keys in `[start, end)` of the column family, where a missing bound leaves the
+    /// range open on that side.
+    ///
+    /// Compaction drops deleted entries and range tombstones within the range, which reclaims
+    /// space and speeds up reads after bulk deletions. This call blocks until the compaction is
+    /// done.
+    pub fn compact_range(&self, start: Option<&K>, end: Option<&K>) -> Result<(), TypedStoreError>
+    where
+        K: Serialize,
+    {
+        let from_buf = start.map(|key| self.codec.encode_key(key)).transpose()?;
+        let to_buf = end.map(|key| self.codec.encode_key(key)).transpose()?;
+        self.rocksdb.compact_range_cf(&self.cf()?, from_buf, to_buf);
+        Ok(())
+    }
+
+    /// Compact the entire column family.
+    pub fn compact_all(&self) -> Result<(), TypedStoreError>
+    where
+        K: Serialize,
+    {
+        self.compact_range(None, None)
+    }
+
+    /// Deletes the keys between `from` (inclusive) and `to` (exclusive) with a single range delete
+    /// tombstone, written right away.
+    ///
+    /// As with [`DBBatch::schedule_delete_range`], reads through a map configured with
+    /// `ignore_range_deletions` set to true, which is the default, still see the deleted values
+    /// until compaction removes them. Only the standard RocksDB engine supports range deletes.
+    pub fn clear_range(&self, from: &K, to: &K) -> Result<(), TypedStoreError>
+    where
+        K: Serialize,
+    {
+        let cap = self.rocksdb.as_range_delete().ok_or_else(|| {
+            TypedStoreError::RocksDBError(
+                "range deletes require the standard RocksDB engine".into(),
+            )
+        })?;
+        let mut batch = self.batch();
+        batch.schedule_delete_range(self, from, to, &cap)?;
+        batch.write()
+    }
+
+    /// Deletes all keys whose serialization starts with the serialization of `prefix`, e.g., all
+    /// keys of a `DBMap<(A, B), V>` sharing the first component, with a single range delete
+    /// tombstone.
+    ///
+    /// The same caveats as for [`DBMap::clear_range`] apply. Fails for maps opened with a custom
+    /// key codec, as the prefix is serialized with the default key encoding, and for column
+    /// families with a custom [`Comparator`].
+    pub fn delete_prefix<P: Serialize>(&self, prefix: &P) -> Result<(), TypedStoreError> {
+        if !self.codec.has_default_key_codec() {
+            return Err(TypedStoreError::SerializationError(
+                "prefix deletes require the default key codec".into(),
+            ));
+        }
+        self.ensure_bytewise_order("prefix deletes")?;
+        let cap = self.rocksdb.as_range_delete().ok_or_else(|| {
+            TypedStoreError::RocksDBError(
+                "range deletes require the standard RocksDB engine".into(),
+            )
+        })?;
+        let from_buf = be_fix_int_ser(prefix)?;
+        let to_buf = if is_max(&from_buf) {
+            // No byte string of the prefix length sorts after the prefix, so the keys sharing it
+            // are exactly the keys from the prefix onwards, which end at the last key.
+            let mut db_iter = self
+                .rocksdb
+                .raw_iterator_cf(&self.cf()?, self.opts.readopts());
+            db_iter.seek_to_last();
+            match db_iter.key() {
+                Some(last) if last >= from_buf.as_slice() => inclusive_upper_bound(last.to_vec()),
+                _ => {
+                    db_iter.status().map_err(typed_store_err_from_rocks_err)?;
+                    return Ok(());
+                }
+            }
+        } else {
+            let mut to_buf = from_buf.clone();
+            big_endian_saturating_add_one(&mut to_buf);
+            to_buf
+        };
+        let mut batch = self.batch();
+        batch
+            .batch
+            .delete_range_cf(&self.cf()?, from_buf, to_buf, &cap)?;
+        batch.record_range(&self.cf);
+        batch.write()
+    }
+
+    /// Deletes all entries of the column family with a single range delete tombstone, written
+    /// right away, without dropping the column family.
+    ///
+    /// Unlike [`Map::unsafe_clear`], which drops and recreates the column family, existing handles
+    /// to the column family stay valid and there is no window in which it does not exist. The
+    /// tombstone is cheap to write, but until compaction removes it along with the deleted
+    /// entries, they still take up space and reads have to skip over them, see
+    /// [`DBMap::compact_all`]. The same caveats as for [`DBMap::clear_range`] apply. Fails for
+    /// column families with a custom [`Comparator`].
+    pub fn clear_via_range_delete(&self) -> Result<(), TypedStoreError> {
+        self.ensure_bytewise_order("range deletes of the whole map")?;
+        let cap = self.rocksdb.as_range_delete().ok_or_else(|| {
+            TypedStoreError::RocksDBError(
+                "range deletes require the standard RocksDB engine".into(),
+            )
+        })?;
+        let cf = self.cf()?;
+        let mut db_iter = self.rocksdb.raw_iterator_cf(&cf, self.opts.readopts());
+        db_iter.seek_to_first();
+        let Some(first_key) = db_iter.key().map(<[u8]>::to_vec) else {
+            return db_iter.status().map_err(typed_store_err_from_rocks_err);
+        };
+        db_iter.seek_to_last();
+        let Some(last_key) = db_iter.key().map(<[u8]>::to_vec) else {
+            return db_iter.status().map_err(typed_store_err_from_rocks_err);
+        };
+        let mut batch = self.batch();
+        batch
+            .batch
+            .delete_range_cf(&cf, first_key, inclusive_upper_bound(last_key), &cap)?;
+        batch.record_range(&self.cf);
+        batch.write()
+    }
+
+    /// Returns, for each prefix, whether any key's serialization starts with the serialization of
+    /// the prefix, in the same order as the prefixes.
+    ///
+    /// Each prefix costs a single seek, independently of the number of keys sharing it. Like
+    /// [`DBMap::delete_prefix`], this fails for maps opened with a custom key codec and for
+    /// column families with a custom [`Comparator`].
+    pub fn multi_contains_prefix<P: Serialize>(
+        &self,
+        prefixes: impl IntoIterator<Item = P>,
+    ) -> Result<Vec<bool>, TypedStoreError> {
+        if !self.codec.has_default_key_codec() {
+            return Err(TypedStoreError::SerializationError(
+                "prefix lookups require the default key codec".into(),
+            ));
+        }
+        self.ensure_bytewise_order("prefix lookups")?;
+        let mut db_iter = self
+            .rocksdb
+            .raw_iterator_cf(&self.cf()?, self.opts.readopts());
+        prefixes
+            .into_iter()
+            .map(|prefix| {
+                let prefix_buf = be_fix_int_ser(&prefix)?;
+                db_iter.seek(&prefix_buf);
+                match db_iter.key() {
+                    Some(key) => Ok(key.starts_with(&prefix_buf)),
+                    None => {
+                        db_iter.status().map_err(typed_store_err_from_rocks_err)?;
+                        Ok(false)
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Compact a range of keys in a specific column family.
+    pub fn compact_range_to_bottom<J: Serialize>(
+        &self,
+        start: &J,
+        end: &J,
+    ) -> Result<(), TypedStoreError> {
+        let from_buf = be_fix_int_ser(start)?;
+        let to_buf = be_fix_int_ser(end)?;
+        self.rocksdb
+            .compact_range_to_bottom(&self.cf()?, Some(from_buf), Some(to_buf));
+        Ok(())
+    }
+
+    /// Deletes the SST files whose keys all lie in `[from, to]`, reclaiming their space without
+    /// writing range deletes or compacting.
+    ///
+    /// Only files fully contained in the range are removed, and files in level 0 are never
+    /// removed, so entries of the range may remain in partially overlapping files, in level 0, or
+    /// in memtables; combine this with [`DBBatch::schedule_delete_range`] to delete all of them.
+    /// The deletion is not atomic and ignores snapshots: open iterators and snapshots may stop
+    /// seeing the removed entries, and older versions of the removed keys in other files may
+    /// become visible again.
+    pub fn delete_files_in_range(&self, from: &K, to: &K) -> Result<(), TypedStoreError>
+    where
+        K: Serialize,
+    {
+        self.rocksdb.ensure_writable()?;
+        let from_buf = self.codec.encode_key(from)?;
+        let to_buf = self.codec.encode_key(to)?;
+        self.rocksdb
+            .delete_file_in_range(&self.cf()?, from_buf, to_buf)
+            .map_err(typed_store_err_from_rocks_err)
+    }
+
+    /// Atomically replaces the value for `key` with `new` if the current value equals `expected`,
+    /// where `expected == None` means that the key must be absent.
+    ///
+    /// Returns whether the swap happened. Values are compared by their serialized form. The
+    /// check and the write are atomic with respect to all other writes of the key through this
+    /// database handle, including plain `insert` and `remove` calls and written batches.
+    pub fn compare_and_swap(
+        &self,
+        key: &K,
+        expected: Option<&V>,
+        new: &V,
+    ) -> Result<bool, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let key_buf = self.codec.encode_key(key)?;
+        let expected = expected
+            .map(|value| self.codec.encode_value(value))
+            .transpose()?;
+        let _guard = self.rocksdb.lock_key(&self.cf, &key_buf);
+        let current = self
+            .rocksdb
+            .get_pinned_cf_opt(&self.cf()?, &key_buf, &self.opts.readopts())
+            .map_err(typed_store_err_from_rocks_err)?;
+        if current.as_deref() != expected.as_deref() {
+            return Ok(false);
+        }
+        drop(current);
+        self.insert(key, new)?;
+        Ok(true)
+    }
+
+    /// Returns the value for `key`, or `V::default()` if the key is absent, without inserting it.
+    pub fn get_or_default(&self, key: &K) -> Result<V, TypedStoreError>
+    where
+        K: Serialize,
+        V: DeserializeOwned + Default,
+    {
+        Ok(self
+            .get_with_readopts(key, &self.opts.readopts())?
+            .unwrap_or_default())
+    }
+
+    /// Returns the value for `key`, inserting and returning `default()` if the key is absent.
+    ///
+    /// `default` runs without holding the key's lock, so it may read and write the database. The
+    /// value is then inserted only if the key is still absent, atomically with respect to other
+    /// atomic read-modify-write operations and all other writes of the key on the same database;
+    /// otherwise the current value is returned. Concurrent callers therefore agree on a single
+    /// value, but `default` may run in several of them.
+    pub fn get_or_insert_with<F>(&self, key: &K, default: F) -> Result<V, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+        F: FnOnce() -> V,
+    {
+        if let Some(value) = self.get(key)? {
+            return Ok(value);
+        }
+        let value = default();
+        let key_buf = self.codec.encode_key(key)?;
+        let _guard = self.rocksdb.lock_key(&self.cf, &key_buf);
+        if let Some(current) = self.get(key)? {
+            return Ok(current);
+        }
+        self.insert(key, &value)?;
+        Ok(value)
+    }
+
+    /// Removes `key` and returns its previous value, or `None` if the key was absent.
+    ///
+    /// The read and the removal are atomic with respect to other atomic read-modify-write
+    /// operations and all other writes of the key on the same database, so among concurrent
+    /// `take` calls on a key only one obtains its value.
+    pub fn take(&self, key: &K) -> Result<Option<V>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let key_buf = self.codec.encode_key(key)?;
+        let _guard = self.rocksdb.lock_key(&self.cf, &key_buf);
+        let value = self.get(key)?;
+        if value.is_some() {
+            self.remove(key)?;
+        }
+        Ok(value)
+    }
+
+    /// Inserts `value` for `key` and returns the previous value, or `None` if the key was absent,
+    /// like [`std::mem::replace`].
+    ///
+    /// The read and the write are atomic with respect to other atomic read-modify-write operations
+    /// and all other writes of the key on the same database, so each value replaced by a
+    /// concurrent `swap` or `insert` call is returned to exactly one caller.
+    pub fn swap(&self, key: &K, value: &V) -> Result<Option<V>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let key_buf = self.codec.encode_key(key)?;
+        let _guard = self.rocksdb.lock_key(&self.cf, &key_buf);
+        let previous = self.get(key)?;
+        self.insert(key, value)?;
+        Ok(previous)
+    }
+
+    /// Returns the entry for `key`, for atomic in-place manipulation of its value.
+    ///
+    /// The entry does not lock the key; writes through it fail if the key was written since the
+    /// entry was created, see [`Entry`].
+    pub fn entry<'a>(&'a self, key: &'a K) -> Result<Entry<'a, K, V>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let key_buf = self.codec.encode_key(key)?;
+        let value_buf = self
+            .rocksdb
+            .get_pinned_cf_opt(&self.cf()?, &key_buf, &self.opts.readopts())
+            .map_err(typed_store_err_from_rocks_err)?
+            .map(|value| value.to_vec());
+        Entry::new(self, key, value_buf)
+    }
+
+    /// Disables automatic compactions and enlarges the memtables of the column family until the
+    /// returned guard and all other guards of the column family are released.
+    ///
+    /// Use this around large initial loads, e.g., with [`Self::multi_insert_chunked`], so that
+    /// background compactions do not compete with the writes, and call [`BulkLoadGuard::finish`]
+    /// afterwards to compact the column family. Fails if the database is read-only.
+    pub fn bulk_load_guard(&self) -> Result<BulkLoadGuard<'_, K, V>, TypedStoreError> {
+        BulkLoadGuard::new(self)
+    }
+
+    /// Changes mutable options of the column family, e.g., `write_buffer_size` or
+    /// `disable_auto_compactions`, without reopening the database.
+    ///
+    /// The options are given as name-value pairs in the format of RocksDB's options files. Fails
+    /// without changing any option if a name is unknown, an option cannot be changed at runtime,
+    /// or a value is invalid. Changes are persisted in the options file but not applied when the
+    /// database is reopened with explicit options.
+    pub fn set_options(&self, opts: &[(&str, &str)]) -> Result<(), TypedStoreError> {
+        self.rocksdb.ensure_writable()?;
+        self.rocksdb
+            .set_options_cf(&self.cf()?, opts)
+            .map_err(typed_store_err_from_rocks_err)
+    }
+
+    /// Inserts the key-value pairs in batches of at most `chunk_size` pairs.
+    ///
+    /// Unlike [`Map::multi_insert`], which writes all pairs in a single batch, this bounds the size
+    /// of each write batch, e.g., [`DEFAULT_WRITE_CHUNK_SIZE`] pairs, so that bulk inserts neither
+    /// spike memory nor stall writes. The insertion is therefore only atomic per chunk: if writing
+    /// a chunk fails, the earlier chunks remain written.
+    pub fn multi_insert_chunked<J, U>(
+        &self,
+        key_val_pairs: impl IntoIterator<Item = (J, U)>,
+        chunk_size: usize,
+    ) -> Result<(), TypedStoreError>
+    where
+        J: Borrow<K>,
+        U: Borrow<V>,
+        K: Serialize,
+        V: Serialize,
+    {
+        let mut key_val_pairs = key_val_pairs.into_iter().peekable();
+        while key_val_pairs.peek().is_some() {
+            let mut batch = self.batch();
+            batch.insert_batch(self, key_val_pairs.by_ref().take(chunk_size.max(1)))?;
+            batch.write()?;
+        }
+        Ok(())
+    }
+
+    /// Removes the keys in batches of at most `chunk_size` keys.
+    ///
+    /// As with [`Self::multi_insert_chunked`], the removal is only atomic per chunk.
+    pub fn multi_remove_chunked<J>(
+        &self,
+        keys: impl IntoIterator<Item = J>,
+        chunk_size: usize,
+    ) -> Result<(), TypedStoreError>
+    where
+        J: Borrow<K>,
+        K: Serialize,
+    {
+        let mut keys = keys.into_iter().peekable();
+        while keys.peek().is_some() {
+            let mut batch = self.batch();
+            batch.delete_batch(self, keys.by_ref().take(chunk_size.max(1)))?;
+            batch.write()?;
+        }
+        Ok(())
+    }
+
+    /// Removes the entries with keys in `range` for which `predicate` returns true, returning the
+    /// number of removed entries.
+    ///
+    /// The range is scanned with a single iterator, and the matching keys are removed in batches
+    /// of [`DEFAULT_WRITE_CHUNK_SIZE`] keys while scanning. The removal is not atomic: concurrent
+    /// writes to the range may be overwritten by the removal or missed by the scan, and if a batch
+    /// fails, the earlier batches remain written.
+    pub fn remove_range_where(
+        &self,
+        range: impl RangeBounds<K>,
+        predicate: impl Fn(&K, &V) -> bool,
+    ) -> Result<usize, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let mut removed = 0;
+        let mut chunk = Vec::new();
+        for entry in self.safe_range_iter(range)? {
+            let (key, value) = entry?;
+            if !predicate(&key, &value) {
+                continue;
+            }
+            chunk.push(key);
+            if chunk.len() == DEFAULT_WRITE_CHUNK_SIZE {
+                removed += chunk.len();
+                self.multi_remove(chunk.drain(..))?;
+            }
+        }
+        removed += chunk.len();
+        self.multi_remove(chunk)?;
+        Ok(removed)
+    }
+
+    /// Copies all entries of the map into `dest`, which may belong to a different database, in
+    /// batches of at most `chunk_size` entries, returning the number of entries copied.
+    ///
+    /// The entries are read from a consistent view of the map, decoded, and encoded with the
+    /// codecs of `dest`, so the maps may use different codecs. Existing entries of `dest` are
+    /// overwritten by entries with the same key and kept otherwise. As with
+    /// [`Self::multi_insert_chunked`], the copy is only atomic per chunk.
+    pub fn copy_to(&self, dest: &DBMap<K, V>, chunk_size: usize) -> Result<u64, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let chunk_size = chunk_size.max(1);
+        let mut copied = 0;
+        let mut chunk = Vec::with_capacity(chunk_size);
+        for entry in self.safe_iter()? {
+            chunk.push(entry?);
+            if chunk.len() == chunk_size {
+                dest.multi_insert(chunk.drain(..))?;
+                copied += chunk_size as u64;
+            }
+        }
+        copied += chunk.len() as u64;
+        dest.multi_insert(chunk)?;
+        Ok(copied)
+    }
+
+    /// Writes all entries of the map to `writer` as newline-delimited JSON, returning the number
+    /// of entries written.
+    ///
+    /// Each line holds one JSON object with the fields `key` and `value`, in key order. The output
+    /// can be loaded back with [`Self::import_ndjson`].
+    pub fn export_ndjson<W: Write>(&self, mut writer: W) -> Result<u64, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let mut count = 0;
+        for entry in self.safe_iter()? {
+            let (key, value) = entry?;
+            serde_json::to_writer(&mut writer, &NdjsonRecord { key, value })
+                .map_err(typed_store_err_from_json_err)?;
+            writer
+                .write_all(b"\n")
+                .map_err(|e| TypedStoreError::SerializationError(e.to_string()))?;
+            count += 1;
+        }
+        writer
+            .flush()
+            .map_err(|e| TypedStoreError::SerializationError(e.to_string()))?;
+        Ok(count)
+    }
+
+    /// Inserts the entries read from newline-delimited JSON as written by
+    /// [`Self::export_ndjson`], returning the number of entries inserted.
+    ///
+    /// Empty lines are skipped. The entries are written in batches of
+    /// [`DEFAULT_WRITE_CHUNK_SIZE`], so if reading fails, the batches written before the failure
+    /// remain in the map.
+    pub fn import_ndjson<R: Read>(&self, reader: R) -> Result<u64, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let mut count = 0;
+        let mut chunk = Vec::with_capacity(DEFAULT_WRITE_CHUNK_SIZE);
+        for line in BufReader::new(reader).lines() {
+            let line = line.map_err(|e| TypedStoreError::SerializationError(e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: NdjsonRecord<K, V> =
+                serde_json::from_str(&line).map_err(typed_store_err_from_json_err)?;
+            chunk.push((record.key, record.value));
+            if chunk.len() == DEFAULT_WRITE_CHUNK_SIZE {
+                self.multi_insert(chunk.drain(..))?;
+            }
+            count += 1;
+        }
+        if !chunk.is_empty() {
+            self.multi_insert(chunk)?;
+        }
+        Ok(count)
+    }
+
+    /// Removes the keys, returning for each key whether it was present before the removal.
+    ///
+    /// The flags are aligned with the input keys; a key given more than once is reported as
+    /// present for each occurrence if it was present. The check and the removal are atomic with
+    /// respect to all other writes of the keys on the same database, and all keys are removed in
+    /// a single batch.
+    pub fn multi_remove_reporting<J>(
+        &self,
+        keys: impl IntoIterator<Item = J>,
+    ) -> Result<Vec<bool>, TypedStoreError>
+    where
+        J: Borrow<K>,
+        K: Serialize,
+    {
+        let keys_bytes = keys
+            .into_iter()
+            .map(|key| self.codec.encode_key(key.borrow()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let _guards = self.rocksdb.lock_keys(&self.cf, &keys_bytes);
+        let existed: Vec<bool> = self
+            .multi_get_pinned_serialized(&keys_bytes)?
+            .iter()
+            .map(Option::is_some)
+            .collect();
+
+        let mut batch = self.batch();
+        let cf = self.cf()?;
+        for key_buf in &keys_bytes {
+            batch.record_key(&self.cf, key_buf);
+            batch.batch.delete_cf(&cf, key_buf);
+        }
+        batch.write()?;
+        Ok(existed)
+    }
+
+    /// Inserts the key-value pairs whose keys are absent, leaving existing values untouched.
+    ///
+    /// Returns for each pair whether it was inserted. If a key is given more than once, only its
+    /// first occurrence can be inserted. The presence check is a single multi-get, and all inserts
+    /// are written in a single batch.
+    ///
+    /// The check and the write are atomic with respect to all other writes of the keys on the
+    /// same database, such as concurrent `multi_insert_if_absent`, `compare_and_swap` or `insert`
+    /// calls, as the keys stay locked in between. No [`IndexedBatch`] is involved, as the check
+    /// only reads committed data.
+    pub fn multi_insert_if_absent<J, U>(
+        &self,
+        key_val_pairs: impl IntoIterator<Item = (J, U)>,
+    ) -> Result<Vec<bool>, TypedStoreError>
+    where
+        J: Borrow<K>,
+        U: Borrow<V>,
+        K: Serialize,
+        V: Serialize,
+    {
+        let (keys_bytes, values): (Vec<_>, Vec<_>) = key_val_pairs
+            .into_iter()
+            .map(|(key, value)| Ok((self.codec.encode_key(key.borrow())?, value)))
+            .collect::<Result<Vec<_>, TypedStoreError>>()?
+            .into_iter()
+            .unzip();
+        let _guards = self.rocksdb.lock_keys(&self.cf, &keys_bytes);
+        let existing = self.multi_get_pinned_serialized(&keys_bytes)?;
+
+        let mut batch = self.batch();
+        let cf = self.cf()?;
+        let mut inserted_keys = HashSet::new();
+        let mut inserted = Vec::with_capacity(keys_bytes.len());
+        for ((key_buf, value), existing) in keys_bytes.iter().zip(values).zip(existing) {
+            let insert = existing.is_none() && inserted_keys.insert(key_buf);
+            if insert {
+                let value_buf = self.codec.encode_value(value.borrow())?;
+                batch.record_key(&self.cf, key_buf);
+                batch.batch.put_cf(&cf, key_buf, value_buf);
+            }
+            inserted.push(insert);
+        }
+        batch.write()?;
+        Ok(inserted)
+    }
+
+    /// Inserts the key-value pair with the given write options instead of the map's own.
+    ///
+    /// See [`DBWriteOptions`] for the durability of the write.
+    pub fn insert_with_options(
+        &self,
+        key: &K,
+        value: &V,
+        opts: &DBWriteOptions,
+    ) -> Result<(), TypedStoreError>
+    where
+        K: Serialize,
+        V: Serialize,
+    {
+        self.insert_with_writeopts(key, value, &opts.writeopts())
+    }
+
+    /// Inserts already serialized value bytes for the key, skipping the value codec; the key is
+    /// serialized as usual.
+    ///
+    /// The bytes are stored as they are, so they must be a valid encoding of a `V` under the
+    /// map's value codec, e.g., as returned by [`DBMap::get_raw`] for a map with the same codec.
+    /// Otherwise, reading the key fails with a deserialization error, or, worse, succeeds with a
+    /// different value.
+    pub fn insert_raw(&self, key: &K, value_bytes: &[u8]) -> Result<(), TypedStoreError>
+    where
+        K: Serialize,
+    {
+        self.insert_serialized_with_writeopts(key, value_bytes, &self.opts.writeopts())
+    }
+
+    fn insert_with_writeopts(
+        &self,
+        key: &K,
+        value: &V,
+        writeopts: &WriteOptions,
+    ) -> Result<(), TypedStoreError>
+    where
+        K: Serialize,
+        V: Serialize,
+    {
+        let value_buf = self.codec.encode_value(value)?;
+        self.insert_serialized_with_writeopts(key, &value_buf, writeopts)
+    }
+
+    fn insert_serialized_with_writeopts(
+        &self,
+        key: &K,
+        value_buf: &[u8],
+        writeopts: &WriteOptions,
+    ) -> Result<(), TypedStoreError>
+    where
+        K: Serialize,
+    {
+        let timer = self
+            .db_metrics
+            .op_metrics
+            .rocksdb_put_latency_seconds
+            .with_label_values(&[&self.cf])
+            .start_timer();
+        let perf_ctx = if self.write_sample_interval.sample() {
+            Some(RocksDBPerfContext)
+        } else {
+            None
+        };
+        let key_buf = self.codec.encode_key(key)?;
+        self.db_metrics
+            .op_metrics
+            .rocksdb_put_key_bytes
+            .with_label_values(&[&self.cf])
+            .observe(key_buf.len() as f64);
+        self.db_metrics
+            .op_metrics
+            .rocksdb_put_value_bytes
+            .with_label_values(&[&self.cf])
+            .observe(value_buf.len() as f64);
+        self.db_metrics
+            .op_metrics
+            .rocksdb_put_bytes
+            .with_label_values(&[&self.cf])
+            .observe(key_buf.len() as f64 + value_buf.len() as f64);
+        if perf_ctx.is_some() {
+            self.db_metrics
+                .write_perf_ctx_metrics
+                .report_metrics(&self.cf);
+        }
+        self.rocksdb.ensure_writable()?;
+        let guard = self.rocksdb.lock_key(&self.cf, &key_buf);
+        self.rocksdb
+            .put_cf(&self.cf()?, &key_buf, value_buf, writeopts)
+            .map_err(typed_store_err_from_rocks_err)?;
+        self.verify_point_write("insert", &key_buf, Some(value_buf))?;
+        drop(guard);
+
+        let elapsed = timer.stop_and_record();
+        if perf_ctx.is_some() {
+            self.db_metrics.observe_sampled_op_latency(
+                &self.cf,
+                "insert",
+                Duration::from_secs_f64(elapsed),
+            );
+        }
+        self.rocksdb.report_if_slow(
+            "insert",
+            Some(&self.cf),
+            key_buf.len(),
+            Duration::from_secs_f64(elapsed),
+        );
+        if elapsed > 1.0 {
+            tracing::warn!(?elapsed, cf = ?self.cf, "very slow insert");
+            self.db_metrics
+                .op_metrics
+                .rocksdb_very_slow_puts_count
+                .with_label_values(&[&self.cf])
+                .inc();
+            self.db_metrics
+                .op_metrics
+                .rocksdb_very_slow_puts_duration_ms
+                .with_label_values(&[&self.cf])
+                .inc_by((elapsed * 1000.0) as u64);
+        }
+
+        Ok(())
+    }
+
+    /// Inserts the key-value pair, failing with [`TypedStoreError::KeyAlreadyExists`] if the key
+    /// is already present.
+    ///
+    /// The existence check and the write are atomic with respect to all other writes of the key on
+    /// the same database, such as concurrent `try_insert` or `insert` calls.
+    pub fn try_insert(&self, key: &K, value: &V) -> Result<(), TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let key_buf = self.codec.encode_key(key)?;
+        let _guard = self.rocksdb.lock_key(&self.cf, &key_buf);
+        let exists = self
+            .rocksdb
+            .get_pinned_cf_opt(&self.cf()?, &key_buf, &self.opts.readopts())
+            .map_err(typed_store_err_from_rocks_err)?
+            .is_some();
+        if exists {
+            return Err(TypedStoreError::KeyAlreadyExists);
+        }
+        self.insert(key, value)
+    }
+
+    /// Merges `operand` into the value for `key` using the column family's merge operator.
+    ///
+    /// Fails if the column family was opened without a merge operator.
+    pub fn merge(&self, key: &K, operand: &V) -> Result<(), TypedStoreError>
+    where
+        K: Serialize,
+        V: Serialize,
+    {
+        let key_buf = self.codec.encode_key(key)?;
+        let operand_buf = self.codec.encode_value(operand)?;
+        self.rocksdb.ensure_writable()?;
+        let _guard = self.rocksdb.lock_key(&self.cf, &key_buf);
+        self.rocksdb
+            .merge_cf(&self.cf()?, &key_buf, &operand_buf, &self.opts.writeopts())
+            .map_err(typed_store_err_from_rocks_err)
+    }
+
+    /// Returns the value of the RocksDB integer property `name`, e.g.,
+    /// `rocksdb.cur-size-all-mem-tables`, for the column family of the map.
+    ///
+    /// Returns `None` if the property is unknown or has no integer value.
+    pub fn get_int_property(&self, name: &str) -> Result<Option<u64>, TypedStoreError> {
+        self.int_property(name)
+    }
+
+    /// Returns RocksDB's estimate of the number of keys in the column family.
+    ///
+    /// The estimate counts unflushed writes and deletions as well as overwritten keys that were
+    /// not yet compacted, so it can be off in either direction.
+    pub fn estimate_num_keys(&self) -> Result<u64, TypedStoreError> {
+        Ok(self
+            .int_property(properties::ESTIMATE_NUM_KEYS)?
+            .unwrap_or_default())
+    }
+
+    /// Returns the compression of the column family, see [`RocksDB::compression`].
+    pub fn compression(&self) -> Option<Compression> {
+        self.rocksdb.compression(&self.cf)
+    }
+
+    /// Returns the bloom filter of the column family, see [`RocksDB::bloom_filter`].
+    pub fn bloom_filter(&self) -> Option<BloomFilter> {
+        self.rocksdb.bloom_filter(&self.cf)
+    }
+
+    /// Returns the total size of the SST files of the column family, in bytes.
+    ///
+    /// Data that was not yet flushed from the memtables is not included.
+    pub fn total_sst_files_size(&self) -> Result<u64, TypedStoreError> {
+        Ok(self
+            .int_property(properties::TOTAL_SST_FILES_SIZE)?
+            .unwrap_or_default())
+    }
+
+    /// Returns the block cache hits and misses of the database, which require statistics to be
+    /// enabled, e.g., with [`DBMap::open_with_statistics`].
+    ///
+    /// The counts are cumulative since the database was opened and cover all its column families,
+    /// as RocksDB does not count them per column family.
+    pub fn block_cache_stats(&self) -> Result<CacheStats, TypedStoreError> {
+        let stats = self.rocksdb.statistics_snapshot().ok_or_else(|| {
+            TypedStoreError::RocksDBError("statistics are not enabled for the database".into())
+        })?;
+        Ok(CacheStats::new(
+            stats.block_cache_hits,
+            stats.block_cache_misses,
+        ))
+    }
+
+    /// Verifies the checksums of all data of the column family.
+    ///
+    /// All entries are read from the SST files, so this takes time proportional to the size of
+    /// the column family, but no entries are deserialized or returned. Blocks already in the block
+    /// cache were verified when they were loaded and are not read again. A mismatch results in
+    /// [`TypedStoreError::Corruption`], whose message names the corrupt file.
+    pub fn verify_checksum(&self) -> Result<(), TypedStoreError> {
+        self.rocksdb.verify_checksum_cf(&self.cf()?)
+    }
+
+    /// Returns the approximate memory used by the column family of the map.
+    ///
+    /// The block cache figures cover the whole cache the column family uses, which by default is
+    /// shared by all column families of the database.
+    pub fn memory_usage(&self) -> Result<MemoryUsage, TypedStoreError> {
+        let property = |name| self.int_property(name).map(Option::unwrap_or_default);
+        Ok(MemoryUsage {
+            mem_tables: property(properties::SIZE_ALL_MEM_TABLES)?,
+            unflushed_mem_tables: property(properties::CUR_SIZE_ALL_MEM_TABLES)?,
+            table_readers: property(properties::ESTIMATE_TABLE_READERS_MEM)?,
+            block_cache: property(properties::BLOCK_CACHE_USAGE)?,
+            block_cache_pinned: property(properties::BLOCK_CACHE_PINNED_USAGE)?,
+        })
+    }
+
+    /// Returns the SST files of the column family, ordered by level and file number.
+    pub fn live_files(&self) -> Result<Vec<SstFileInfo>, TypedStoreError> {
+        let mut files: Vec<_> = self
+            .rocksdb
+            .live_files()
+            .map_err(typed_store_err_from_rocks_err)?
+            .into_iter()
+            .filter(|file| file.column_family_name == self.cf)
+            .map(SstFileInfo::from)
+            .collect();
+        files.sort_by_key(|file| (file.level, file.file_number));
+        Ok(files)
+    }
+
+    fn int_property(&self, name: impl CStrLike) -> Result<Option<u64>, TypedStoreError> {
+        self.rocksdb
+            .property_int_value_cf(&self.cf()?, name)
+            .map_err(typed_store_err_from_rocks_err)
+    }
+
+    /// Estimates the number of keys in `range` without iterating over it.
+    ///
+    /// The estimate apportions RocksDB's estimated key count of the flushed data of the column
+    /// family by the share of on-disk bytes that falls within the range, as reported by RocksDB's
+    /// approximate sizes. It assumes entries of roughly uniform size and can be off by a large
+    /// factor for small ranges.
+    ///
+    /// Only data flushed to SST files is counted: entries still in memtables, including all
+    /// recently written ones, are ignored. Use [`DBMap::count_in_range`] when precision matters.
+    /// Like the iterators, this fails for excluded lower or included upper bounds on column
+    /// families with a custom [`Comparator`].
+    pub fn estimate_keys_in_range(&self, range: impl RangeBounds<K>) -> Result<u64, TypedStoreError>
+    where
+        K: Serialize,
+    {
+        let cf = self.cf()?;
+        // The estimated key count includes the memtables, whose sizes are not part of the SST
+        // files' size.
+        let total_keys = Self::get_i64_property(&self.rocksdb, &cf, properties::ESTIMATE_NUM_KEYS)?
+            - Self::get_i64_property(&self.rocksdb, &cf, properties::NUM_ENTRIES_ACTIVE_MEM_TABLE)?
+            - Self::get_i64_property(&self.rocksdb, &cf, properties::NUM_ENTRIES_IMM_MEM_TABLES)?;
+        let total_bytes =
+            Self::get_i64_property(&self.rocksdb, &cf, properties::TOTAL_SST_FILES_SIZE)?;
+        if total_keys <= 0 || total_bytes <= 0 {
+            return Ok(0);
+        }
+
+        let start = match range.start_bound() {
+            Bound::Included(key) => self.codec.encode_key(key)?,
+            Bound::Excluded(key) => {
+                self.ensure_bytewise_order("excluded lower bounds")?;
+                inclusive_upper_bound(self.codec.encode_key(key)?)
+            }
+            Bound::Unbounded => Vec::new(),
+        };
+        let range_bytes = match range.end_bound() {
+            Bound::Included(key) => {
+                self.ensure_bytewise_order("included upper bounds")?;
+                let end = inclusive_upper_bound(self.codec.encode_key(key)?);
+                self.rocksdb.approximate_size_cf(&cf, &start, &end)
+            }
+            Bound::Excluded(key) => {
+                let end = self.codec.encode_key(key)?;
+                self.rocksdb.approximate_size_cf(&cf, &start, &end)
+            }
+            Bound::Unbounded => (total_bytes as u64)
+                .saturating_sub(self.rocksdb.approximate_size_cf(&cf, &[], &start)),
+        };
+        let fraction = (range_bytes as f64 / total_bytes as f64).min(1.0);
+        Ok((total_keys as f64 * fraction).round() as u64)
     }
 
-    /// Compact a range of keys in a specific column family.
-    pub fn compact_range<J: Serialize>(&self, start: &J, end: &J) -> Result<(), TypedStoreError> {
-        let from_buf = be_fix_int_ser(start)?;
-        let to_buf = be_fix_int_ser(end)?;
-        self.rocksdb
-            .compact_range_cf(&self.cf()?, Some(from_buf), Some(to_buf));
-        Ok(())
+    /// Counts the keys in `range` exactly by iterating over them without decoding the entries.
+    pub fn count_in_range(&self, range: impl RangeBounds<K>) -> Result<u64, TypedStoreError>
+    where
+        K: Serialize,
+    {
+        self.count_with_readopts(self.create_read_options_with_range(range)?)
     }
 
-    /// Compact a range of keys in a specific column family.
-    pub fn compact_range_to_bottom<J: Serialize>(
+    /// Counts the entries of the map exactly.
+    ///
+    /// This iterates over all entries and thus takes time linear in their number, but it neither
+    /// decodes keys nor reads or deserializes any value, which makes it considerably cheaper than
+    /// counting the entries of [`Map::safe_iter`]. Use [`DBMap::estimate_num_keys`] for a constant
+    /// time estimate.
+    pub fn count(&self) -> Result<usize, TypedStoreError> {
+        Ok(self
+            .count_with_readopts(self.opts.readopts())?
+            .try_into()
+            .expect("the number of entries fits into a usize"))
+    }
+
+    /// Counts the entries with keys strictly less than `key` exactly, see [`DBMap::count`].
+    ///
+    /// The entry at `key` itself, if any, is not counted.
+    pub fn count_below(&self, key: &K) -> Result<usize, TypedStoreError>
+    where
+        K: Serialize,
+    {
+        Ok(self
+            .count_in_range((Bound::Unbounded, Bound::Excluded(key)))?
+            .try_into()
+            .expect("the number of entries fits into a usize"))
+    }
+
+    /// Counts the entries with keys strictly greater than `key` exactly, see [`DBMap::count`].
+    ///
+    /// The entry at `key` itself, if any, is not counted.
+    pub fn count_above(&self, key: &K) -> Result<usize, TypedStoreError>
+    where
+        K: Serialize,
+    {
+        Ok(self
+            .count_in_range((Bound::Excluded(key), Bound::Unbounded))?
+            .try_into()
+            .expect("the number of entries fits into a usize"))
+    }
+
+    /// Returns the entry with the smallest key, or `None` if the map is empty.
+    pub fn first(&self) -> Result<Option<(K, V)>, TypedStoreError>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+    {
+        let mut db_iter = self
+            .rocksdb
+            .raw_iterator_cf(&self.cf()?, self.opts.readopts());
+        db_iter.seek_to_first();
+        self.decode_current_entry(&db_iter)
+    }
+
+    /// Returns the entry with the largest key, or `None` if the map is empty.
+    pub fn last(&self) -> Result<Option<(K, V)>, TypedStoreError>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+    {
+        let mut db_iter = self
+            .rocksdb
+            .raw_iterator_cf(&self.cf()?, self.opts.readopts());
+        db_iter.seek_to_last();
+        self.decode_current_entry(&db_iter)
+    }
+
+    /// Removes and returns the entry with the smallest key, or returns `None` if the map is empty.
+    ///
+    /// The removal is atomic like [`DBMap::take`], so concurrent consumers each pop a different
+    /// entry.
+    pub fn pop_first(&self) -> Result<Option<(K, V)>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        self.pop_boundary(|db_iter| db_iter.seek_to_first())
+    }
+
+    /// Removes and returns the entry with the largest key, or returns `None` if the map is empty.
+    ///
+    /// The removal is atomic like [`DBMap::take`], so concurrent consumers each pop a different
+    /// entry.
+    pub fn pop_last(&self) -> Result<Option<(K, V)>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        self.pop_boundary(|db_iter| db_iter.seek_to_last())
+    }
+
+    /// Takes the entry a raw iterator points to after `seek`, retrying with the next boundary key
+    /// if a concurrent consumer took the entry first.
+    fn pop_boundary(
         &self,
-        start: &J,
-        end: &J,
-    ) -> Result<(), TypedStoreError> {
-        let from_buf = be_fix_int_ser(start)?;
-        let to_buf = be_fix_int_ser(end)?;
-        self.rocksdb
-            .compact_range_to_bottom(&self.cf()?, Some(from_buf), Some(to_buf));
-        Ok(())
+        seek: impl Fn(&mut RocksDBRawIter<'_>),
+    ) -> Result<Option<(K, V)>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        loop {
+            let mut db_iter = self
+                .rocksdb
+                .raw_iterator_cf(&self.cf()?, self.opts.readopts());
+            seek(&mut db_iter);
+            let Some(key_buf) = db_iter.key() else {
+                db_iter.status().map_err(typed_store_err_from_rocks_err)?;
+                return Ok(None);
+            };
+            let key = self.codec.decode_key(key_buf)?;
+            drop(db_iter);
+            if let Some(value) = self.take(&key)? {
+                return Ok(Some((key, value)));
+            }
+        }
+    }
+
+    /// Decodes the entry a raw iterator points to, or returns `None` if it is not valid.
+    fn decode_current_entry(
+        &self,
+        db_iter: &RocksDBRawIter<'_>,
+    ) -> Result<Option<(K, V)>, TypedStoreError>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+    {
+        match (db_iter.key(), db_iter.value()) {
+            (Some(key), Some(value)) => Ok(Some((
+                self.codec.decode_key(key)?,
+                self.codec.decode_value(value)?,
+            ))),
+            _ => {
+                db_iter.status().map_err(typed_store_err_from_rocks_err)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Scans the entries in `range` without decoding them and reports how much work RocksDB did
+    /// for it, see [`ScanStats`].
+    ///
+    /// Many skipped internal keys relative to the keys seen indicate that deletions or overwrites
+    /// in the range are not compacted yet, which slows down scans; compacting the range with
+    /// [`DBMap::compact_range`] removes them. The scan enables RocksDB's perf context of the
+    /// calling thread and restores its previous level afterwards; counters collected by an
+    /// enclosing [`RocksDBPerfContext`] keep accumulating.
+    pub fn scan_diagnostics(&self, range: impl RangeBounds<K>) -> Result<ScanStats, TypedStoreError>
+    where
+        K: Serialize,
+    {
+        let readopts = self.create_read_options_with_range(range)?;
+        let mut db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
+        // The perf context is thread-local, so the difference of its counters is the work of this
+        // scan. It is not reset, so that it keeps the counts of an enclosing measurement.
+        let perf_context = PerfContext::default();
+        let previous_level = set_perf_level(PerfStatsLevel::EnableCount);
+        let internal_keys_before = perf_context.metric(PerfMetric::InternalKeySkippedCount);
+        let deletions_before = perf_context.metric(PerfMetric::InternalDeleteSkippedCount);
+        let start = std::time::Instant::now();
+        db_iter.seek_to_first();
+        let mut keys_seen = 0;
+        while db_iter.valid() {
+            keys_seen += 1;
+            db_iter.next();
+        }
+        let elapsed = start.elapsed();
+        let internal_keys_skipped = perf_context
+            .metric(PerfMetric::InternalKeySkippedCount)
+            .saturating_sub(internal_keys_before);
+        let deletions_skipped = perf_context
+            .metric(PerfMetric::InternalDeleteSkippedCount)
+            .saturating_sub(deletions_before);
+        set_perf_level(previous_level);
+        db_iter.status().map_err(typed_store_err_from_rocks_err)?;
+        Ok(ScanStats {
+            keys_seen,
+            internal_keys_skipped,
+            deletions_skipped,
+            elapsed,
+        })
+    }
+
+    fn count_with_readopts(&self, readopts: ReadOptions) -> Result<u64, TypedStoreError> {
+        let mut db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
+        db_iter.seek_to_first();
+        let mut count = 0;
+        while db_iter.valid() {
+            count += 1;
+            db_iter.next();
+        }
+        db_iter.status().map_err(typed_store_err_from_rocks_err)?;
+        Ok(count)
     }
 
     /// Get the column family.
+    ///
+    /// Fails with [`TypedStoreError::DatabaseClosed`] if the database was shut down, which makes
+    /// all operations on the map fail that way.
     pub fn cf(&self) -> Result<Arc<rocksdb::BoundColumnFamily<'_>>, TypedStoreError> {
+        self.rocksdb.ensure_open()?;
         self.rocksdb
             .cf_handle(&self.cf)
             .ok_or_else(|| TypedStoreError::UnregisteredColumn(self.cf.clone()))
     }
 
-    /// Flush the column family.
+    /// Flush the memtable of the column family to SST files.
+    ///
+    /// Writes are durable through the write-ahead log regardless; flushing makes them part of the
+    /// SST files, e.g., so that a subsequent checkpoint or backup does not need to replay the log.
     pub fn flush(&self) -> Result<(), TypedStoreError> {
         self.rocksdb
             .flush_cf(&self.cf()?)
             .map_err(|e| TypedStoreError::RocksDBError(e.into_string()))
     }
 
-    fn get_int_property(
+    /// Ingests the given SST files into the column family.
+    ///
+    /// This is much faster than inserting the same data through [`Map::multi_insert`] and is meant
+    /// for bulk loads, e.g., when bootstrapping a node. The files are typically written with an
+    /// [`SstFileWriter`](sst::SstFileWriter) for this map, which requires their keys to be sorted.
+    /// Ingested values overwrite existing values for the same keys.
+    pub fn ingest_external_sst(
+        &self,
+        files: &[PathBuf],
+        opts: IngestOptions,
+    ) -> Result<(), TypedStoreError> {
+        self.rocksdb.ingest_external_file_cf(
+            &self.cf()?,
+            &opts.to_rocksdb_options(),
+            files.iter().map(PathBuf::as_path).collect(),
+        )
+    }
+
+    fn get_i64_property(
         rocksdb: &RocksDB,
         cf: &impl AsColumnFamilyRef,
         property_name: &std::ffi::CStr,
@@ -980,8 +3516,13 @@ impl<K, V> DBMap<K, V> {
         }
     }
 
-    /// Returns a vector of raw values corresponding to the keys provided.
-    fn multi_get_pinned<J>(
+    /// Returns the serialized values corresponding to the keys provided, in the same order as the
+    /// keys, as slices pinned in RocksDB's block cache or memtables.
+    ///
+    /// Unlike [`DBMap::multi_get_raw`], no buffer is allocated per value, which makes this
+    /// preferable for large batches of big values that are only inspected or deserialized in
+    /// place. The slices keep the underlying blocks pinned until they are dropped.
+    pub fn multi_get_pinned<J>(
         &self,
         keys: impl IntoIterator<Item = J>,
     ) -> Result<Vec<Option<DBPinnableSlice<'_>>>, TypedStoreError>
@@ -989,6 +3530,118 @@ impl<K, V> DBMap<K, V> {
         J: Borrow<K>,
         K: Serialize,
     {
+        let keys_bytes: Result<Vec<_>, _> = keys
+            .into_iter()
+            .map(|k| self.codec.encode_key(k.borrow()))
+            .collect();
+        self.multi_get_pinned_serialized(&keys_bytes?)
+    }
+
+    /// Returns a vector of values corresponding to the keys provided.
+    ///
+    /// Unlike [`Map::multi_get`], large key sets are split into chunks that are looked up and
+    /// deserialized in parallel on the rayon thread pool, each chunk with a single batched RocksDB
+    /// lookup. The result preserves the order of the keys, including duplicates. All chunks read
+    /// through a single snapshot taken before the lookups, so the values reflect one point in
+    /// time, like those of a single [`Map::multi_get`], regardless of concurrent writes.
+    pub fn par_multi_get<J>(
+        &self,
+        keys: impl IntoIterator<Item = J>,
+    ) -> Result<Vec<Option<V>>, TypedStoreError>
+    where
+        J: Borrow<K>,
+        K: Serialize,
+        V: DeserializeOwned + Send,
+    {
+        let keys_bytes: Result<Vec<_>, _> = keys
+            .into_iter()
+            .map(|k| self.codec.encode_key(k.borrow()))
+            .collect();
+        let keys_bytes = keys_bytes?;
+        if keys_bytes.len() <= PAR_MULTI_GET_CHUNK_SIZE {
+            return decode_multi_get_values(
+                &self.codec,
+                self.multi_get_pinned_serialized(&keys_bytes)?,
+            );
+        }
+        let snapshot = self.rocksdb.snapshot();
+        let chunks: Vec<_> = keys_bytes
+            .par_chunks(PAR_MULTI_GET_CHUNK_SIZE)
+            .map(|chunk| {
+                let mut readopts = self.opts.readopts();
+                snapshot.set_on(&self.rocksdb, &mut readopts)?;
+                decode_multi_get_values(
+                    &self.codec,
+                    self.multi_get_pinned_serialized_opt(chunk, &readopts)?,
+                )
+            })
+            .collect::<Result<_, TypedStoreError>>()?;
+        Ok(chunks.into_iter().flatten().collect())
+    }
+
+    /// Returns a vector of values corresponding to the keys provided, like [`Map::multi_get`], but
+    /// looks up and deserializes each distinct key only once.
+    ///
+    /// Values of keys that occur several times are cloned into each of their positions.
+    pub fn multi_get_dedup<J>(
+        &self,
+        keys: impl IntoIterator<Item = J>,
+    ) -> Result<Vec<Option<V>>, TypedStoreError>
+    where
+        J: Borrow<K>,
+        K: Serialize,
+        V: DeserializeOwned + Clone,
+    {
+        let mut unique_keys = vec![];
+        let mut unique_index = HashMap::new();
+        let positions = keys
+            .into_iter()
+            .map(|key| {
+                let key_buf = self.codec.encode_key(key.borrow())?;
+                Ok(*unique_index.entry(key_buf).or_insert_with_key(|key_buf| {
+                    unique_keys.push(key_buf.clone());
+                    unique_keys.len() - 1
+                }))
+            })
+            .collect::<Result<Vec<_>, TypedStoreError>>()?;
+        let values =
+            decode_multi_get_values(&self.codec, self.multi_get_pinned_serialized(&unique_keys)?)?;
+        Ok(positions
+            .into_iter()
+            .map(|position| values[position].clone())
+            .collect())
+    }
+
+    /// Returns the serialized values corresponding to the keys provided, in the same order as
+    /// the keys, without deserializing them.
+    pub fn multi_get_raw<J>(
+        &self,
+        keys: impl IntoIterator<Item = J>,
+    ) -> Result<Vec<Option<Vec<u8>>>, TypedStoreError>
+    where
+        J: Borrow<K>,
+        K: Serialize,
+    {
+        Ok(self
+            .multi_get_pinned(keys)?
+            .into_iter()
+            .map(|value| value.map(|data| data.to_vec()))
+            .collect())
+    }
+
+    fn multi_get_pinned_serialized(
+        &self,
+        keys_bytes: &[Vec<u8>],
+    ) -> Result<Vec<Option<DBPinnableSlice<'_>>>, TypedStoreError> {
+        self.multi_get_pinned_serialized_opt(keys_bytes, &self.opts.readopts())
+    }
+
+    fn multi_get_pinned_serialized_opt(
+        &self,
+        keys_bytes: &[Vec<u8>],
+        readopts: &ReadOptions,
+    ) -> Result<Vec<Option<DBPinnableSlice<'_>>>, TypedStoreError> {
+        let start = std::time::Instant::now();
         let _timer = self
             .db_metrics
             .op_metrics
@@ -1000,11 +3653,6 @@ impl<K, V> DBMap<K, V> {
         } else {
             None
         };
-        let keys_bytes: Result<Vec<_>, _> = keys
-            .into_iter()
-            .map(|k| be_fix_int_ser(k.borrow()))
-            .collect();
-        let keys_bytes = keys_bytes?;
         let keys_refs = keys_bytes.iter().collect::<Vec<&Vec<u8>>>();
         let results: Result<Vec<_>, TypedStoreError> = self
             .rocksdb
@@ -1012,7 +3660,7 @@ impl<K, V> DBMap<K, V> {
                 &self.cf()?,
                 keys_refs,
                 /*sorted_keys=*/ false,
-                &self.opts.readopts(),
+                readopts,
             )
             .into_iter()
             .map(|r| r.map_err(|e| TypedStoreError::RocksDBError(e.into_string())))
@@ -1032,6 +3680,8 @@ impl<K, V> DBMap<K, V> {
             self.db_metrics
                 .read_perf_ctx_metrics
                 .report_metrics(&self.cf);
+            self.db_metrics
+                .observe_sampled_op_latency(&self.cf, "multi_get", start.elapsed());
         }
         Ok(entries)
     }
@@ -1050,7 +3700,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_total_sst_files_size
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::TOTAL_SST_FILES_SIZE)
+                Self::get_i64_property(rocksdb, &cf, properties::TOTAL_SST_FILES_SIZE)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1058,7 +3708,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_total_blob_files_size
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, ROCKSDB_PROPERTY_TOTAL_BLOB_FILES_SIZE)
+                Self::get_i64_property(rocksdb, &cf, ROCKSDB_PROPERTY_TOTAL_BLOB_FILES_SIZE)
                     .unwrap_or(METRICS_ERROR),
             );
         // 7 is the default number of levels in RocksDB. If we ever change the number.
@@ -1066,7 +3716,7 @@ impl<K, V> DBMap<K, V> {
         // there isn't an API to query the DB to get the number of levels (yet).
         let total_num_files: i64 = (0..=6)
             .map(|level| {
-                Self::get_int_property(rocksdb, &cf, &num_files_at_level(level))
+                Self::get_i64_property(rocksdb, &cf, &num_files_at_level(level))
                     .unwrap_or(METRICS_ERROR)
             })
             .sum();
@@ -1080,7 +3730,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_num_level0_files
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, &num_files_at_level(0))
+                Self::get_i64_property(rocksdb, &cf, &num_files_at_level(0))
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1088,7 +3738,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_current_size_active_mem_tables
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::CUR_SIZE_ACTIVE_MEM_TABLE)
+                Self::get_i64_property(rocksdb, &cf, properties::CUR_SIZE_ACTIVE_MEM_TABLE)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1096,7 +3746,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_size_all_mem_tables
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::SIZE_ALL_MEM_TABLES)
+                Self::get_i64_property(rocksdb, &cf, properties::SIZE_ALL_MEM_TABLES)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1104,7 +3754,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_num_snapshots
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::NUM_SNAPSHOTS)
+                Self::get_i64_property(rocksdb, &cf, properties::NUM_SNAPSHOTS)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1112,7 +3762,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_oldest_snapshot_time
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::OLDEST_SNAPSHOT_TIME)
+                Self::get_i64_property(rocksdb, &cf, properties::OLDEST_SNAPSHOT_TIME)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1120,7 +3770,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_actual_delayed_write_rate
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::ACTUAL_DELAYED_WRITE_RATE)
+                Self::get_i64_property(rocksdb, &cf, properties::ACTUAL_DELAYED_WRITE_RATE)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1128,7 +3778,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_is_write_stopped
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::IS_WRITE_STOPPED)
+                Self::get_i64_property(rocksdb, &cf, properties::IS_WRITE_STOPPED)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1136,7 +3786,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_block_cache_capacity
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::BLOCK_CACHE_CAPACITY)
+                Self::get_i64_property(rocksdb, &cf, properties::BLOCK_CACHE_CAPACITY)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1144,7 +3794,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_block_cache_usage
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::BLOCK_CACHE_USAGE)
+                Self::get_i64_property(rocksdb, &cf, properties::BLOCK_CACHE_USAGE)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1152,7 +3802,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_block_cache_pinned_usage
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::BLOCK_CACHE_PINNED_USAGE)
+                Self::get_i64_property(rocksdb, &cf, properties::BLOCK_CACHE_PINNED_USAGE)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1160,7 +3810,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_estimate_table_readers_mem
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::ESTIMATE_TABLE_READERS_MEM)
+                Self::get_i64_property(rocksdb, &cf, properties::ESTIMATE_TABLE_READERS_MEM)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1168,7 +3818,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_estimated_num_keys
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::ESTIMATE_NUM_KEYS)
+                Self::get_i64_property(rocksdb, &cf, properties::ESTIMATE_NUM_KEYS)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1176,7 +3826,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_num_immutable_mem_tables
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::NUM_IMMUTABLE_MEM_TABLE)
+                Self::get_i64_property(rocksdb, &cf, properties::NUM_IMMUTABLE_MEM_TABLE)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1184,7 +3834,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_mem_table_flush_pending
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::MEM_TABLE_FLUSH_PENDING)
+                Self::get_i64_property(rocksdb, &cf, properties::MEM_TABLE_FLUSH_PENDING)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1192,7 +3842,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_compaction_pending
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::COMPACTION_PENDING)
+                Self::get_i64_property(rocksdb, &cf, properties::COMPACTION_PENDING)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1200,7 +3850,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_estimate_pending_compaction_bytes
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::ESTIMATE_PENDING_COMPACTION_BYTES)
+                Self::get_i64_property(rocksdb, &cf, properties::ESTIMATE_PENDING_COMPACTION_BYTES)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1208,7 +3858,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_num_running_compactions
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::NUM_RUNNING_COMPACTIONS)
+                Self::get_i64_property(rocksdb, &cf, properties::NUM_RUNNING_COMPACTIONS)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1216,7 +3866,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_num_running_flushes
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::NUM_RUNNING_FLUSHES)
+                Self::get_i64_property(rocksdb, &cf, properties::NUM_RUNNING_FLUSHES)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1224,7 +3874,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_estimate_oldest_key_time
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::ESTIMATE_OLDEST_KEY_TIME)
+                Self::get_i64_property(rocksdb, &cf, properties::ESTIMATE_OLDEST_KEY_TIME)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1232,7 +3882,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_background_errors
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::BACKGROUND_ERRORS)
+                Self::get_i64_property(rocksdb, &cf, properties::BACKGROUND_ERRORS)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1240,7 +3890,7 @@ impl<K, V> DBMap<K, V> {
             .rocksdb_base_level
             .with_label_values(&[cf_name])
             .set(
-                Self::get_int_property(rocksdb, &cf, properties::BASE_LEVEL)
+                Self::get_i64_property(rocksdb, &cf, properties::BASE_LEVEL)
                     .unwrap_or(METRICS_ERROR),
             );
         db_metrics
@@ -1327,41 +3977,461 @@ impl<K, V> DBMap<K, V> {
             .set(rocksdb.db_options().get_ticker_count(Ticker::BytesWritten) as i64);
     }
 
-    /// Create a checkpoint of the database.
-    pub fn checkpoint_db(&self, path: &Path) -> Result<(), TypedStoreError> {
-        self.rocksdb.checkpoint(path)
+    /// Create a checkpoint of the database.
+    pub fn checkpoint_db(&self, path: &Path) -> Result<(), TypedStoreError> {
+        self.rocksdb.checkpoint(path)
+    }
+
+    /// Create a checkpoint of the database, see [`RocksDB::checkpoint_with_options`].
+    pub fn checkpoint_db_with_options(
+        &self,
+        path: &Path,
+        overwrite: bool,
+    ) -> Result<(), TypedStoreError> {
+        self.rocksdb.checkpoint_with_options(path, overwrite)
+    }
+
+    /// Get a summary of the table.
+    pub fn table_summary(&self) -> eyre::Result<TableSummary>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let mut num_keys = 0;
+        let mut key_bytes_total = 0;
+        let mut value_bytes_total = 0;
+        let mut key_hist = hdrhistogram::Histogram::<u64>::new_with_max(100000, 2)
+            .expect("the function parameters are valid");
+        let mut value_hist = hdrhistogram::Histogram::<u64>::new_with_max(100000, 2)
+            .expect("the function parameters are valid");
+        for item in self.safe_iter()? {
+            let (key, value) = item?;
+            num_keys += 1;
+            let key_len = self.codec.encode_key(&key)?.len();
+            let value_len = self.codec.encode_value(&value)?.len();
+            key_bytes_total += key_len;
+            value_bytes_total += value_len;
+            key_hist.record(key_len as u64)?;
+            value_hist.record(value_len as u64)?;
+        }
+        Ok(TableSummary {
+            num_keys,
+            key_bytes_total,
+            value_bytes_total,
+            key_hist,
+            value_hist,
+        })
+    }
+
+    /// Returns the serialized value for the given key, skipping the value codec; the key is
+    /// serialized as usual.
+    ///
+    /// The bytes are the encoding of the value under the map's value codec, which can be stored
+    /// unchanged with [`DBMap::insert_raw`] in a map using the same codec.
+    pub fn get_raw(&self, key: &K) -> Result<Option<Vec<u8>>, TypedStoreError>
+    where
+        K: Serialize,
+    {
+        Ok(self
+            .get_serialized_with_readopts(key, &self.opts.readopts())?
+            .map(|data| data.to_vec()))
+    }
+
+    /// Returns the serialized value for the given key without copying it out of RocksDB.
+    ///
+    /// The returned slice pins the value in the memtable or block cache until it is dropped.
+    pub fn get_pinned(&self, key: &K) -> Result<Option<DBPinnableSlice<'_>>, TypedStoreError>
+    where
+        K: Serialize,
+    {
+        let key_buf = self.codec.encode_key(key)?;
+        self.rocksdb
+            .get_pinned_cf_opt(&self.cf()?, &key_buf, &self.opts.readopts())
+            .map_err(typed_store_err_from_rocks_err)
+    }
+
+    /// Returns the value for the given key as a [`PinnedValue`], which can be deserialized
+    /// directly from RocksDB's memory, including into types borrowing from it.
+    pub fn get_pinned_value(&self, key: &K) -> Result<Option<PinnedValue<'_, V>>, TypedStoreError>
+    where
+        K: Serialize,
+    {
+        Ok(self.get_pinned(key)?.map(|slice| PinnedValue {
+            slice,
+            value_codec: self.codec.value_codec(),
+        }))
+    }
+
+    /// Returns the value for the given key as seen through a snapshot.
+    ///
+    /// The snapshot must have been taken on the database backing this map.
+    pub fn get_with_snapshot(
+        &self,
+        key: &K,
+        snapshot: &DbSnapshot<'_>,
+    ) -> Result<Option<V>, TypedStoreError>
+    where
+        K: Serialize,
+        V: DeserializeOwned,
+    {
+        let mut readopts = self.opts.readopts();
+        snapshot.set_on(&self.rocksdb, &mut readopts)?;
+        self.get_with_readopts(key, &readopts)
+    }
+
+    /// Returns true if the map contains the key as seen through a snapshot.
+    ///
+    /// The snapshot must have been taken on the database backing this map. Like
+    /// [`DBMap::get_with_snapshot`], this checks the key's presence at the snapshot's point in
+    /// time, without deserializing the value.
+    pub fn contains_key_with_snapshot(
+        &self,
+        key: &K,
+        snapshot: &DbSnapshot<'_>,
+    ) -> Result<bool, TypedStoreError>
+    where
+        K: Serialize,
+    {
+        let key_buf = self.codec.encode_key(key)?;
+        let mut readopts = self.opts.readopts();
+        snapshot.set_on(&self.rocksdb, &mut readopts)?;
+        let pinned = self
+            .rocksdb
+            .get_pinned_cf_opt(&self.cf()?, &key_buf, &readopts)
+            .map_err(typed_store_err_from_rocks_err)?;
+        Ok(pinned.is_some())
     }
 
-    /// Get a summary of the table.
-    pub fn table_summary(&self) -> eyre::Result<TableSummary>
+    /// Creates a safe iterator over all entries of the map that yields
+    /// [`TypedStoreError::DeadlineExceeded`] and stops once `deadline` has passed.
+    ///
+    /// This bounds the time of scans on behalf of requests with a timeout. To keep the overhead
+    /// low, the deadline is only checked every few entries, so the iteration may run slightly past
+    /// it.
+    pub fn safe_iter_with_deadline(
+        &self,
+        deadline: Instant,
+    ) -> Result<SafeIter<'_, K, V>, TypedStoreError>
     where
         K: Serialize + DeserializeOwned,
         V: Serialize + DeserializeOwned,
     {
-        let mut num_keys = 0;
-        let mut key_bytes_total = 0;
-        let mut value_bytes_total = 0;
-        let mut key_hist = hdrhistogram::Histogram::<u64>::new_with_max(100000, 2)
-            .expect("the function parameters are valid");
-        let mut value_hist = hdrhistogram::Histogram::<u64>::new_with_max(100000, 2)
-            .expect("the function parameters are valid");
-        for item in self.safe_iter()? {
-            let (key, value) = item?;
-            num_keys += 1;
-            let key_len = be_fix_int_ser(key.borrow())?.len();
-            let value_len = bcs::to_bytes(value.borrow())?.len();
-            key_bytes_total += key_len;
-            value_bytes_total += value_len;
-            key_hist.record(key_len as u64)?;
-            value_hist.record(value_len as u64)?;
+        Ok(self.safe_iter()?.with_deadline(deadline))
+    }
+
+    /// Creates a safe iterator over the entries of the map as seen through a snapshot.
+    ///
+    /// The snapshot must have been taken on the database backing this map.
+    pub fn safe_iter_with_snapshot<'b>(
+        &'b self,
+        snapshot: &'b DbSnapshot<'_>,
+    ) -> Result<SafeIter<'b, K, V>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let mut readopts = self.opts.readopts();
+        snapshot.set_on(&self.rocksdb, &mut readopts)?;
+        let db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
+        Ok(self.new_safe_iter(db_iter))
+    }
+
+    /// Creates a safe iterator over the entries of the map as of a snapshot taken now.
+    ///
+    /// The iterator holds the snapshot until it is dropped, so all entries it returns reflect a
+    /// single point in time, regardless of concurrent writes. The snapshot is available through
+    /// [`SafeIter::snapshot`] for consistent point reads alongside the scan. Holding it prevents
+    /// compactions from dropping data overwritten in the meantime, so long scans retain more data.
+    pub fn snapshot_iter(&self) -> Result<SafeIter<'_, K, V>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let snapshot = self.rocksdb.snapshot();
+        let mut readopts = self.opts.readopts();
+        snapshot.set_on(&self.rocksdb, &mut readopts)?;
+        let db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
+        Ok(self.new_safe_iter(db_iter).with_snapshot(snapshot))
+    }
+
+    /// Creates an iterator over the entries of the map in key order, yielding each key as the
+    /// exact bytes stored in RocksDB along with the decoded value.
+    ///
+    /// The key bytes decode to the keys yielded by [`Map::safe_iter`], but serializing a decoded
+    /// key is not guaranteed to reproduce them, e.g., with a custom key codec, so this is the way
+    /// to refer to stored keys from outside the map. The iterator stops after the first error.
+    pub fn safe_iter_raw_keys(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<(Vec<u8>, V), TypedStoreError>> + '_, TypedStoreError>
+    where
+        V: DeserializeOwned,
+    {
+        let mut db_iter = self
+            .rocksdb
+            .raw_iterator_cf(&self.cf()?, self.opts.readopts());
+        db_iter.seek_to_first();
+        let mut failed = false;
+        Ok(std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            let (Some(key), Some(value)) = (db_iter.key(), db_iter.value()) else {
+                failed = true;
+                return Some(Err(typed_store_err_from_rocks_err(db_iter.status().err()?)));
+            };
+            let entry = self
+                .codec
+                .decode_value(value)
+                .map(|value| (key.to_vec(), value));
+            failed = entry.is_err();
+            db_iter.next();
+            Some(entry)
+        }))
+    }
+
+    /// Streams the entries of the map in the same order as [`Map::safe_iter`].
+    ///
+    /// The iterator runs on Tokio's blocking thread pool and hands the entries over in chunks
+    /// through a bounded channel, so consuming the stream never blocks the async runtime and the
+    /// iterator only runs a few chunks ahead of the consumer. The blocking thread is released once
+    /// the iterator is exhausted or the stream is dropped. Must be called within a Tokio runtime.
+    ///
+    /// Unlike [`AsyncDBMap::stream`](async_map::AsyncDBMap::stream), the number of concurrent
+    /// blocking iterators is not bounded.
+    pub fn safe_stream(&self) -> impl Stream<Item = Result<(K, V), TypedStoreError>> + 'static
+    where
+        K: Serialize + DeserializeOwned + Send + 'static,
+        V: Serialize + DeserializeOwned + Send + 'static,
+    {
+        async_map::stream_blocking(self.clone(), ())
+    }
+
+    /// Returns the value for the given key, reading it on Tokio's blocking thread pool.
+    ///
+    /// Unlike [`AsyncDBMap`](async_map::AsyncDBMap), the number of concurrent blocking reads is
+    /// not bounded.
+    pub async fn get_async(&self, key: K) -> Result<Option<V>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned + Send + 'static,
+        V: Serialize + DeserializeOwned + Send + 'static,
+    {
+        async_map::run_blocking(self.clone(), (), move |map| map.get(&key)).await
+    }
+
+    /// Inserts the key-value pair, writing it on Tokio's blocking thread pool.
+    pub async fn insert_async(&self, key: K, value: V) -> Result<(), TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned + Send + 'static,
+        V: Serialize + DeserializeOwned + Send + 'static,
+    {
+        async_map::run_blocking(self.clone(), (), move |map| map.insert(&key, &value)).await
+    }
+
+    /// Returns the values for the given keys, in the same order as the keys, reading them on
+    /// Tokio's blocking thread pool.
+    pub async fn multi_get_async(&self, keys: Vec<K>) -> Result<Vec<Option<V>>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned + Send + 'static,
+        V: Serialize + DeserializeOwned + Send + 'static,
+    {
+        async_map::run_blocking(self.clone(), (), move |map| map.multi_get(keys)).await
+    }
+
+    /// Returns the value for the given key, reading it with the given read options.
+    ///
+    /// With [`ReadTier::CacheOnly`], this fails with [`TypedStoreError::NotInCache`] if the value
+    /// would have to be read from disk.
+    pub fn get_with_read_options(
+        &self,
+        key: &K,
+        opts: &DBReadOptions,
+    ) -> Result<Option<V>, TypedStoreError>
+    where
+        K: Serialize,
+        V: DeserializeOwned,
+    {
+        let mut readopts = self.opts.readopts();
+        opts.set_on(&mut readopts);
+        self.get_with_readopts(key, &readopts)
+    }
+
+    /// Creates a safe iterator over the entries of the map, reading them with the given read
+    /// options.
+    pub fn safe_iter_with_read_options(
+        &self,
+        opts: &DBReadOptions,
+    ) -> Result<SafeIter<'_, K, V>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let mut readopts = self.opts.readopts();
+        opts.set_on(&mut readopts);
+        let db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
+        Ok(self.new_safe_iter(db_iter))
+    }
+
+    /// Creates a safe iterator over all entries of the map that reads ahead `readahead_bytes`
+    /// from the SST files whenever it reads a block from disk.
+    ///
+    /// Readahead turns the many small block reads of a sequential scan into few large reads,
+    /// which improves the throughput of long scans on devices with a high latency per read, such
+    /// as spinning disks. Each iterator buffers up to `readahead_bytes` per SST file it reads, so
+    /// large values across many concurrent iterators cost a corresponding amount of memory, and
+    /// short scans waste the read-ahead data. A value of 0 disables readahead.
+    pub fn safe_iter_with_readahead(
+        &self,
+        readahead_bytes: usize,
+    ) -> Result<SafeIter<'_, K, V>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        self.safe_range_iter_with_readahead(.., readahead_bytes)
+    }
+
+    /// Creates a safe iterator over the entries with keys in `range`, with readahead like
+    /// [`DBMap::safe_iter_with_readahead`].
+    pub fn safe_range_iter_with_readahead(
+        &self,
+        range: impl RangeBounds<K>,
+        readahead_bytes: usize,
+    ) -> Result<SafeIter<'_, K, V>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let mut readopts = self.create_read_options_with_range(range)?;
+        readopts.set_readahead_size(readahead_bytes);
+        let db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
+        Ok(self.new_safe_iter(db_iter))
+    }
+
+    fn get_with_readopts(
+        &self,
+        key: &K,
+        readopts: &ReadOptions,
+    ) -> Result<Option<V>, TypedStoreError>
+    where
+        K: Serialize,
+        V: DeserializeOwned,
+    {
+        self.get_serialized_with_readopts(key, readopts)?
+            .map(|data| self.codec.decode_value(&data))
+            .transpose()
+    }
+
+    /// Reads back the value of a key written by `op`, if read-after-write verification is enabled,
+    /// and checks that it is the written value, where `None` means that the key was removed.
+    ///
+    /// Must be called while holding the key's lock, so that other writes through this database
+    /// cannot interleave. The value is read from the same column family it was written to, so
+    /// this cannot detect writes to the wrong column family.
+    fn verify_point_write(
+        &self,
+        op: &str,
+        key_buf: &[u8],
+        expected: Option<&[u8]>,
+    ) -> Result<(), TypedStoreError> {
+        if !self.rocksdb.verifies_writes() {
+            return Ok(());
+        }
+        let actual = self
+            .rocksdb
+            .get_pinned_cf_opt(&self.cf()?, key_buf, &ReadOptions::default())
+            .map_err(typed_store_err_from_rocks_err)?;
+        if actual.as_deref() == expected {
+            return Ok(());
+        }
+        Err(TypedStoreError::VerifyFailed(format!(
+            "{op} of key {key_buf:02x?} in column family {}: expected {}, read back {}",
+            self.cf,
+            describe_value(expected),
+            describe_value(actual.as_deref()),
+        )))
+    }
+
+    fn get_serialized_with_readopts(
+        &self,
+        key: &K,
+        readopts: &ReadOptions,
+    ) -> Result<Option<DBPinnableSlice<'_>>, TypedStoreError>
+    where
+        K: Serialize,
+    {
+        let start = std::time::Instant::now();
+        let perf_ctx = if self.get_sample_interval.sample() {
+            Some(RocksDBPerfContext)
+        } else {
+            None
+        };
+        let key_buf = self.codec.encode_key(key)?;
+        let res = self
+            .rocksdb
+            .get_pinned_cf_opt(&self.cf()?, &key_buf, readopts)
+            .map_err(typed_store_err_from_rocks_err)?;
+        self.rocksdb
+            .report_if_slow("get", Some(&self.cf), key_buf.len(), start.elapsed());
+        let found = res.is_some();
+        self.db_metrics
+            .op_metrics
+            .rocksdb_get_latency_seconds
+            .with_label_values(&[&self.cf, &found.to_string()])
+            .observe(start.elapsed().as_secs_f64());
+        self.db_metrics
+            .op_metrics
+            .rocksdb_get_key_bytes
+            .with_label_values(&[&self.cf])
+            .observe(key_buf.len() as f64);
+        self.db_metrics
+            .op_metrics
+            .rocksdb_get_bytes
+            .with_label_values(&[&self.cf])
+            .observe(key_buf.len() as f64 + res.as_ref().map_or(0.0, |v| v.len() as f64));
+        self.db_metrics
+            .op_metrics
+            .rocksdb_get_value_bytes
+            .with_label_values(&[&self.cf])
+            .observe(res.as_ref().map_or(0.0, |v| v.len() as f64));
+        if perf_ctx.is_some() {
+            self.db_metrics
+                .read_perf_ctx_metrics
+                .report_metrics(&self.cf);
+            self.db_metrics
+                .observe_sampled_op_latency(&self.cf, "get", start.elapsed());
+        }
+        Ok(res)
+    }
+
+    /// Creates a safe iterator over the entries of the raw iterator, which must iterate over the
+    /// map's column family.
+    fn new_safe_iter<'b>(&'b self, db_iter: RocksDBRawIter<'b>) -> SafeIter<'b, K, V>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+    {
+        SafeIter::new(
+            self.cf.clone(),
+            db_iter,
+            self.create_iter_context(),
+            Some(self.db_metrics.clone()),
+            self.codec.clone(),
+        )
+        .with_comparator(self.rocksdb.comparator(&self.cf))
+    }
+
+    /// Fails if the column family orders its keys with a custom [`Comparator`], for operations
+    /// that derive keys assuming the default bytewise order.
+    fn ensure_bytewise_order(&self, operation: &str) -> Result<(), TypedStoreError> {
+        match self.rocksdb.comparator(&self.cf) {
+            Some(comparator) => Err(TypedStoreError::RocksDBError(format!(
+                "{operation} require the default bytewise key order, but column family {} is \
+                ordered by comparator {}",
+                self.cf,
+                comparator.name()
+            ))),
+            None => Ok(()),
         }
-        Ok(TableSummary {
-            num_keys,
-            key_bytes_total,
-            value_bytes_total,
-            key_hist,
-            value_hist,
-        })
     }
 
     // Creates metrics and context for tracking an iterator usage and performance.
@@ -1414,24 +4484,71 @@ impl<K, V> DBMap<K, V> {
         &self,
         lower_bound: Option<K>,
         upper_bound: Option<K>,
-    ) -> ReadOptions
+    ) -> Result<ReadOptions, TypedStoreError>
     where
         K: Serialize,
     {
         let mut readopts = self.opts.readopts();
         if let Some(lower_bound) = lower_bound {
-            let key_buf = be_fix_int_ser(&lower_bound).expect("serialization must not fail");
+            let key_buf = self.codec.encode_key(&lower_bound)?;
             readopts.set_iterate_lower_bound(key_buf);
         }
         if let Some(upper_bound) = upper_bound {
-            let key_buf = be_fix_int_ser(&upper_bound).expect("serialization must not fail");
+            let key_buf = self.codec.encode_key(&upper_bound)?;
             readopts.set_iterate_upper_bound(key_buf);
         }
-        readopts
+        Ok(readopts)
+    }
+
+    /// Creates a safe iterator over the keys within the given bounds, each of which may be
+    /// inclusive, exclusive, or unbounded.
+    ///
+    /// Unlike the `Option` bounds of [`Map::safe_iter_with_bounds`], this distinguishes iterating
+    /// from the very start from iterating from the smallest key, and supports starting strictly
+    /// after a key. An excluded lower bound at the largest key yields no entries.
+    pub fn safe_iter_with_range_bounds(
+        &self,
+        lower_bound: Bound<K>,
+        upper_bound: Bound<K>,
+    ) -> Result<SafeIter<'_, K, V>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        self.safe_range_iter((lower_bound, upper_bound))
+    }
+
+    /// Creates a safe iterator with optional bounds, both of which are inclusive.
+    ///
+    /// Unlike [`Map::safe_iter_with_bounds`], the entry at `upper_bound` is yielded if present.
+    /// Fails for an upper bound on a column family with a custom [`Comparator`].
+    pub fn safe_iter_with_bounds_inclusive(
+        &self,
+        lower_bound: Option<K>,
+        upper_bound: Option<K>,
+    ) -> Result<SafeIter<'_, K, V>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let mut readopts = self.opts.readopts();
+        if let Some(lower_bound) = lower_bound {
+            readopts.set_iterate_lower_bound(self.codec.encode_key(&lower_bound)?);
+        }
+        if let Some(upper_bound) = upper_bound {
+            self.ensure_bytewise_order("included upper bounds")?;
+            readopts.set_iterate_upper_bound(inclusive_upper_bound(
+                self.codec.encode_key(&upper_bound)?,
+            ));
+        }
+        let db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
+        Ok(self.new_safe_iter(db_iter))
     }
 
     /// Creates a safe reversed iterator with optional bounds.
-    /// Upper bound is included.
+    ///
+    /// Both bounds are included: the iterator starts at the greatest key less than or equal to
+    /// `upper_bound` and stops after the smallest key greater than or equal to `lower_bound`.
     pub fn reversed_safe_iter_with_bounds(
         &self,
         lower_bound: Option<K>,
@@ -1439,33 +4556,109 @@ impl<K, V> DBMap<K, V> {
     ) -> Result<SafeRevIter<'_, K, V>, TypedStoreError>
     where
         K: Serialize + DeserializeOwned,
-        V: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let upper_bound_key = upper_bound.as_ref().map(|k| self.codec.encode_key(k));
+        let readopts = self.create_read_options_with_range((
+            lower_bound
+                .as_ref()
+                .map(Bound::Included)
+                .unwrap_or(Bound::Unbounded),
+            upper_bound
+                .as_ref()
+                .map(Bound::Included)
+                .unwrap_or(Bound::Unbounded),
+        ))?;
+
+        let db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
+        let iter = self.new_safe_iter(db_iter);
+        Ok(SafeRevIter::new(iter, upper_bound_key.transpose()?))
+    }
+
+    /// Creates a safe iterator over the entries in `range`, in descending key order.
+    ///
+    /// The bounds of the range are handled the same way as by [`Map::safe_range_iter`].
+    pub fn safe_range_iter_rev(
+        &self,
+        range: impl RangeBounds<K>,
+    ) -> Result<SafeRevIter<'_, K, V>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        let readopts = self.create_read_options_with_range(range)?;
+        let db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
+        let iter = self.new_safe_iter(db_iter);
+        // The iterator's upper bound already excludes everything after the range.
+        Ok(SafeRevIter::new(iter, None))
+    }
+
+    /// Returns a page of up to `limit` entries with keys strictly greater than `after`, in key
+    /// order, starting from the first entry if `after` is `None`.
+    ///
+    /// Also returns the continuation key to pass as `after` to fetch the next page, or `None` if
+    /// there are no further entries. The continuation key does not need to be present in the map
+    /// anymore when the next page is fetched, e.g., if it was removed in between. A `limit` of zero
+    /// returns an empty page without a continuation key.
+    pub fn scan_page(
+        &self,
+        after: Option<K>,
+        limit: usize,
+    ) -> Result<(Vec<(K, V)>, Option<K>), TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned + Clone,
+        V: Serialize + DeserializeOwned,
+    {
+        let lower_bound = match &after {
+            Some(after) => Bound::Excluded(after),
+            None => Bound::Unbounded,
+        };
+        if limit == 0 {
+            return Ok((vec![], None));
+        }
+        let mut iter = self.safe_range_iter((lower_bound, Bound::Unbounded))?;
+        let page = iter.by_ref().take(limit).collect::<Result<Vec<_>, _>>()?;
+        let continuation = if iter.peek()?.is_some() {
+            page.last().map(|(key, _)| key.clone())
+        } else {
+            None
+        };
+        Ok((page, continuation))
+    }
+
+    /// Returns up to `limit` keys in `range`, in key order.
+    ///
+    /// Only the keys are decoded, and the scan stops as soon as `limit` keys are found.
+    pub fn get_keys_in_range(
+        &self,
+        range: impl RangeBounds<K>,
+        limit: usize,
+    ) -> Result<Vec<K>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
     {
-        let upper_bound_key = upper_bound.as_ref().map(|k| be_fix_int_ser(&k));
-        let readopts = self.create_read_options_with_range((
-            lower_bound
-                .as_ref()
-                .map(Bound::Included)
-                .unwrap_or(Bound::Unbounded),
-            upper_bound
-                .as_ref()
-                .map(Bound::Included)
-                .unwrap_or(Bound::Unbounded),
-        ));
-
-        let db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
-        let iter_context = self.create_iter_context();
-        let iter = SafeIter::new(
-            self.cf.clone(),
-            db_iter,
-            iter_context,
-            Some(self.db_metrics.clone()),
-        );
-        Ok(SafeRevIter::new(iter, upper_bound_key.transpose()?))
+        let mut keys = Vec::new();
+        if limit == 0 {
+            return Ok(keys);
+        }
+        let readopts = self.create_read_options_with_range(range)?;
+        let mut db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
+        db_iter.seek_to_first();
+        while keys.len() < limit
+            && let Some(raw_key) = db_iter.key()
+        {
+            keys.push(self.codec.decode_key(raw_key)?);
+            db_iter.next();
+        }
+        db_iter.status().map_err(typed_store_err_from_rocks_err)?;
+        Ok(keys)
     }
 
     // Creates a RocksDB read option with lower and upper bounds set corresponding to `range`.
-    fn create_read_options_with_range(&self, range: impl RangeBounds<K>) -> ReadOptions
+    fn create_read_options_with_range(
+        &self,
+        range: impl RangeBounds<K>,
+    ) -> Result<ReadOptions, TypedStoreError>
     where
         K: Serialize,
     {
@@ -1477,43 +4670,90 @@ impl<K, V> DBMap<K, V> {
         match lower_bound {
             Bound::Included(lower_bound) => {
                 // Rocksdb lower bound is inclusive by default so nothing to do.
-                let key_buf = be_fix_int_ser(&lower_bound).expect("serialization must not fail");
+                let key_buf = self.codec.encode_key(lower_bound)?;
                 readopts.set_iterate_lower_bound(key_buf);
             }
             Bound::Excluded(lower_bound) => {
-                let mut key_buf =
-                    be_fix_int_ser(&lower_bound).expect("serialization must not fail");
+                self.ensure_bytewise_order("excluded lower bounds")?;
+                let key_buf = self.codec.encode_key(lower_bound)?;
 
-                // Since we want exclusive, we need to increment the key to exclude the previous.
-                big_endian_saturating_add_one(&mut key_buf);
-                readopts.set_iterate_lower_bound(key_buf);
+                // Since we want exclusive, we start at the smallest key after the bound, which
+                // also excludes everything if the bound is the largest key.
+                readopts.set_iterate_lower_bound(inclusive_upper_bound(key_buf));
             }
             Bound::Unbounded => (),
         };
 
         match upper_bound {
             Bound::Included(upper_bound) => {
-                let mut key_buf =
-                    be_fix_int_ser(&upper_bound).expect("serialization must not fail");
-
-                // If the key is already at the limit, there's nowhere else to go,
-                // so no upper bound.
-                if !is_max(&key_buf) {
-                    // Since we want exclusive, we need to increment the key to
-                    // get the upper bound.
-                    big_endian_saturating_add_one(&mut key_buf);
-                    readopts.set_iterate_upper_bound(key_buf);
-                }
+                self.ensure_bytewise_order("included upper bounds")?;
+                let key_buf = self.codec.encode_key(upper_bound)?;
+
+                // Since RocksDB upper bounds are exclusive, we stop at the smallest key after the
+                // bound.
+                readopts.set_iterate_upper_bound(inclusive_upper_bound(key_buf));
             }
             Bound::Excluded(upper_bound) => {
                 // Rocksdb upper bound is inclusive by default so nothing to do.
-                let key_buf = be_fix_int_ser(&upper_bound).expect("serialization must not fail");
+                let key_buf = self.codec.encode_key(upper_bound)?;
                 readopts.set_iterate_upper_bound(key_buf);
             }
             Bound::Unbounded => (),
         };
 
-        readopts
+        Ok(readopts)
+    }
+}
+
+impl<A, B, V> DBMap<(A, B), V> {
+    /// Returns an iterator over all entries whose key has `first` as its first component, in key
+    /// order.
+    ///
+    /// The default key encoding serializes a tuple as the concatenation of its components, so the
+    /// entries sharing the first component form a contiguous range of serialized keys. Fails for
+    /// maps opened with a custom key codec and for column families with a custom [`Comparator`].
+    pub fn seek_range_by_first(&self, first: &A) -> Result<SafeIter<'_, (A, B), V>, TypedStoreError>
+    where
+        A: Serialize + DeserializeOwned,
+        B: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        if !self.codec.has_default_key_codec() {
+            return Err(TypedStoreError::SerializationError(
+                "ranges by the first key component require the default key codec".into(),
+            ));
+        }
+        self.ensure_bytewise_order("ranges by the first key component")?;
+        let prefix = be_fix_int_ser(first)?;
+        let mut readopts = self.opts.readopts();
+        // There is no key after all keys sharing the largest possible prefix.
+        if !is_max(&prefix) {
+            let mut upper_bound = prefix.clone();
+            big_endian_saturating_add_one(&mut upper_bound);
+            readopts.set_iterate_upper_bound(upper_bound);
+        }
+        readopts.set_iterate_lower_bound(prefix);
+        let db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
+        Ok(self.new_safe_iter(db_iter))
+    }
+}
+
+impl<K, T> DBMap<K, Vec<T>> {
+    /// Appends `items` to the list stored under `key`, without reading the existing list.
+    ///
+    /// Requires the column family to be opened with [`MergeOperator::append`].
+    pub fn append(&self, key: &K, items: &[T]) -> Result<(), TypedStoreError>
+    where
+        K: Serialize,
+        T: Serialize,
+    {
+        let key_buf = self.codec.encode_key(key)?;
+        let operand_buf = bcs::to_bytes(items).map_err(typed_store_err_from_bcs_err)?;
+        self.rocksdb.ensure_writable()?;
+        let _guard = self.rocksdb.lock_key(&self.cf, &key_buf);
+        self.rocksdb
+            .merge_cf(&self.cf()?, &key_buf, &operand_buf, &self.opts.writeopts())
+            .map_err(typed_store_err_from_rocks_err)
     }
 }
 
@@ -1585,6 +4825,8 @@ pub struct DBBatch {
     db_metrics: Arc<DBMetrics>,
     /// The sampling interval for write operations.
     write_sample_interval: SamplingInterval,
+    /// The key locks taken while writing the batch.
+    key_locks: BatchKeyLocks,
 }
 
 impl fmt::Debug for DBBatch {
@@ -1610,12 +4852,41 @@ impl DBBatch {
             opts,
             db_metrics: db_metrics.clone(),
             write_sample_interval: write_sample_interval.clone(),
+            key_locks: BatchKeyLocks::default(),
         }
     }
 
+    /// Records that the batch writes `key` in column family `cf`, so that writing the batch locks
+    /// the key, see [`RocksDB::lock_key`].
+    fn record_key(&mut self, cf: &str, key: &[u8]) {
+        let stripe = self.rocksdb.key_stripe(cf, key);
+        self.key_locks.record_key(cf, stripe);
+    }
+
+    /// Records that the batch deletes a range of keys in column family `cf`, so that writing the
+    /// batch excludes all other writes to the column family.
+    fn record_range(&mut self, cf: &str) {
+        self.key_locks.record_range(cf);
+    }
+
+    /// Consumes the batch and writes it without taking the locks of its keys.
+    ///
+    /// Only for callers that already hold the locks of all keys that concurrent writers may
+    /// write, as taking further locks while holding some could deadlock.
+    pub(super) fn write_without_key_locks(mut self) -> Result<(), TypedStoreError> {
+        self.key_locks = BatchKeyLocks::default();
+        self.write_and_clear()
+    }
+
     /// Consume the batch and write its operations to the database.
     #[tracing::instrument(level = "trace", skip_all, err)]
-    pub fn write(self) -> Result<(), TypedStoreError> {
+    pub fn write(mut self) -> Result<(), TypedStoreError> {
+        self.write_and_clear()
+    }
+
+    /// Writes the operations of the batch to the database and clears it, so that it can be
+    /// reused. On failure, the operations are discarded as well.
+    fn write_and_clear(&mut self) -> Result<(), TypedStoreError> {
         let db_name = self.rocksdb.db_name();
         let timer = self
             .db_metrics
@@ -1630,7 +4901,12 @@ impl DBBatch {
         } else {
             None
         };
-        self.rocksdb.write(self.batch, &self.opts)?;
+        let empty = self.batch.empty();
+        let batch = std::mem::replace(&mut self.batch, empty);
+        let key_locks = std::mem::take(&mut self.key_locks);
+        let guards = self.rocksdb.lock_batch_keys(&key_locks);
+        self.rocksdb.write(batch, &self.opts)?;
+        drop(guards);
         self.db_metrics
             .op_metrics
             .rocksdb_batch_commit_bytes
@@ -1643,6 +4919,12 @@ impl DBBatch {
                 .report_metrics(&db_name);
         }
         let elapsed = timer.stop_and_record();
+        self.rocksdb.report_if_slow(
+            "batch_write",
+            None,
+            batch_size,
+            Duration::from_secs_f64(elapsed),
+        );
         if elapsed > 1.0 {
             tracing::warn!(?elapsed, ?db_name, "very slow batch write");
             self.db_metrics
@@ -1659,13 +4941,149 @@ impl DBBatch {
         Ok(())
     }
 
+    /// Consumes the batch and writes it like [`DBBatch::write`], on Tokio's blocking thread pool.
+    ///
+    /// Committing a large batch can take a while, which would otherwise block the async runtime.
+    pub async fn write_async(self) -> Result<(), TypedStoreError> {
+        tokio::task::spawn_blocking(move || self.write()).await?
+    }
+
+    /// Consumes the batch and writes it with the given write options instead of its own.
+    ///
+    /// See [`DBWriteOptions`] for the durability of the write.
+    pub fn write_with_options(mut self, opts: &DBWriteOptions) -> Result<(), TypedStoreError> {
+        self.opts = opts.writeopts();
+        self.write()
+    }
+
     /// Get the size of the batch in bytes.
+    ///
+    /// This is the size of the serialized batch, including a fixed-size header, so an empty batch
+    /// has a non-zero size.
     pub fn size_in_bytes(&self) -> usize {
         self.batch.size_in_bytes()
     }
+
+    /// Get the number of operations in the batch.
+    ///
+    /// Every inserted, deleted, or merged key counts as one operation, as does every scheduled
+    /// range deletion.
+    pub fn len(&self) -> usize {
+        self.batch.len()
+    }
+
+    /// Returns true if the batch contains no operations.
+    pub fn is_empty(&self) -> bool {
+        self.batch.is_empty()
+    }
+
+    /// Discards all operations and save points of the batch, resetting its length and size.
+    pub fn clear(&mut self) {
+        self.batch.clear();
+        self.key_locks = BatchKeyLocks::default();
+    }
+
+    /// Records the current state of the batch, to which it can later be rolled back.
+    ///
+    /// Save points nest: each call pushes a new save point.
+    pub fn set_save_point(&mut self) {
+        self.batch.set_save_point();
+    }
+
+    /// Discards all operations added since the most recent save point and removes that save
+    /// point.
+    ///
+    /// Fails with [`TypedStoreError::NoSavePoint`] if no save point is set.
+    pub fn rollback_to_save_point(&mut self) -> Result<(), TypedStoreError> {
+        self.batch
+            .rollback_to_save_point()
+            .map_err(|_| TypedStoreError::NoSavePoint)
+    }
+
+    /// Removes the most recent save point without discarding any operations.
+    ///
+    /// Fails with [`TypedStoreError::NoSavePoint`] if no save point is set.
+    pub fn pop_save_point(&mut self) -> Result<(), TypedStoreError> {
+        self.batch
+            .pop_save_point()
+            .map_err(|_| TypedStoreError::NoSavePoint)
+    }
+}
+
+/// A write operation on a single key, for assembling a [`DBBatch`] from an iterator with
+/// [`DBBatch::from_ops`] or [`DBBatch::apply_ops`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOp<K, V> {
+    /// Inserts the value for the key, replacing any existing value.
+    Insert(K, V),
+    /// Deletes the key.
+    Delete(K),
 }
 
 impl DBBatch {
+    /// Creates a batch on the database of `db` inserting the given (key, value) pairs.
+    pub fn from_inserts<J: Borrow<K>, K: Serialize, U: Borrow<V>, V: Serialize>(
+        db: &DBMap<K, V>,
+        new_vals: impl IntoIterator<Item = (J, U)>,
+    ) -> Result<Self, TypedStoreError> {
+        let mut batch = db.batch();
+        batch.insert_batch(db, new_vals)?;
+        Ok(batch)
+    }
+
+    /// Creates a batch on the database of `db` with the given operations on `db`, in order.
+    pub fn from_ops<K: Serialize, V: Serialize>(
+        db: &DBMap<K, V>,
+        ops: impl IntoIterator<Item = BatchOp<K, V>>,
+    ) -> Result<Self, TypedStoreError> {
+        let mut batch = db.batch();
+        batch.apply_ops(db, ops)?;
+        Ok(batch)
+    }
+
+    /// Adds the given operations on `db` to the batch, in order, so that a later operation on a
+    /// key takes precedence over an earlier one.
+    pub fn apply_ops<K: Serialize, V: Serialize>(
+        &mut self,
+        db: &DBMap<K, V>,
+        ops: impl IntoIterator<Item = BatchOp<K, V>>,
+    ) -> Result<&mut Self, TypedStoreError> {
+        if !Arc::ptr_eq(&db.rocksdb, &self.rocksdb) {
+            return Err(TypedStoreError::CrossDBBatch);
+        }
+        let cf = db.cf()?;
+        let mut key_total = 0usize;
+        let mut value_total = 0usize;
+        for op in ops {
+            match op {
+                BatchOp::Insert(k, v) => {
+                    let k_buf = db.codec.encode_key(&k)?;
+                    let v_buf = db.codec.encode_value(&v)?;
+                    key_total += k_buf.len();
+                    value_total += v_buf.len();
+                    self.record_key(&db.cf, &k_buf);
+                    self.batch.put_cf(&cf, k_buf, v_buf);
+                }
+                BatchOp::Delete(k) => {
+                    let k_buf = db.codec.encode_key(&k)?;
+                    self.record_key(&db.cf, &k_buf);
+                    self.batch.delete_cf(&cf, k_buf);
+                }
+            }
+        }
+        self.db_metrics
+            .op_metrics
+            .rocksdb_batch_put_key_bytes
+            .with_label_values(&[&db.cf])
+            .observe(key_total as f64);
+        self.db_metrics
+            .op_metrics
+            .rocksdb_batch_put_value_bytes
+            .with_label_values(&[&db.cf])
+            .observe(value_total as f64);
+        Ok(self)
+    }
+
     /// Delete a batch of keys.
     pub fn delete_batch<J: Borrow<K>, K: Serialize, V>(
         &mut self,
@@ -1679,7 +5097,8 @@ impl DBBatch {
         purged_vals
             .into_iter()
             .try_for_each::<_, Result<_, TypedStoreError>>(|k| {
-                let k_buf = be_fix_int_ser(k.borrow())?;
+                let k_buf = db.codec.encode_key(k.borrow())?;
+                self.record_key(&db.cf, &k_buf);
                 self.batch.delete_cf(&db.cf()?, k_buf);
 
                 Ok(())
@@ -1707,14 +5126,34 @@ impl DBBatch {
             return Err(TypedStoreError::CrossDBBatch);
         }
 
-        let from_buf = be_fix_int_ser(from)?;
-        let to_buf = be_fix_int_ser(to)?;
+        let from_buf = db.codec.encode_key(from)?;
+        let to_buf = db.codec.encode_key(to)?;
 
         self.batch
             .delete_range_cf(&db.cf()?, from_buf, to_buf, cap)?;
+        self.record_range(&db.cf);
         Ok(())
     }
 
+    /// Deletes the keys between `from` (inclusive) and `to` (exclusive), like
+    /// [`Self::schedule_delete_range`], returning the batch for chaining.
+    ///
+    /// Fails if the database does not support range deletes.
+    pub fn delete_range_batch<K: Serialize, V>(
+        &mut self,
+        db: &DBMap<K, V>,
+        from: &K,
+        to: &K,
+    ) -> Result<&mut Self, TypedStoreError> {
+        let cap = db.rocksdb.as_range_delete().ok_or_else(|| {
+            TypedStoreError::RocksDBError(
+                "range deletes require the standard RocksDB engine".into(),
+            )
+        })?;
+        self.schedule_delete_range(db, from, to, &cap)?;
+        Ok(self)
+    }
+
     /// inserts a range of (key, value) pairs given as an iterator.
     pub fn insert_batch<J: Borrow<K>, K: Serialize, U: Borrow<V>, V: Serialize>(
         &mut self,
@@ -1729,10 +5168,11 @@ impl DBBatch {
         new_vals
             .into_iter()
             .try_for_each::<_, Result<_, TypedStoreError>>(|(k, v)| {
-                let k_buf = be_fix_int_ser(k.borrow())?;
-                let v_buf = bcs::to_bytes(v.borrow()).map_err(typed_store_err_from_bcs_err)?;
+                let k_buf = db.codec.encode_key(k.borrow())?;
+                let v_buf = db.codec.encode_value(v.borrow())?;
                 key_total += k_buf.len();
                 value_total += v_buf.len();
+                self.record_key(&db.cf, &k_buf);
                 self.batch.put_cf(&db.cf()?, k_buf, v_buf);
                 Ok(())
             })?;
@@ -1749,6 +5189,28 @@ impl DBBatch {
         Ok(self)
     }
 
+    /// Merges a range of (key, operand) pairs given as an iterator, using the merge operator
+    /// of the map's column family.
+    pub fn merge_batch<J: Borrow<K>, K: Serialize, U: Borrow<V>, V: Serialize>(
+        &mut self,
+        db: &DBMap<K, V>,
+        operands: impl IntoIterator<Item = (J, U)>,
+    ) -> Result<&mut Self, TypedStoreError> {
+        if !Arc::ptr_eq(&db.rocksdb, &self.rocksdb) {
+            return Err(TypedStoreError::CrossDBBatch);
+        }
+        operands
+            .into_iter()
+            .try_for_each::<_, Result<_, TypedStoreError>>(|(k, v)| {
+                let k_buf = db.codec.encode_key(k.borrow())?;
+                let v_buf = db.codec.encode_value(v.borrow())?;
+                self.record_key(&db.cf, &k_buf);
+                self.batch.merge_cf(&db.cf()?, k_buf, v_buf);
+                Ok(())
+            })?;
+        Ok(self)
+    }
+
     /// Inserts a range of (key, value) pairs given as an iterator.
     pub fn partial_merge_batch<J: Borrow<K>, K: Serialize, V: Serialize, B: AsRef<[u8]>>(
         &mut self,
@@ -1761,7 +5223,8 @@ impl DBBatch {
         new_vals
             .into_iter()
             .try_for_each::<_, Result<_, TypedStoreError>>(|(k, v)| {
-                let k_buf = be_fix_int_ser(k.borrow())?;
+                let k_buf = db.codec.encode_key(k.borrow())?;
+                self.record_key(&db.cf, &k_buf);
                 self.batch.merge_cf(&db.cf()?, k_buf, v);
                 Ok(())
             })?;
@@ -1853,7 +5316,7 @@ where
     #[tracing::instrument(level = "trace", skip_all, err)]
     fn contains_key(&self, key: &K) -> Result<bool, TypedStoreError> {
         let start = std::time::Instant::now();
-        let key_buf = be_fix_int_ser(key)?;
+        let key_buf = self.codec.encode_key(key)?;
         // [`rocksdb::DBWithThreadMode::key_may_exist_cf`] can have false positives,.
         // but no false negatives. We use it to short-circuit the absent case.
         let readopts = self.opts.readopts();
@@ -1905,106 +5368,12 @@ where
 
     #[tracing::instrument(level = "trace", skip_all, err)]
     fn get(&self, key: &K) -> Result<Option<V>, TypedStoreError> {
-        let start = std::time::Instant::now();
-        let perf_ctx = if self.get_sample_interval.sample() {
-            Some(RocksDBPerfContext)
-        } else {
-            None
-        };
-        let key_buf = be_fix_int_ser(key)?;
-        let res = self
-            .rocksdb
-            .get_pinned_cf_opt(&self.cf()?, &key_buf, &self.opts.readopts())
-            .map_err(typed_store_err_from_rocks_err)?;
-        let found = res.is_some();
-        self.db_metrics
-            .op_metrics
-            .rocksdb_get_latency_seconds
-            .with_label_values(&[&self.cf, &found.to_string()])
-            .observe(start.elapsed().as_secs_f64());
-        self.db_metrics
-            .op_metrics
-            .rocksdb_get_key_bytes
-            .with_label_values(&[&self.cf])
-            .observe(key_buf.len() as f64);
-        self.db_metrics
-            .op_metrics
-            .rocksdb_get_bytes
-            .with_label_values(&[&self.cf])
-            .observe(key_buf.len() as f64 + res.as_ref().map_or(0.0, |v| v.len() as f64));
-        self.db_metrics
-            .op_metrics
-            .rocksdb_get_value_bytes
-            .with_label_values(&[&self.cf])
-            .observe(res.as_ref().map_or(0.0, |v| v.len() as f64));
-        if perf_ctx.is_some() {
-            self.db_metrics
-                .read_perf_ctx_metrics
-                .report_metrics(&self.cf);
-        }
-        match res {
-            Some(data) => Ok(Some(
-                bcs::from_bytes(&data).map_err(typed_store_err_from_bcs_err)?,
-            )),
-            None => Ok(None),
-        }
+        self.get_with_readopts(key, &self.opts.readopts())
     }
 
     #[tracing::instrument(level = "trace", skip_all, err)]
     fn insert(&self, key: &K, value: &V) -> Result<(), TypedStoreError> {
-        let timer = self
-            .db_metrics
-            .op_metrics
-            .rocksdb_put_latency_seconds
-            .with_label_values(&[&self.cf])
-            .start_timer();
-        let perf_ctx = if self.write_sample_interval.sample() {
-            Some(RocksDBPerfContext)
-        } else {
-            None
-        };
-        let key_buf = be_fix_int_ser(key)?;
-        let value_buf = bcs::to_bytes(value).map_err(typed_store_err_from_bcs_err)?;
-        self.db_metrics
-            .op_metrics
-            .rocksdb_put_key_bytes
-            .with_label_values(&[&self.cf])
-            .observe(key_buf.len() as f64);
-        self.db_metrics
-            .op_metrics
-            .rocksdb_put_value_bytes
-            .with_label_values(&[&self.cf])
-            .observe(value_buf.len() as f64);
-        self.db_metrics
-            .op_metrics
-            .rocksdb_put_bytes
-            .with_label_values(&[&self.cf])
-            .observe(key_buf.len() as f64 + value_buf.len() as f64);
-        if perf_ctx.is_some() {
-            self.db_metrics
-                .write_perf_ctx_metrics
-                .report_metrics(&self.cf);
-        }
-        self.rocksdb
-            .put_cf(&self.cf()?, &key_buf, &value_buf, &self.opts.writeopts())
-            .map_err(typed_store_err_from_rocks_err)?;
-
-        let elapsed = timer.stop_and_record();
-        if elapsed > 1.0 {
-            tracing::warn!(?elapsed, cf = ?self.cf, "very slow insert");
-            self.db_metrics
-                .op_metrics
-                .rocksdb_very_slow_puts_count
-                .with_label_values(&[&self.cf])
-                .inc();
-            self.db_metrics
-                .op_metrics
-                .rocksdb_very_slow_puts_duration_ms
-                .with_label_values(&[&self.cf])
-                .inc_by((elapsed * 1000.0) as u64);
-        }
-
-        Ok(())
+        self.insert_with_writeopts(key, value, &self.opts.writeopts())
     }
 
     #[tracing::instrument(level = "trace", skip_all, err)]
@@ -2020,10 +5389,14 @@ where
         } else {
             None
         };
-        let key_buf = be_fix_int_ser(key)?;
+        let key_buf = self.codec.encode_key(key)?;
+        self.rocksdb.ensure_writable()?;
+        let guard = self.rocksdb.lock_key(&self.cf, &key_buf);
         self.rocksdb
-            .delete_cf(&self.cf()?, key_buf, &self.opts.writeopts())
+            .delete_cf(&self.cf()?, &key_buf, &self.opts.writeopts())
             .map_err(typed_store_err_from_rocks_err)?;
+        self.verify_point_write("remove", &key_buf, None)?;
+        drop(guard);
         self.db_metrics
             .op_metrics
             .rocksdb_deletes
@@ -2043,10 +5416,17 @@ where
     /// one is not created yet.
     #[tracing::instrument(level = "trace", skip_all, err)]
     fn unsafe_clear(&self) -> Result<(), TypedStoreError> {
+        let comparator = self.rocksdb.comparator(&self.cf);
         let _ = self.rocksdb.drop_cf(&self.cf);
-        self.rocksdb
-            .create_cf(self.cf.clone(), &default_db_options().options)
-            .map_err(typed_store_err_from_rocks_err)?;
+        let options = default_db_options().options;
+        match comparator {
+            Some(comparator) => {
+                self.rocksdb
+                    .create_cf_with_comparator(self.cf.clone(), &options, &comparator)
+            }
+            None => self.rocksdb.create_cf(self.cf.clone(), &options),
+        }
+        .map_err(typed_store_err_from_rocks_err)?;
         Ok(())
     }
 
@@ -2079,11 +5459,22 @@ where
         }
     }
 
+    /// Returns true if the map is empty.
+    ///
+    /// This costs a single seek of a raw iterator to the first key, which neither decodes keys nor
+    /// reads or deserializes any value. The seek may still have to skip over deleted entries, e.g.,
+    /// after many removals that were not yet compacted. A failing seek is reported as non-empty.
     fn is_empty(&self) -> bool {
-        self.safe_iter()
-            .expect("safe_iter should not fail")
-            .next()
-            .is_none()
+        let cf = match self.cf() {
+            Ok(cf) => cf,
+            Err(error) => {
+                tracing::warn!(?error, cf = ?self.cf, "failed to check whether the map is empty");
+                return false;
+            }
+        };
+        let mut db_iter = self.rocksdb.raw_iterator_cf(&cf, self.opts.readopts());
+        db_iter.seek_to_first();
+        !db_iter.valid() && db_iter.status().is_ok()
     }
 
     /// Deletes all entries by iterating keys and issuing per-key deletes (batched), for engines
@@ -2114,13 +5505,7 @@ where
         let db_iter = self
             .rocksdb
             .raw_iterator_cf(&self.cf()?, self.opts.readopts());
-        let iter_context = self.create_iter_context();
-        Ok(SafeIter::new(
-            self.cf.clone(),
-            db_iter,
-            iter_context,
-            Some(self.db_metrics.clone()),
-        ))
+        Ok(self.new_safe_iter(db_iter))
     }
 
     fn safe_iter_with_bounds(
@@ -2128,30 +5513,18 @@ where
         lower_bound: Option<K>,
         upper_bound: Option<K>,
     ) -> Result<Self::SafeIterator, TypedStoreError> {
-        let readopts = self.create_read_options_with_bounds(lower_bound, upper_bound);
+        let readopts = self.create_read_options_with_bounds(lower_bound, upper_bound)?;
         let db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
-        let iter_context = self.create_iter_context();
-        Ok(SafeIter::new(
-            self.cf.clone(),
-            db_iter,
-            iter_context,
-            Some(self.db_metrics.clone()),
-        ))
+        Ok(self.new_safe_iter(db_iter))
     }
 
     fn safe_range_iter(
         &'a self,
         range: impl RangeBounds<K>,
     ) -> Result<Self::SafeIterator, TypedStoreError> {
-        let readopts = self.create_read_options_with_range(range);
+        let readopts = self.create_read_options_with_range(range)?;
         let db_iter = self.rocksdb.raw_iterator_cf(&self.cf()?, readopts);
-        let iter_context = self.create_iter_context();
-        Ok(SafeIter::new(
-            self.cf.clone(),
-            db_iter,
-            iter_context,
-            Some(self.db_metrics.clone()),
-        ))
+        Ok(self.new_safe_iter(db_iter))
     }
 
     /// Returns a vector of values corresponding to the keys provided.
@@ -2160,21 +5533,10 @@ where
         &self,
         keys: impl IntoIterator<Item = J>,
     ) -> Result<Vec<Option<V>>, TypedStoreError>
-    where
-        J: Borrow<K>,
-    {
-        let results = self.multi_get_pinned(keys)?;
-        let values_parsed: Result<Vec<_>, TypedStoreError> = results
-            .into_iter()
-            .map(|value_byte| match value_byte {
-                Some(data) => Ok(Some(
-                    bcs::from_bytes(&data).map_err(typed_store_err_from_bcs_err)?,
-                )),
-                None => Ok(None),
-            })
-            .collect();
-
-        values_parsed
+    where
+        J: Borrow<K>,
+    {
+        decode_multi_get_values(&self.codec, self.multi_get_pinned(keys)?)
     }
 
     /// Convenience method for batch insertion.
@@ -2236,115 +5598,809 @@ pub struct ReadWriteOptions {
     sync_to_disk: bool,
 }
 
-impl ReadWriteOptions {
-    /// The read options.
-    pub fn readopts(&self) -> ReadOptions {
-        let mut readopts = ReadOptions::default();
-        readopts.set_ignore_range_deletions(self.ignore_range_deletions);
-        readopts
+impl ReadWriteOptions {
+    /// The read options.
+    pub fn readopts(&self) -> ReadOptions {
+        let mut readopts = ReadOptions::default();
+        readopts.set_ignore_range_deletions(self.ignore_range_deletions);
+        // Iterators see all keys in order, also in column families with a prefix extractor.
+        readopts.set_total_order_seek(true);
+        readopts
+    }
+
+    /// The write options.
+    pub fn writeopts(&self) -> WriteOptions {
+        let mut opts = WriteOptions::default();
+        opts.set_sync(self.sync_to_disk);
+        opts
+    }
+
+    /// Set the ignore range deletions.
+    pub fn set_ignore_range_deletions(mut self, ignore: bool) -> Self {
+        self.ignore_range_deletions = ignore;
+        self
+    }
+}
+
+impl Default for ReadWriteOptions {
+    fn default() -> Self {
+        Self {
+            ignore_range_deletions: true,
+            sync_to_disk: std::env::var("SUI_DB_SYNC_TO_DISK").is_ok_and(|v| v != "0"),
+        }
+    }
+}
+
+/// The storage tiers a read may access.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReadTier {
+    /// The read may access all tiers, including the disk.
+    #[default]
+    All,
+    /// The read only accesses the memtables and the block cache.
+    ///
+    /// A point read of a value that is not cached fails with [`TypedStoreError::NotInCache`]
+    /// instead of reading from disk, as does an iterator reaching uncached data.
+    CacheOnly,
+}
+
+/// Per-read options, controlling the block cache.
+///
+/// Used with [`DBMap::get_with_read_options`] and [`DBMap::safe_iter_with_read_options`] on top of
+/// the read options of the map's [`ReadWriteOptions`].
+#[derive(Clone, Debug)]
+pub struct DBReadOptions {
+    fill_cache: bool,
+    read_tier: ReadTier,
+}
+
+impl Default for DBReadOptions {
+    fn default() -> Self {
+        Self {
+            fill_cache: true,
+            read_tier: ReadTier::All,
+        }
+    }
+}
+
+impl DBReadOptions {
+    /// Sets whether blocks read from disk are added to the block cache.
+    ///
+    /// Disable this for large one-off scans, so that they do not evict the working set of other
+    /// reads from the cache.
+    pub fn fill_cache(mut self, fill_cache: bool) -> Self {
+        self.fill_cache = fill_cache;
+        self
+    }
+
+    /// Sets the storage tiers the read may access.
+    pub fn read_tier(mut self, read_tier: ReadTier) -> Self {
+        self.read_tier = read_tier;
+        self
+    }
+
+    /// Applies the options to the given read options.
+    fn set_on(&self, readopts: &mut ReadOptions) {
+        readopts.fill_cache(self.fill_cache);
+        readopts.set_read_tier(match self.read_tier {
+            ReadTier::All => rocksdb::ReadTier::All,
+            ReadTier::CacheOnly => rocksdb::ReadTier::BlockCache,
+        });
+    }
+}
+
+/// Per-write durability options, trading durability for write throughput.
+///
+/// Used with [`DBMap::insert_with_options`] and [`DBBatch::write_with_options`] to override the
+/// write options of the map's [`ReadWriteOptions`] for individual writes.
+#[derive(Clone, Debug, Default)]
+pub struct DBWriteOptions {
+    disable_wal: bool,
+    sync: bool,
+}
+
+impl DBWriteOptions {
+    /// Sets whether to bypass the write-ahead log.
+    ///
+    /// Unlogged writes only live in the memtable until it is flushed to SST files, which happens
+    /// when it fills up, on an explicit flush, or when the database is closed cleanly. They are
+    /// lost if the process crashes before that, and writes made after them may survive while they
+    /// do not. Only use this for data that can be rebuilt from elsewhere.
+    pub fn disable_wal(mut self, disable_wal: bool) -> Self {
+        self.disable_wal = disable_wal;
+        self
+    }
+
+    /// Sets whether to `fsync` the write-ahead log before the write returns.
+    ///
+    /// Without syncing, a write survives a crash of the process but may be lost if the machine
+    /// crashes or loses power before the operating system persists the log. Syncing makes the
+    /// write durable against both, at the cost of a disk flush per write. Writes that sync while
+    /// bypassing the write-ahead log are rejected by RocksDB.
+    pub fn sync(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    /// The write options.
+    pub fn writeopts(&self) -> WriteOptions {
+        let mut opts = WriteOptions::default();
+        opts.disable_wal(self.disable_wal);
+        opts.set_sync(self.sync);
+        opts
+    }
+}
+
+/// The rocksdb options.
+#[derive(Default, Clone)]
+pub struct DBOptions {
+    /// The rocksdb options.
+    pub options: rocksdb::Options,
+    /// The read-write options.
+    pub rw_options: ReadWriteOptions,
+}
+
+impl fmt::Debug for DBOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DBOptions")
+    }
+}
+
+/// Creates a default RocksDB option, to be used when RocksDB option is unspecified.
+pub fn default_db_options() -> DBOptions {
+    let mut opt = rocksdb::Options::default();
+
+    // One common issue when running tests on Mac is that the default ulimit is too low,.
+    // leading to I/O errors such as "Too many open files". Raising fdlimit to bypass it.
+    if let Ok(outcome) = fdlimit::raise_fd_limit() {
+        let limit = match outcome {
+            fdlimit::Outcome::LimitRaised { to, .. } => to,
+            fdlimit::Outcome::Unsupported => {
+                tracing::warn!("failed to raise fdlimit, defaulting to 1024");
+                1024
+            }
+        };
+        // on windows raise_fd_limit return None.
+        opt.set_max_open_files((limit / 8) as i32);
+    }
+
+    // The table cache is locked for updates and this determines the number.
+    // of shards, ie 2^10. Increase in case of lock contentions.
+    opt.set_table_cache_num_shard_bits(10);
+
+    // LSM compression settings.
+    opt.set_compression_type(rocksdb::DBCompressionType::Lz4);
+    opt.set_bottommost_compression_type(rocksdb::DBCompressionType::Zstd);
+    opt.set_bottommost_zstd_max_train_bytes(1024 * 1024, true);
+
+    // Sui uses multiple RocksDB in a node, so total sizes of write buffers and WAL can be higher.
+    // than the limits below.
+    //
+    // RocksDB also exposes the option to configure total write buffer size across.
+    // multiple instances. But the write buffer flush policy (flushing the buffer.
+    // receiving the next write) may not work well. So sticking to per-db write buffer.
+    // size limit for now.
+    //
+    // The environment variables are only meant to be emergency overrides.
+    // They may go away in future.
+    // It is preferable to update the default value, or override the option in code.
+    opt.set_db_write_buffer_size(
+        read_size_from_env(ENV_VAR_DB_WRITE_BUFFER_SIZE).unwrap_or(DEFAULT_DB_WRITE_BUFFER_SIZE)
+            * 1024
+            * 1024,
+    );
+    opt.set_max_total_wal_size(
+        read_size_from_env(ENV_VAR_DB_WAL_SIZE).unwrap_or(DEFAULT_DB_WAL_SIZE) as u64 * 1024 * 1024,
+    );
+
+    // Num threads for compactions and memtable flushes.
+    opt.increase_parallelism(read_size_from_env(ENV_VAR_DB_PARALLELISM).unwrap_or(8) as i32);
+
+    opt.set_enable_pipelined_write(true);
+
+    // Increase block size to 16KiB.
+    // https://github.com/EighteenZi/rocksdb_wiki/blob/master/.
+    // Memory-usage-in-RocksDB.md#indexes-and-filter-blocks.
+    opt.set_block_based_table_factory(&get_block_options(
+        DEFAULT_BLOCK_CACHE_SIZE,
+        Some(DEFAULT_BLOCK_SIZE),
+        Some(true),
+    ));
+
+    // Set memtable bloomfilter.
+    opt.set_memtable_prefix_bloom_ratio(0.02);
+
+    DBOptions {
+        options: opt,
+        rw_options: ReadWriteOptions::default(),
+    }
+}
+
+// Default block cache and block sizes of the block-based table options.
+const DEFAULT_BLOCK_CACHE_SIZE: usize = 128 << 20;
+const DEFAULT_BLOCK_SIZE: usize = 16 << 10;
+
+// Default zstd window bits, level and strategy, as used by RocksDB's `CompressionOptions`.
+const ZSTD_DEFAULT_WINDOW_BITS: i32 = -14;
+const ZSTD_DEFAULT_LEVEL: i32 = 32767;
+const ZSTD_DEFAULT_STRATEGY: i32 = 0;
+
+/// The compression settings for the bottommost level of the LSM tree.
+#[derive(Debug, Clone)]
+pub struct BottommostCompressionConfig {
+    /// The compression algorithm used for the bottommost level.
+    pub compression_type: rocksdb::DBCompressionType,
+    /// The maximum size of the compression dictionary in bytes; 0 disables dictionaries.
+    pub max_dict_bytes: i32,
+    /// The maximum number of bytes sampled to train the zstd dictionary; 0 disables training.
+    pub zstd_max_train_bytes: i32,
+}
+
+impl BottommostCompressionConfig {
+    /// Zstd compression with a dictionary of at most `max_dict_bytes`, trained on 100 times as
+    /// much sample data, as recommended by the zstd documentation.
+    pub fn zstd_with_dictionary(max_dict_bytes: i32) -> Self {
+        Self {
+            compression_type: rocksdb::DBCompressionType::Zstd,
+            max_dict_bytes,
+            zstd_max_train_bytes: max_dict_bytes.saturating_mul(100),
+        }
+    }
+
+    /// Applies the settings to the given options.
+    pub fn apply(&self, options: &mut rocksdb::Options) {
+        options.set_bottommost_compression_type(self.compression_type);
+        options.set_bottommost_compression_options(
+            ZSTD_DEFAULT_WINDOW_BITS,
+            ZSTD_DEFAULT_LEVEL,
+            ZSTD_DEFAULT_STRATEGY,
+            self.max_dict_bytes,
+            true,
+        );
+        options.set_bottommost_zstd_max_train_bytes(self.zstd_max_train_bytes, true);
+    }
+}
+
+impl Default for BottommostCompressionConfig {
+    fn default() -> Self {
+        Self::zstd_with_dictionary(16 * 1024)
+    }
+}
+
+/// The compression of a column family, see [`open_cf_with_compression`].
+///
+/// This is RocksDB block compression: the data blocks of the SST files are compressed as a whole
+/// when they are written, so similar values stored next to each other compress well. Individual
+/// values are stored uncompressed in the memtables and returned uncompressed by reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// No compression.
+    None,
+    /// LZ4 compression, the default for all but the bottommost level.
+    Lz4,
+    /// Zstd compression at the given level.
+    Zstd {
+        /// The compression level, between 1 and 22 with higher levels compressing better.
+        level: i32,
+    },
+}
+
+impl Compression {
+    /// Applies the compression to all levels of the LSM tree in the given options.
+    ///
+    /// This overrides any bottommost-level compression; a [`BottommostCompressionConfig`] can be
+    /// applied afterwards to compress the bottommost level differently.
+    pub fn apply(&self, options: &mut rocksdb::Options) {
+        let compression_type = match self {
+            Self::None => rocksdb::DBCompressionType::None,
+            Self::Lz4 => rocksdb::DBCompressionType::Lz4,
+            Self::Zstd { .. } => rocksdb::DBCompressionType::Zstd,
+        };
+        options.set_compression_type(compression_type);
+        options.set_bottommost_compression_type(compression_type);
+        if let Self::Zstd { level } = self {
+            options.set_compression_options(
+                ZSTD_DEFAULT_WINDOW_BITS,
+                *level,
+                ZSTD_DEFAULT_STRATEGY,
+                0,
+            );
+            options.set_bottommost_compression_options(
+                ZSTD_DEFAULT_WINDOW_BITS,
+                *level,
+                ZSTD_DEFAULT_STRATEGY,
+                0,
+                true,
+            );
+        }
+    }
+}
+
+/// The integrity checks of a database, see [`open_cf_with_integrity_checks`].
+///
+/// RocksDB always checks the consistency of the LSM tree of every column family whenever it
+/// changes, e.g., for overlapping files in a level: these checks are enabled by default, and the
+/// bindings offer no way to disable them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntegrityChecks {
+    /// Whether RocksDB verifies the database files when opening the database and fails on any
+    /// inconsistency, instead of trying to continue, e.g., after a background error.
+    pub paranoid_checks: bool,
+}
+
+impl IntegrityChecks {
+    /// All integrity checks, as recommended for critical metadata.
+    pub fn enabled() -> Self {
+        Self {
+            paranoid_checks: true,
+        }
+    }
+
+    /// Applies the checks to the given options.
+    pub fn apply(&self, options: &mut rocksdb::Options) {
+        options.set_paranoid_checks(self.paranoid_checks);
+    }
+}
+
+/// The order of the keys of a column family, see [`open_cf_with_comparators`].
+///
+/// **RocksDB persists only the name of a column family's comparator, not its order.** A column
+/// family must be opened with a comparator of the same name, and the comparator must order all
+/// keys exactly as before, every time the database is reopened: opening it with a different name
+/// fails, and changing the order under the same name silently corrupts the column family, e.g.,
+/// keys become unreachable and compactions misbehave.
+///
+/// Iterators, such as [`DBMap::safe_iter`], yield keys in the comparator's order, and included
+/// lower and excluded upper range bounds, seeks, and [`SafeIter::skip_to`] follow the
+/// comparator. Operations that derive keys assuming the default bytewise order fail on column
+/// families with a comparator: ranges with excluded lower or included upper bounds, prefix
+/// operations such as [`DBMap::delete_prefix`] and [`DBMap::multi_contains_prefix`], and
+/// [`DBMap::clear_via_range_delete`]. Prefix bloom filters must not be configured for such
+/// column families either, as keys sharing a prefix need not be adjacent in the comparator's
+/// order.
+#[derive(Clone)]
+pub struct Comparator {
+    name: String,
+    compare: Arc<dyn Fn(&[u8], &[u8]) -> std::cmp::Ordering + Send + Sync>,
+}
+
+impl fmt::Debug for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Comparator")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Comparator {
+    /// Creates a comparator with the given name, ordering serialized keys with `compare`.
+    pub fn new(
+        name: impl Into<String>,
+        compare: impl Fn(&[u8], &[u8]) -> std::cmp::Ordering + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            compare: Arc::new(compare),
+        }
+    }
+
+    /// A comparator ordering serialized keys in descending byte order.
+    pub fn reverse_bytewise() -> Self {
+        Self::new("typed_store.ReverseBytewise", |a, b| b.cmp(a))
+    }
+
+    /// Returns the name of the comparator, as persisted by RocksDB.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Compares two serialized keys in the comparator's order.
+    pub fn compare(&self, a: &[u8], b: &[u8]) -> std::cmp::Ordering {
+        (self.compare)(a, b)
+    }
+
+    /// Applies the comparator to the given column family options.
+    pub fn apply(&self, options: &mut rocksdb::Options) {
+        let compare = self.compare.clone();
+        options.set_comparator(self.name.as_str(), Box::new(move |a, b| compare(a, b)));
+    }
+}
+
+/// The keys a [`BloomFilter`] is built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterKind {
+    /// A full filter over whole keys, used by point lookups.
+    Full,
+    /// A filter over the serialized keys' prefixes of the given length, used by point lookups and
+    /// prefix seeks.
+    ///
+    /// This installs a fixed-length prefix extractor on the column family. The iterators of
+    /// [`DBMap`] use total order seeks, so they see all keys in order regardless of their
+    /// prefixes; raw iterators must set `total_order_seek` in their read options to do the same.
+    Prefix {
+        /// The length of the prefixes in bytes.
+        len: usize,
+    },
+}
+
+/// The bloom filter of a column family, see [`open_cf_with_bloom_filters`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomFilter {
+    /// The bits of the filter per key; 10 bits yield a false positive rate of about 1%.
+    pub bits_per_key: f64,
+    /// Whether whole keys or key prefixes are filtered.
+    pub kind: FilterKind,
+}
+
+impl BloomFilter {
+    /// A full filter with the given bits per key.
+    pub fn full(bits_per_key: f64) -> Self {
+        Self {
+            bits_per_key,
+            kind: FilterKind::Full,
+        }
+    }
+
+    /// A prefix filter on prefixes of `len` bytes with the given bits per key.
+    pub fn prefix(bits_per_key: f64, len: usize) -> Self {
+        Self {
+            bits_per_key,
+            kind: FilterKind::Prefix { len },
+        }
+    }
+}
+
+/// A limit on the rate at which flushes and compactions write to disk.
+///
+/// Databases opened with the options returned by [`RateLimiter::db_options`], or through
+/// [`DBMap::open_with_rate_limiter`], share the same underlying RocksDB limiter, including through
+/// clones of the `RateLimiter`, so that their combined background writes stay below the limit.
+/// User writes to the WAL are not throttled.
+#[derive(Clone)]
+pub struct RateLimiter {
+    bytes_per_sec: u64,
+    /// Database options holding the limiter, cloned for each database so that they share it, as
+    /// the bindings do not expose the limiter itself.
+    options: rocksdb::Options,
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("bytes_per_sec", &self.bytes_per_sec)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RateLimiter {
+    /// The interval at which the limiter refills its budget, in microseconds.
+    const REFILL_PERIOD_US: i64 = 100_000;
+    /// RocksDB's default fairness between high- and low-priority requests.
+    const FAIRNESS: i32 = 10;
+
+    /// Creates a limiter allowing `bytes_per_sec` bytes of background writes per second, based on
+    /// the default database options, see [`default_db_options`].
+    ///
+    /// The default options include a block cache, so all databases opened with
+    /// [`Self::db_options`] share that cache as well as the limiter. Use
+    /// [`Self::with_db_options`] to give them options with a cache of their choice.
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self::with_db_options(bytes_per_sec, default_db_options().options)
+    }
+
+    /// Creates a limiter allowing `bytes_per_sec` bytes of background writes per second, adding it
+    /// to the given database options.
+    ///
+    /// All databases opened with [`Self::db_options`] share everything the options share between
+    /// their clones, e.g., the block cache of their table options.
+    pub fn with_db_options(bytes_per_sec: u64, mut options: rocksdb::Options) -> Self {
+        options.set_ratelimiter(
+            i64::try_from(bytes_per_sec).unwrap_or(i64::MAX),
+            Self::REFILL_PERIOD_US,
+            Self::FAIRNESS,
+        );
+        Self {
+            bytes_per_sec,
+            options,
+        }
+    }
+
+    /// Returns the limit in bytes per second.
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec
+    }
+
+    /// Returns the database options the limiter was created with, including the limiter.
+    ///
+    /// The options can be changed further before opening a database with them, but the limiter is
+    /// only shared if the options are obtained from this method.
+    pub fn db_options(&self) -> rocksdb::Options {
+        self.options.clone()
+    }
+}
+
+/// Approximate memory usage of a column family, as returned by [`DBMap::memory_usage`].
+///
+/// All sizes are in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// The size of the active, unflushed immutable, and pinned immutable memtables.
+    pub mem_tables: u64,
+    /// The size of the active and unflushed immutable memtables.
+    pub unflushed_mem_tables: u64,
+    /// The memory used by the readers of the SST files, excluding the block cache.
+    pub table_readers: u64,
+    /// The size of the entries in the block cache.
+    pub block_cache: u64,
+    /// The size of the entries in the block cache that are pinned.
+    pub block_cache_pinned: u64,
+}
+
+impl MemoryUsage {
+    /// Returns the memory used by the memtables, table readers, and block cache.
+    pub fn total(&self) -> u64 {
+        self.mem_tables + self.table_readers + self.block_cache
     }
+}
 
-    /// The write options.
-    pub fn writeopts(&self) -> WriteOptions {
-        let mut opts = WriteOptions::default();
-        opts.set_sync(self.sync_to_disk);
-        opts
+/// The work done by a scan, as returned by [`DBMap::scan_diagnostics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    /// The number of live entries the scan returned.
+    pub keys_seen: u64,
+    /// The number of internal keys skipped, i.e., deleted or overwritten entries and deletion
+    /// tombstones that are not compacted yet.
+    pub internal_keys_skipped: u64,
+    /// The number of deletion tombstones skipped.
+    pub deletions_skipped: u64,
+    /// The duration of the scan.
+    pub elapsed: Duration,
+}
+
+/// Metadata of an SST file of a column family, as returned by [`DBMap::live_files`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SstFileInfo {
+    /// The level of the LSM tree the file is in.
+    pub level: usize,
+    /// The number of the file, which increases with the creation time of files.
+    pub file_number: u64,
+    /// The name of the file, relative to the database directory.
+    pub name: String,
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The smallest serialized key in the file.
+    pub smallest_key: Option<Vec<u8>>,
+    /// The largest serialized key in the file.
+    pub largest_key: Option<Vec<u8>>,
+    /// The number of entries in the file, including deletions.
+    pub num_entries: u64,
+    /// The number of deletion entries in the file.
+    pub num_deletions: u64,
+}
+
+impl SstFileInfo {
+    /// Decodes the smallest and largest keys of the file with the key encoding of the map.
+    pub fn decode_key_range<K: DeserializeOwned, V>(
+        &self,
+        map: &DBMap<K, V>,
+    ) -> Result<(Option<K>, Option<K>), TypedStoreError> {
+        let decode = |key: &Option<Vec<u8>>| {
+            key.as_deref()
+                .map(|key| map.codec.decode_key(key))
+                .transpose()
+        };
+        Ok((decode(&self.smallest_key)?, decode(&self.largest_key)?))
     }
+}
 
-    /// Set the ignore range deletions.
-    pub fn set_ignore_range_deletions(mut self, ignore: bool) -> Self {
-        self.ignore_range_deletions = ignore;
-        self
+impl From<LiveFile> for SstFileInfo {
+    fn from(file: LiveFile) -> Self {
+        let name = file.name.trim_start_matches('/').to_owned();
+        Self {
+            level: usize::try_from(file.level).unwrap_or_default(),
+            file_number: name
+                .strip_suffix(".sst")
+                .and_then(|number| number.parse().ok())
+                .unwrap_or_default(),
+            name,
+            size: file.size as u64,
+            smallest_key: file.start_key,
+            largest_key: file.end_key,
+            num_entries: file.num_entries,
+            num_deletions: file.num_deletions,
+        }
     }
 }
 
-impl Default for ReadWriteOptions {
-    fn default() -> Self {
+/// The common RocksDB statistics of a database, as returned by [`RocksDB::statistics_snapshot`].
+///
+/// Tickers are cumulative since the database was opened. Histograms of operation latencies are in
+/// microseconds and are only collected with [`StatsLevel::All`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatisticsSnapshot {
+    /// The number of block cache hits.
+    pub block_cache_hits: u64,
+    /// The number of block cache misses.
+    pub block_cache_misses: u64,
+    /// The number of uncompressed bytes returned by reads.
+    pub bytes_read: u64,
+    /// The number of uncompressed bytes written by writes.
+    pub bytes_written: u64,
+    /// The number of keys returned by reads.
+    pub keys_read: u64,
+    /// The number of keys written.
+    pub keys_written: u64,
+    /// The number of reads served from the memtables.
+    pub memtable_hits: u64,
+    /// The number of reads not served from the memtables.
+    pub memtable_misses: u64,
+    /// The number of times a bloom filter avoided reading an SST file.
+    pub bloom_filter_useful: u64,
+    /// The number of bytes read by compactions.
+    pub compaction_bytes_read: u64,
+    /// The number of bytes written by compactions.
+    pub compaction_bytes_written: u64,
+    /// The number of bytes written by memtable flushes.
+    pub flush_bytes_written: u64,
+    /// The time writes were stalled, in microseconds.
+    pub write_stall_micros: u64,
+    /// The latencies of gets.
+    pub get_latency: HistogramSnapshot,
+    /// The latencies of writes.
+    pub write_latency: HistogramSnapshot,
+    /// The durations of compactions.
+    pub compaction_time: HistogramSnapshot,
+}
+
+impl StatisticsSnapshot {
+    fn from_options(options: &rocksdb::Options) -> Self {
+        let ticker = |ticker| options.get_ticker_count(ticker);
+        let histogram = |histogram| HistogramSnapshot::from(options.get_histogram_data(histogram));
         Self {
-            ignore_range_deletions: true,
-            sync_to_disk: std::env::var("SUI_DB_SYNC_TO_DISK").is_ok_and(|v| v != "0"),
+            block_cache_hits: ticker(Ticker::BlockCacheHit),
+            block_cache_misses: ticker(Ticker::BlockCacheMiss),
+            bytes_read: ticker(Ticker::BytesRead),
+            bytes_written: ticker(Ticker::BytesWritten),
+            keys_read: ticker(Ticker::NumberKeysRead),
+            keys_written: ticker(Ticker::NumberKeysWritten),
+            memtable_hits: ticker(Ticker::MemtableHit),
+            memtable_misses: ticker(Ticker::MemtableMiss),
+            bloom_filter_useful: ticker(Ticker::BloomFilterUseful),
+            compaction_bytes_read: ticker(Ticker::CompactReadBytes),
+            compaction_bytes_written: ticker(Ticker::CompactWriteBytes),
+            flush_bytes_written: ticker(Ticker::FlushWriteBytes),
+            write_stall_micros: ticker(Ticker::StallMicros),
+            get_latency: histogram(Histogram::DbGet),
+            write_latency: histogram(Histogram::DbWrite),
+            compaction_time: histogram(Histogram::CompactionTime),
         }
     }
 }
 
-/// The rocksdb options.
-#[derive(Default, Clone)]
-pub struct DBOptions {
-    /// The rocksdb options.
-    pub options: rocksdb::Options,
-    /// The read-write options.
-    pub rw_options: ReadWriteOptions,
+/// The hits and misses of a cache, as returned by [`DBMap::block_cache_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    /// The number of lookups served from the cache.
+    pub hits: u64,
+    /// The number of lookups not served from the cache.
+    pub misses: u64,
+    /// The fraction of lookups served from the cache, or 0 if there were no lookups.
+    pub hit_ratio: f64,
 }
 
-impl fmt::Debug for DBOptions {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "DBOptions")
+impl CacheStats {
+    fn new(hits: u64, misses: u64) -> Self {
+        let lookups = hits.saturating_add(misses);
+        Self {
+            hits,
+            misses,
+            hit_ratio: if lookups == 0 {
+                0.0
+            } else {
+                hits as f64 / lookups as f64
+            },
+        }
     }
 }
 
-/// Creates a default RocksDB option, to be used when RocksDB option is unspecified.
-pub fn default_db_options() -> DBOptions {
-    let mut opt = rocksdb::Options::default();
+/// A summary of a RocksDB statistics histogram.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HistogramSnapshot {
+    /// The number of recorded values.
+    pub count: u64,
+    /// The sum of the recorded values.
+    pub sum: u64,
+    /// The mean of the recorded values.
+    pub average: f64,
+    /// The median of the recorded values.
+    pub median: f64,
+    /// The 95th percentile of the recorded values.
+    pub p95: f64,
+    /// The 99th percentile of the recorded values.
+    pub p99: f64,
+    /// The largest recorded value.
+    pub max: f64,
+}
 
-    // One common issue when running tests on Mac is that the default ulimit is too low,.
-    // leading to I/O errors such as "Too many open files". Raising fdlimit to bypass it.
-    if let Ok(outcome) = fdlimit::raise_fd_limit() {
-        let limit = match outcome {
-            fdlimit::Outcome::LimitRaised { to, .. } => to,
-            fdlimit::Outcome::Unsupported => {
-                tracing::warn!("failed to raise fdlimit, defaulting to 1024");
-                1024
-            }
-        };
-        // on windows raise_fd_limit return None.
-        opt.set_max_open_files((limit / 8) as i32);
+impl From<HistogramData> for HistogramSnapshot {
+    fn from(data: HistogramData) -> Self {
+        Self {
+            count: data.count(),
+            sum: data.sum(),
+            average: data.average(),
+            median: data.median(),
+            p95: data.p95(),
+            p99: data.p99(),
+            max: data.max(),
+        }
     }
+}
 
-    // The table cache is locked for updates and this determines the number.
-    // of shards, ie 2^10. Increase in case of lock contentions.
-    opt.set_table_cache_num_shard_bits(10);
+/// An LRU block cache that can be shared by multiple databases and column families.
+///
+/// By default, every database opened with [`default_db_options`] gets its own block cache. Applying
+/// the same `BlockCache` to the options of several databases or column families instead bounds
+/// the memory of all their cached blocks by a single capacity.
+#[derive(Clone)]
+pub struct BlockCache {
+    cache: Cache,
+    capacity: usize,
+}
 
-    // LSM compression settings.
-    opt.set_compression_type(rocksdb::DBCompressionType::Lz4);
-    opt.set_bottommost_compression_type(rocksdb::DBCompressionType::Zstd);
-    opt.set_bottommost_zstd_max_train_bytes(1024 * 1024, true);
+impl fmt::Debug for BlockCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "BlockCache {{ capacity: {}, usage: {} }}",
+            self.capacity,
+            self.usage()
+        )
+    }
+}
 
-    // Sui uses multiple RocksDB in a node, so total sizes of write buffers and WAL can be higher.
-    // than the limits below.
-    //
-    // RocksDB also exposes the option to configure total write buffer size across.
-    // multiple instances. But the write buffer flush policy (flushing the buffer.
-    // receiving the next write) may not work well. So sticking to per-db write buffer.
-    // size limit for now.
-    //
-    // The environment variables are only meant to be emergency overrides.
-    // They may go away in future.
-    // It is preferable to update the default value, or override the option in code.
-    opt.set_db_write_buffer_size(
-        read_size_from_env(ENV_VAR_DB_WRITE_BUFFER_SIZE).unwrap_or(DEFAULT_DB_WRITE_BUFFER_SIZE)
-            * 1024
-            * 1024,
-    );
-    opt.set_max_total_wal_size(
-        read_size_from_env(ENV_VAR_DB_WAL_SIZE).unwrap_or(DEFAULT_DB_WAL_SIZE) as u64 * 1024 * 1024,
-    );
+impl BlockCache {
+    /// Creates an LRU block cache holding up to `capacity` bytes.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            cache: Cache::new_lru_cache(capacity),
+            capacity,
+        }
+    }
 
-    // Num threads for compactions and memtable flushes.
-    opt.increase_parallelism(read_size_from_env(ENV_VAR_DB_PARALLELISM).unwrap_or(8) as i32);
+    /// Returns the capacity of the cache in bytes.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
 
-    opt.set_enable_pipelined_write(true);
+    /// Returns the size of the entries in the cache in bytes.
+    pub fn usage(&self) -> usize {
+        self.cache.get_usage()
+    }
 
-    // Increase block size to 16KiB.
-    // https://github.com/EighteenZi/rocksdb_wiki/blob/master/.
-    // Memory-usage-in-RocksDB.md#indexes-and-filter-blocks.
-    opt.set_block_based_table_factory(&get_block_options(128 << 20, Some(16 << 10), Some(true)));
+    /// Returns the size of the entries in the cache that are pinned in memory in bytes.
+    pub fn pinned_usage(&self) -> usize {
+        self.cache.get_pinned_usage()
+    }
 
-    // Set memtable bloomfilter.
-    opt.set_memtable_prefix_bloom_ratio(0.02);
+    /// Returns block options that use this cache, see [`get_block_options`].
+    pub fn block_options(
+        &self,
+        block_size_bytes: Option<usize>,
+        pin_l0_filter_and_index_blocks_in_block_cache: Option<bool>,
+    ) -> BlockBasedOptions {
+        block_options_with_cache(
+            &self.cache,
+            block_size_bytes,
+            pin_l0_filter_and_index_blocks_in_block_cache,
+        )
+    }
 
-    DBOptions {
-        options: opt,
-        rw_options: ReadWriteOptions::default(),
+    /// Applies the default block options of [`default_db_options`] with this cache to the given
+    /// options.
+    pub fn apply(&self, options: &mut rocksdb::Options) {
+        options.set_block_based_table_factory(
+            &self.block_options(Some(DEFAULT_BLOCK_SIZE), Some(true)),
+        );
     }
 }
 
@@ -2353,6 +6409,18 @@ pub fn get_block_options(
     block_cache_size_bytes: usize,
     block_size_bytes: Option<usize>,
     pin_l0_filter_and_index_blocks_in_block_cache: Option<bool>,
+) -> BlockBasedOptions {
+    block_options_with_cache(
+        &Cache::new_lru_cache(block_cache_size_bytes),
+        block_size_bytes,
+        pin_l0_filter_and_index_blocks_in_block_cache,
+    )
+}
+
+fn block_options_with_cache(
+    cache: &Cache,
+    block_size_bytes: Option<usize>,
+    pin_l0_filter_and_index_blocks_in_block_cache: Option<bool>,
 ) -> BlockBasedOptions {
     // https://github.com/facebook/rocksdb/blob/.
     // 11cb6af6e5009c51794641905ca40ce5beec7fee/options/options.cc#L611-L621.
@@ -2362,7 +6430,7 @@ pub fn get_block_options(
         block_options.set_block_size(block_size_bytes);
     }
     // Configure a block cache.
-    block_options.set_block_cache(&Cache::new_lru_cache(block_cache_size_bytes));
+    block_options.set_block_cache(cache);
     block_options.set_cache_index_and_filter_blocks(true);
     // Set a bloomfilter with 1% false positive rate.
     block_options.set_bloom_filter(10.0, false);
@@ -2400,6 +6468,176 @@ pub fn open_cf<P: AsRef<Path>>(
     )
 }
 
+/// Opens a database like [`open_cf`], applying the given compressions to their column families.
+///
+/// All column families with a compression must be among `opt_cfs`; the others keep the
+/// compression of `db_options`. The compressions are reported by [`RocksDB::compression`]. As it
+/// is block compression, it only affects data flushed to SST files and is reflected in their size,
+/// e.g., as reported by [`DBMap::total_sst_files_size`].
+#[tracing::instrument(level="debug", skip_all, fields(path = ?path.as_ref(), cf = ?opt_cfs), err)]
+pub fn open_cf_with_compression<P: AsRef<Path>>(
+    path: P,
+    db_options: Option<rocksdb::Options>,
+    metric_conf: MetricConf,
+    opt_cfs: &[&str],
+    compressions: &[(&str, Compression)],
+) -> Result<Arc<RocksDB>, TypedStoreError> {
+    if let Some((cf_name, _)) = compressions
+        .iter()
+        .find(|(cf_name, _)| !opt_cfs.contains(cf_name))
+    {
+        return Err(TypedStoreError::RocksDBError(format!(
+            "compression given for column family {cf_name}, which is not opened"
+        )));
+    }
+    let options = db_options.unwrap_or_else(|| default_db_options().options);
+    let column_descriptors: Vec<_> = opt_cfs
+        .iter()
+        .map(|name| {
+            let mut cf_options = options.clone();
+            if let Some((_, compression)) = compressions.iter().find(|(cf_name, _)| cf_name == name)
+            {
+                compression.apply(&mut cf_options);
+            }
+            (*name, cf_options)
+        })
+        .collect();
+    let db = open_cf_opts(path, Some(options), metric_conf, &column_descriptors)?;
+    for (cf_name, compression) in compressions {
+        db.record_cf_settings(cf_name, |settings| {
+            settings.compression = Some(*compression)
+        });
+    }
+    Ok(db)
+}
+
+/// Opens a database like [`open_cf`], configuring bloom filters for the given column families.
+///
+/// Bloom filters let point lookups such as [`Map::get`] and [`Map::multi_contains_keys`] skip SST
+/// files that cannot contain a key, saving disk reads for absent keys at the cost of about
+/// `bits_per_key` bits of memory per key. Each filter is added to the given block-based table
+/// options, which replace those of `db_options` for its column family only, e.g., the options
+/// returned by [`BlockCache::block_options`] to keep sharing a block cache; RocksDB does not
+/// expose the table options of `db_options` to extend them in place. All column families with a
+/// filter must be among `opt_cfs`. The filters are reported by [`RocksDB::bloom_filter`].
+#[tracing::instrument(level="debug", skip_all, fields(path = ?path.as_ref(), cf = ?opt_cfs), err)]
+pub fn open_cf_with_bloom_filters<P: AsRef<Path>>(
+    path: P,
+    db_options: Option<rocksdb::Options>,
+    metric_conf: MetricConf,
+    opt_cfs: &[&str],
+    bloom_filters: Vec<(&str, BloomFilter, BlockBasedOptions)>,
+) -> Result<Arc<RocksDB>, TypedStoreError> {
+    if let Some((cf_name, ..)) = bloom_filters
+        .iter()
+        .find(|(cf_name, ..)| !opt_cfs.contains(cf_name))
+    {
+        return Err(TypedStoreError::RocksDBError(format!(
+            "bloom filter given for column family {cf_name}, which is not opened"
+        )));
+    }
+    let options = db_options.unwrap_or_else(|| default_db_options().options);
+    let mut column_descriptors: Vec<_> = opt_cfs
+        .iter()
+        .map(|name| (*name, options.clone()))
+        .collect();
+    let mut filters = Vec::with_capacity(bloom_filters.len());
+    for (cf_name, bloom_filter, mut block_options) in bloom_filters {
+        let Some((_, cf_options)) = column_descriptors
+            .iter_mut()
+            .find(|(name, _)| *name == cf_name)
+        else {
+            continue;
+        };
+        block_options.set_bloom_filter(bloom_filter.bits_per_key, false);
+        if let FilterKind::Prefix { len } = bloom_filter.kind {
+            cf_options.set_prefix_extractor(SliceTransform::create_fixed_prefix(len));
+            block_options.set_whole_key_filtering(false);
+        }
+        cf_options.set_block_based_table_factory(&block_options);
+        filters.push((cf_name, bloom_filter));
+    }
+    let db = open_cf_opts(path, Some(options), metric_conf, &column_descriptors)?;
+    for (cf_name, bloom_filter) in filters {
+        db.record_cf_settings(cf_name, |settings| {
+            settings.bloom_filter = Some(bloom_filter)
+        });
+    }
+    Ok(db)
+}
+
+/// Opens a database like [`open_cf`], with the given integrity checks.
+///
+/// If the checks detect inconsistent database files, the database is not opened and the
+/// returned error, e.g., a [`TypedStoreError::Corruption`], describes the inconsistency.
+#[tracing::instrument(level="debug", skip_all, fields(path = ?path.as_ref(), cf = ?opt_cfs), err)]
+pub fn open_cf_with_integrity_checks<P: AsRef<Path>>(
+    path: P,
+    db_options: Option<rocksdb::Options>,
+    metric_conf: MetricConf,
+    opt_cfs: &[&str],
+    integrity_checks: IntegrityChecks,
+) -> Result<Arc<RocksDB>, TypedStoreError> {
+    let path = path.as_ref();
+    let mut options = db_options.unwrap_or_else(|| default_db_options().options);
+    integrity_checks.apply(&mut options);
+    open_cf(path, Some(options), metric_conf, opt_cfs).map_err(|error| {
+        let context = |message: String| {
+            format!(
+                "failed to open {} with integrity checks: {message}",
+                path.display()
+            )
+        };
+        match error {
+            TypedStoreError::RocksDBError(message) => {
+                TypedStoreError::RocksDBError(context(message))
+            }
+            TypedStoreError::Corruption(message) => TypedStoreError::Corruption(context(message)),
+            error => error,
+        }
+    })
+}
+
+/// Opens a database like [`open_cf`], ordering the keys of the given column families with their
+/// comparators.
+///
+/// All column families with a comparator must be among `opt_cfs`. **Each of them must be opened
+/// with the same comparator whenever the database is reopened**, see [`Comparator`]. The
+/// comparators are reported by [`RocksDB::comparator`].
+#[tracing::instrument(level="debug", skip_all, fields(path = ?path.as_ref(), cf = ?opt_cfs), err)]
+pub fn open_cf_with_comparators<P: AsRef<Path>>(
+    path: P,
+    db_options: Option<rocksdb::Options>,
+    metric_conf: MetricConf,
+    opt_cfs: &[&str],
+    comparators: &[(&str, Comparator)],
+) -> Result<Arc<RocksDB>, TypedStoreError> {
+    if let Some((cf_name, _)) = comparators
+        .iter()
+        .find(|(cf_name, _)| !opt_cfs.contains(cf_name))
+    {
+        return Err(TypedStoreError::RocksDBError(format!(
+            "comparator given for column family {cf_name}, which is not opened"
+        )));
+    }
+    let options = db_options.unwrap_or_else(|| default_db_options().options);
+    let column_descriptors: Vec<_> = opt_cfs
+        .iter()
+        .map(|name| {
+            let mut cf_options = options.clone();
+            if let Some((_, comparator)) = comparators.iter().find(|(cf_name, _)| cf_name == name) {
+                comparator.apply(&mut cf_options);
+            }
+            (*name, cf_options)
+        })
+        .collect();
+    let db = open_cf_opts(path, Some(options), metric_conf, &column_descriptors)?;
+    for (cf_name, comparator) in comparators {
+        db.record_comparator(cf_name, comparator);
+    }
+    Ok(db)
+}
+
 fn prepare_db_options(db_options: Option<rocksdb::Options>) -> rocksdb::Options {
     // Customize database options.
     let mut options = db_options.unwrap_or_else(|| default_db_options().options);
@@ -2427,7 +6665,8 @@ pub fn open_cf_opts<P: AsRef<Path>>(
     // This is a no-op in non-simulator builds.
 
     let cfs = populate_missing_cfs(opt_cfs, path).map_err(typed_store_err_from_rocks_err)?;
-    sui_macros::nondeterministic!({
+    let cf_names: Vec<_> = cfs.iter().map(|(name, _)| name.clone()).collect();
+    let db = sui_macros::nondeterministic!({
         let options = prepare_db_options(db_options);
         let rocksdb = {
             rocksdb::DBWithThreadMode::<MultiThreaded>::open_cf_descriptors(
@@ -2443,8 +6682,12 @@ pub fn open_cf_opts<P: AsRef<Path>>(
             metric_conf,
             PathBuf::from(path),
             options,
+            AccessMode::ReadWrite,
+            cf_names,
         ))))
-    })
+    })?;
+    db.start_event_listener();
+    Ok(db)
 }
 
 /// Opens an OptimisticTransactionDB with options, and a number of column families with.
@@ -2458,7 +6701,8 @@ pub fn open_cf_opts_optimistic<P: AsRef<Path>>(
 ) -> Result<Arc<RocksDB>, TypedStoreError> {
     let path = path.as_ref();
     let cfs = populate_missing_cfs(opt_cfs, path).map_err(typed_store_err_from_rocks_err)?;
-    sui_macros::nondeterministic!({
+    let cf_names: Vec<_> = cfs.iter().map(|(name, _)| name.clone()).collect();
+    let db = sui_macros::nondeterministic!({
         let options = prepare_db_options(db_options);
         rocksdb::OptimisticTransactionDB::open_cf_descriptors(
             &options,
@@ -2468,11 +6712,20 @@ pub fn open_cf_opts_optimistic<P: AsRef<Path>>(
         )
         .map(|db| {
             Arc::new(RocksDB::OptimisticTransactionDB(
-                OptimisticTransactionDBWrapper::new(db, metric_conf, PathBuf::from(path), options),
+                OptimisticTransactionDBWrapper::new(
+                    db,
+                    metric_conf,
+                    PathBuf::from(path),
+                    options,
+                    AccessMode::ReadWrite,
+                    cf_names,
+                ),
             ))
         })
         .map_err(typed_store_err_from_rocks_err)
-    })
+    })?;
+    db.start_event_listener();
+    Ok(db)
 }
 
 /// Opens an OptimisticTransactionDB with options, and a number of column families that are created.
@@ -2493,6 +6746,85 @@ pub fn open_cf_optimistic<P: AsRef<Path>>(
     Ok(db)
 }
 
+/// Opens an existing database in read-only mode with the given column families.
+///
+/// The database does not take the write lock, so it can be opened alongside a writer. It only
+/// sees the data present when it was opened, and all writes through it fail with
+/// [`TypedStoreError::ReadOnlyDatabase`].
+#[tracing::instrument(level="debug", skip_all, fields(path = ?path.as_ref()), err)]
+pub fn open_cf_opts_readonly<P: AsRef<Path>>(
+    path: P,
+    db_options: Option<rocksdb::Options>,
+    metric_conf: MetricConf,
+    opt_cfs: &[(&str, rocksdb::Options)],
+) -> Result<Arc<RocksDB>, TypedStoreError> {
+    let path = path.as_ref();
+    sui_macros::nondeterministic!({
+        let options = db_options.unwrap_or_else(|| default_db_options().options);
+        let rocksdb = rocksdb::DBWithThreadMode::<MultiThreaded>::open_cf_descriptors_read_only(
+            &options,
+            path,
+            opt_cfs
+                .iter()
+                .map(|(name, opts)| ColumnFamilyDescriptor::new(*name, opts.clone())),
+            /* error_if_log_file_exist */ false,
+        )
+        .map_err(typed_store_err_from_rocks_err)?;
+        Ok(Arc::new(RocksDB::DB(DBWithThreadModeWrapper::new(
+            rocksdb,
+            metric_conf,
+            PathBuf::from(path),
+            options,
+            AccessMode::ReadOnly,
+            opt_cfs.iter().map(|(name, _)| name.to_string()),
+        ))))
+    })
+}
+
+/// Opens a secondary instance following the database at `primary_path`.
+///
+/// The secondary keeps its own info logs in `secondary_path` and sees the primary's writes as of
+/// the last call to [`RocksDB::try_catch_up_with_primary`]. All writes through it fail with
+/// [`TypedStoreError::ReadOnlyDatabase`].
+#[tracing::instrument(
+    level="debug",
+    skip_all,
+    fields(primary_path = ?primary_path.as_ref(), secondary_path = ?secondary_path.as_ref()),
+    err
+)]
+pub fn open_cf_opts_secondary<P: AsRef<Path>, S: AsRef<Path>>(
+    primary_path: P,
+    secondary_path: S,
+    db_options: Option<rocksdb::Options>,
+    metric_conf: MetricConf,
+    opt_cfs: &[(&str, rocksdb::Options)],
+) -> Result<Arc<RocksDB>, TypedStoreError> {
+    let primary_path = primary_path.as_ref();
+    let secondary_path = secondary_path.as_ref();
+    sui_macros::nondeterministic!({
+        let mut options = db_options.unwrap_or_else(|| default_db_options().options);
+        // Secondary instances must keep all files open to follow the primary's file deletions.
+        options.set_max_open_files(-1);
+        let rocksdb = rocksdb::DBWithThreadMode::<MultiThreaded>::open_cf_descriptors_as_secondary(
+            &options,
+            primary_path,
+            secondary_path,
+            opt_cfs
+                .iter()
+                .map(|(name, opts)| ColumnFamilyDescriptor::new(*name, opts.clone())),
+        )
+        .map_err(typed_store_err_from_rocks_err)?;
+        Ok(Arc::new(RocksDB::DB(DBWithThreadModeWrapper::new(
+            rocksdb,
+            metric_conf,
+            PathBuf::from(primary_path),
+            options,
+            AccessMode::Secondary,
+            opt_cfs.iter().map(|(name, _)| name.to_string()),
+        ))))
+    })
+}
+
 /// TODO: Good description of why we're doing this :
 /// RocksDB stores keys in BE and has a seek operator.
 /// on iterators, see `https://github.com/facebook/rocksdb/wiki/Iterator#introduction`
@@ -2514,6 +6846,25 @@ pub fn safe_drop_db(path: PathBuf) -> Result<(), rocksdb::Error> {
     rocksdb::DB::destroy(&rocksdb::Options::default(), path)
 }
 
+/// Deserializes the values returned by a multi-get, keeping missing values as `None`.
+fn decode_multi_get_values<K, V: DeserializeOwned>(
+    codec: &MapCodec<K, V>,
+    results: Vec<Option<DBPinnableSlice<'_>>>,
+) -> Result<Vec<Option<V>>, TypedStoreError> {
+    results
+        .into_iter()
+        .map(|value_byte| value_byte.map(|data| codec.decode_value(&data)).transpose())
+        .collect()
+}
+
+/// Describes a serialized value, or its absence, for error messages.
+fn describe_value(value: Option<&[u8]>) -> String {
+    match value {
+        Some(value) => format!("a value of {} bytes", value.len()),
+        None => "no value".to_owned(),
+    }
+}
+
 /// Populate missing column families.
 fn populate_missing_cfs(
     input_cfs: &[(&str, rocksdb::Options)],
@@ -2539,6 +6890,36 @@ fn populate_missing_cfs(
     Ok(cfs)
 }
 
+/// Reads the current options of the column family from the latest options file of the database.
+///
+/// RocksDB persists the options whenever they change, and the bindings offer no other way to read
+/// them back.
+fn read_cf_options(
+    db_path: &Path,
+    cf_name: &str,
+) -> Result<HashMap<String, String>, TypedStoreError> {
+    let io_err = |e: std::io::Error| TypedStoreError::RocksDBError(e.to_string());
+    let (_, latest_file) = fs::read_dir(db_path)
+        .map_err(io_err)?
+        .filter_map(|entry| {
+            let file_name = entry.ok()?.file_name().into_string().ok()?;
+            let number: u64 = file_name.strip_prefix("OPTIONS-")?.parse().ok()?;
+            Some((number, file_name))
+        })
+        .max()
+        .ok_or_else(|| TypedStoreError::RocksDBError("no options file found".into()))?;
+    let contents = fs::read_to_string(db_path.join(latest_file)).map_err(io_err)?;
+    let section = format!("[CFOptions \"{cf_name}\"]");
+    Ok(contents
+        .lines()
+        .skip_while(|line| line.trim() != section)
+        .skip(1)
+        .take_while(|line| !line.trim_start().starts_with('['))
+        .filter_map(|line| line.trim().split_once('='))
+        .map(|(name, value)| (name.to_owned(), value.to_owned()))
+        .collect())
+}
+
 /// Given a vec<u8>, find the value which is one more than the vector.
 /// if the vector was a big endian number.
 /// If the vector is already minimum, don't change it.
@@ -2556,6 +6937,16 @@ fn big_endian_saturating_add_one(v: &mut [u8]) {
     }
 }
 
+/// Turns a serialized key into an exclusive RocksDB upper bound that still includes the key.
+///
+/// Appending a zero byte yields the smallest byte string that sorts strictly after the key, so
+/// this is correct for variable-length encodings, where incrementing the last byte would also
+/// admit longer keys sharing the same prefix.
+fn inclusive_upper_bound(mut key_buf: Vec<u8>) -> Vec<u8> {
+    key_buf.push(0);
+    key_buf
+}
+
 /// Check if all the bytes in the vector are 0xFF.
 fn is_max(v: &[u8]) -> bool {
     v.iter().all(|&x| x == u8::MAX)
@@ -0,0 +1,255 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{borrow::Borrow, fmt, sync::Arc, time::Instant};
+
+use futures::{Stream, StreamExt, stream};
+use prometheus::IntGauge;
+use serde::{Serialize, de::DeserializeOwned};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, mpsc};
+
+use super::DBMap;
+use crate::{Map, TypedStoreError};
+
+/// The default number of blocking reads an [`AsyncDBMap`] runs concurrently.
+pub const DEFAULT_MAX_CONCURRENT_READS: usize = 16;
+
+/// The number of entries buffered between a blocking iterator and its async consumer.
+const ITER_CHANNEL_CAPACITY: usize = 1024;
+
+/// The number of entries handed over at once by the iterator behind [`AsyncDBMap::stream`] and
+/// [`DBMap::safe_stream`].
+const STREAM_CHUNK_SIZE: usize = 256;
+
+/// The number of chunks the iterator behind a stream may run ahead of its consumer.
+const STREAM_CHANNEL_CAPACITY: usize = 4;
+
+/// A slot in the blocking pool, held for the duration of a blocking read.
+struct PoolSlot {
+    _permit: OwnedSemaphorePermit,
+    in_flight: IntGauge,
+}
+
+impl PoolSlot {
+    fn new(permit: OwnedSemaphorePermit, in_flight: IntGauge) -> Self {
+        in_flight.inc();
+        Self {
+            _permit: permit,
+            in_flight,
+        }
+    }
+}
+
+impl Drop for PoolSlot {
+    fn drop(&mut self) {
+        self.in_flight.dec();
+    }
+}
+
+/// A wrapper around a [`DBMap`] that runs reads and writes on Tokio's blocking thread pool.
+///
+/// At most a fixed number of operations run at once, so heavy load neither stalls the async
+/// runtime nor exhausts the blocking pool. Saturation is reported through the
+/// `rocksdb_async_reads_in_flight` and `rocksdb_async_read_wait_seconds` metrics.
+pub struct AsyncDBMap<K, V> {
+    map: Arc<DBMap<K, V>>,
+    permits: Arc<Semaphore>,
+}
+
+impl<K, V> fmt::Debug for AsyncDBMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AsyncDBMap {{ cf: {:?} }}", self.map.cf)
+    }
+}
+
+impl<K, V> Clone for AsyncDBMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+            permits: self.permits.clone(),
+        }
+    }
+}
+
+impl<K, V> AsyncDBMap<K, V>
+where
+    K: Serialize + DeserializeOwned + Send + 'static,
+    V: Serialize + DeserializeOwned + Send + 'static,
+{
+    /// Wraps the map, allowing at most `max_concurrent_reads` blocking operations at a time.
+    pub fn new(map: DBMap<K, V>, max_concurrent_reads: usize) -> Self {
+        Self {
+            map: Arc::new(map),
+            permits: Arc::new(Semaphore::new(max_concurrent_reads.max(1))),
+        }
+    }
+
+    /// Returns the wrapped map.
+    pub fn inner(&self) -> &DBMap<K, V> {
+        &self.map
+    }
+
+    /// Returns the value for the given key from the map, if it exists.
+    pub async fn get(&self, key: K) -> Result<Option<V>, TypedStoreError> {
+        self.run(move |map| map.get(&key)).await
+    }
+
+    /// Returns the values for the given keys, in the same order as the keys.
+    pub async fn multi_get(&self, keys: Vec<K>) -> Result<Vec<Option<V>>, TypedStoreError> {
+        self.run(move |map| map.multi_get(keys)).await
+    }
+
+    /// Returns true if the map contains a value for the specified key.
+    pub async fn contains_key(&self, key: K) -> Result<bool, TypedStoreError> {
+        self.run(move |map| map.contains_key(&key)).await
+    }
+
+    /// Inserts the key-value pair into the map.
+    pub async fn insert(&self, key: K, value: V) -> Result<(), TypedStoreError> {
+        self.run(move |map| map.insert(&key, &value)).await
+    }
+
+    /// Iterates over all entries of the map on a blocking thread.
+    ///
+    /// Entries are handed over through a bounded channel, so the blocking iterator only runs
+    /// ahead of the consumer by a limited number of entries. The iterator holds a pool slot until
+    /// it is exhausted or the returned [`AsyncIter`] is dropped.
+    pub async fn iter(&self) -> AsyncIter<K, V> {
+        let slot = self.acquire().await;
+        let map = self.map.clone();
+        let (sender, receiver) = mpsc::channel(ITER_CHANNEL_CAPACITY);
+        tokio::task::spawn_blocking(move || {
+            let _slot = slot;
+            let iter = match map.safe_iter() {
+                Ok(iter) => iter,
+                Err(error) => {
+                    let _ = sender.blocking_send(Err(error));
+                    return;
+                }
+            };
+            for item in iter {
+                if sender.blocking_send(item).is_err() {
+                    // The consumer is gone.
+                    return;
+                }
+            }
+        });
+        AsyncIter { receiver }
+    }
+
+    /// Streams the entries of the map in the same order as [`Map::safe_iter`].
+    ///
+    /// Like [`Self::iter`], but the entries are handed over in chunks, so the blocking iterator
+    /// only runs a few chunks ahead of the consumer. The iterator holds a pool slot until it is
+    /// exhausted or the returned stream is dropped.
+    pub async fn stream(&self) -> impl Stream<Item = Result<(K, V), TypedStoreError>> + 'static {
+        let slot = self.acquire().await;
+        stream_blocking(self.map.clone(), slot)
+    }
+
+    async fn run<R, F>(&self, f: F) -> Result<R, TypedStoreError>
+    where
+        F: FnOnce(&DBMap<K, V>) -> Result<R, TypedStoreError> + Send + 'static,
+        R: Send + 'static,
+    {
+        let slot = self.acquire().await;
+        run_blocking(self.map.clone(), slot, f).await
+    }
+
+    async fn acquire(&self) -> PoolSlot {
+        let start = Instant::now();
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the semaphore is never closed");
+        let op_metrics = &self.map.db_metrics.op_metrics;
+        op_metrics
+            .rocksdb_async_read_wait_seconds
+            .with_label_values(&[&self.map.cf])
+            .observe(start.elapsed().as_secs_f64());
+        PoolSlot::new(
+            permit,
+            op_metrics
+                .rocksdb_async_reads_in_flight
+                .with_label_values(&[&self.map.cf]),
+        )
+    }
+}
+
+/// Runs `f` on the map on Tokio's blocking thread pool, holding `guard` until `f` returns.
+pub(super) async fn run_blocking<M, K, V, G, R, F>(
+    map: M,
+    guard: G,
+    f: F,
+) -> Result<R, TypedStoreError>
+where
+    M: Borrow<DBMap<K, V>> + Send + 'static,
+    G: Send + 'static,
+    F: FnOnce(&DBMap<K, V>) -> Result<R, TypedStoreError> + Send + 'static,
+    R: Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        let _guard = guard;
+        f(map.borrow())
+    })
+    .await?
+}
+
+/// Streams the entries of the map from an iterator on Tokio's blocking thread pool, handing them
+/// over in chunks through a bounded channel.
+///
+/// `guard` is held until the iterator is exhausted or the returned stream is dropped.
+pub(super) fn stream_blocking<M, K, V, G>(
+    map: M,
+    guard: G,
+) -> impl Stream<Item = Result<(K, V), TypedStoreError>> + 'static
+where
+    M: Borrow<DBMap<K, V>> + Send + 'static,
+    K: Serialize + DeserializeOwned + Send + 'static,
+    V: Serialize + DeserializeOwned + Send + 'static,
+    G: Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(STREAM_CHANNEL_CAPACITY);
+    tokio::task::spawn_blocking(move || {
+        let _guard = guard;
+        let mut iter = match map.borrow().safe_iter() {
+            Ok(iter) => iter,
+            Err(error) => {
+                let _ = sender.blocking_send(vec![Err(error)]);
+                return;
+            }
+        };
+        loop {
+            let chunk: Vec<_> = iter.by_ref().take(STREAM_CHUNK_SIZE).collect();
+            // Stop once the iterator is exhausted or the stream was dropped.
+            if chunk.is_empty() || sender.blocking_send(chunk).is_err() {
+                return;
+            }
+        }
+    });
+    stream::unfold(receiver, |mut receiver| async move {
+        let chunk = receiver.recv().await?;
+        Some((stream::iter(chunk), receiver))
+    })
+    .flatten()
+}
+
+/// The entries of an [`AsyncDBMap`], produced by an iterator running on a blocking thread.
+pub struct AsyncIter<K, V> {
+    receiver: mpsc::Receiver<Result<(K, V), TypedStoreError>>,
+}
+
+impl<K, V> fmt::Debug for AsyncIter<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AsyncIter")
+    }
+}
+
+impl<K, V> AsyncIter<K, V> {
+    /// Returns the next entry, or `None` once the iterator is exhausted.
+    pub async fn next(&mut self) -> Option<Result<(K, V), TypedStoreError>> {
+        self.receiver.recv().await
+    }
+}
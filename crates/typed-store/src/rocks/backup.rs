@@ -0,0 +1,126 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use rocksdb::{
+    Env,
+    backup::{BackupEngineInfo, BackupEngineOptions, RestoreOptions},
+};
+
+use super::RocksDB;
+use crate::{TypedStoreError, rocks::errors::typed_store_err_from_rocks_err};
+
+/// Metadata of a single backup in a [`BackupEngine`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackupInfo {
+    /// The ID of the backup, increasing with each backup taken.
+    pub id: u32,
+    /// The creation time of the backup, in seconds since the Unix epoch.
+    pub timestamp: i64,
+    /// The total size of the files referenced by the backup, in bytes.
+    ///
+    /// Files shared with other backups are counted in full for each of them.
+    pub size: u64,
+    /// The number of files referenced by the backup.
+    pub num_files: u32,
+}
+
+impl From<BackupEngineInfo> for BackupInfo {
+    fn from(info: BackupEngineInfo) -> Self {
+        Self {
+            id: info.backup_id,
+            timestamp: info.timestamp,
+            size: info.size,
+            num_files: info.num_files,
+        }
+    }
+}
+
+/// Incremental backups of a [`RocksDB`] stored in a backup directory.
+///
+/// Unlike [`RocksDB::checkpoint`], which produces a full copy of the database, backups taken
+/// through the same engine share identical SST files, so each new backup only stores the files
+/// created since the previous one.
+pub struct BackupEngine {
+    engine: rocksdb::backup::BackupEngine,
+    backup_dir: PathBuf,
+}
+
+impl fmt::Debug for BackupEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BackupEngine {{ backup_dir: {:?} }}", self.backup_dir)
+    }
+}
+
+impl BackupEngine {
+    /// Opens the backup engine on the given directory, creating the directory if needed.
+    pub fn open(backup_dir: impl AsRef<Path>) -> Result<Self, TypedStoreError> {
+        let backup_dir = backup_dir.as_ref().to_path_buf();
+        let env = Env::new().map_err(typed_store_err_from_rocks_err)?;
+        let options =
+            BackupEngineOptions::new(&backup_dir).map_err(typed_store_err_from_rocks_err)?;
+        let engine = rocksdb::backup::BackupEngine::open(&options, &env)
+            .map_err(typed_store_err_from_rocks_err)?;
+        Ok(Self { engine, backup_dir })
+    }
+
+    /// Returns the directory the backups are stored in.
+    pub fn backup_dir(&self) -> &Path {
+        &self.backup_dir
+    }
+
+    /// Flushes the memtables of the database and takes a new backup of it.
+    ///
+    /// Only the SST files not already stored by an earlier backup are copied.
+    pub fn create_backup(&mut self, db: &RocksDB) -> Result<BackupInfo, TypedStoreError> {
+        db.create_new_backup_flush(&mut self.engine, true)?;
+        self.list_backups()
+            .pop()
+            .ok_or_else(|| TypedStoreError::RocksDBError("the new backup is not listed".into()))
+    }
+
+    /// Returns the metadata of all backups, ordered from oldest to newest.
+    pub fn list_backups(&self) -> Vec<BackupInfo> {
+        let mut backups: Vec<BackupInfo> = self
+            .engine
+            .get_backup_info()
+            .into_iter()
+            .map(BackupInfo::from)
+            .collect();
+        backups.sort_by_key(|info| info.id);
+        backups
+    }
+
+    /// Restores the latest backup in `backup_dir` into `db_dir`.
+    ///
+    /// The database at `db_dir` must not be open; its contents are replaced by the backup.
+    pub fn restore_from_latest(
+        backup_dir: impl AsRef<Path>,
+        db_dir: impl AsRef<Path>,
+    ) -> Result<(), TypedStoreError> {
+        let db_dir = db_dir.as_ref();
+        Self::open(backup_dir)?
+            .engine
+            .restore_from_latest_backup(db_dir, db_dir, &RestoreOptions::default())
+            .map_err(typed_store_err_from_rocks_err)
+    }
+
+    /// Restores the backup with the given ID in `backup_dir` into `db_dir`.
+    ///
+    /// The database at `db_dir` must not be open; its contents are replaced by the backup.
+    pub fn restore(
+        backup_dir: impl AsRef<Path>,
+        backup_id: u32,
+        db_dir: impl AsRef<Path>,
+    ) -> Result<(), TypedStoreError> {
+        let db_dir = db_dir.as_ref();
+        Self::open(backup_dir)?
+            .engine
+            .restore_from_backup(db_dir, db_dir, &RestoreOptions::default(), backup_id)
+            .map_err(typed_store_err_from_rocks_err)
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::hash_map::Entry, fmt, sync::PoisonError};
+
+use super::{DBMap, read_cf_options};
+use crate::{TypedStoreError, rocks::errors::typed_store_err_from_rocks_err};
+
+/// The column family options changed for the duration of a bulk load, with their values during
+/// the load.
+const BULK_LOAD_OPTIONS: [(&str, &str); 3] = [
+    // Compactions would compete with the writes for disk bandwidth. Without automatic compactions,
+    // RocksDB also does not stall writes because of the number of L0 files.
+    ("disable_auto_compactions", "true"),
+    // Larger and more memtables produce fewer L0 files to compact after the load.
+    ("write_buffer_size", "268435456"),
+    ("max_write_buffer_number", "6"),
+];
+
+/// The bulk loads in progress on a column family.
+#[derive(Debug)]
+pub(super) struct BulkLoadState {
+    /// The number of guards currently held for the column family.
+    guards: usize,
+    /// The values of the changed options before the first guard was taken, restored when the
+    /// last guard is released.
+    original_options: Vec<(&'static str, String)>,
+}
+
+/// Speeds up bulk loads into a column family, obtained through [`DBMap::bulk_load_guard`].
+///
+/// While a guard is held, automatic compactions of the column family are disabled and its
+/// memtables are larger. Guards of the same column family may be nested; the original options are
+/// restored once the last of them is released.
+///
+/// Call [`Self::finish`] to release the guard and compact the column family. Dropping the guard
+/// only restores the options and leaves the compaction to RocksDB's automatic compactions; errors
+/// while doing so are logged.
+pub struct BulkLoadGuard<'a, K, V> {
+    map: &'a DBMap<K, V>,
+    /// Whether the guard was already released through [`Self::finish`].
+    released: bool,
+}
+
+impl<K, V> fmt::Debug for BulkLoadGuard<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BulkLoadGuard {{ cf: {} }}", self.map.cf)
+    }
+}
+
+impl<'a, K, V> BulkLoadGuard<'a, K, V> {
+    pub(super) fn new(map: &'a DBMap<K, V>) -> Result<Self, TypedStoreError> {
+        map.rocksdb.ensure_writable()?;
+        let mut bulk_loads = map
+            .rocksdb
+            .bulk_loads()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(state) = bulk_loads.get_mut(&map.cf) {
+            state.guards += 1;
+            return Ok(Self {
+                map,
+                released: false,
+            });
+        }
+
+        // No other guard changed the options, so the options file holds their original values.
+        let current_options = read_cf_options(map.rocksdb.path(), &map.cf)?;
+        let original_options = BULK_LOAD_OPTIONS
+            .iter()
+            .map(|(name, _)| {
+                let value = current_options.get(*name).ok_or_else(|| {
+                    TypedStoreError::RocksDBError(format!(
+                        "option {name} of column family {} not found",
+                        map.cf
+                    ))
+                })?;
+                Ok((*name, value.clone()))
+            })
+            .collect::<Result<_, TypedStoreError>>()?;
+        map.rocksdb
+            .set_options_cf(&map.cf()?, &BULK_LOAD_OPTIONS)
+            .map_err(typed_store_err_from_rocks_err)?;
+        bulk_loads.insert(
+            map.cf.clone(),
+            BulkLoadState {
+                guards: 1,
+                original_options,
+            },
+        );
+        Ok(Self {
+            map,
+            released: false,
+        })
+    }
+
+    /// Releases the guard and, if it was the last guard of the column family, restores the
+    /// original options and compacts the whole column family, blocking until the compaction is
+    /// done.
+    pub fn finish(mut self) -> Result<(), TypedStoreError> {
+        if self.release()? {
+            let cf = self.map.cf()?;
+            self.map
+                .rocksdb
+                .compact_range_cf(&cf, None::<Vec<u8>>, None::<Vec<u8>>);
+        }
+        Ok(())
+    }
+
+    /// Releases the guard, restoring the original options if it was the last guard of the column
+    /// family. Returns whether it was the last guard.
+    fn release(&mut self) -> Result<bool, TypedStoreError> {
+        self.released = true;
+        let mut bulk_loads = self
+            .map
+            .rocksdb
+            .bulk_loads()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let Entry::Occupied(mut entry) = bulk_loads.entry(self.map.cf.clone()) else {
+            return Ok(false);
+        };
+        entry.get_mut().guards -= 1;
+        if entry.get().guards > 0 {
+            return Ok(false);
+        }
+        let state = entry.remove();
+        let options: Vec<_> = state
+            .original_options
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+        self.map
+            .rocksdb
+            .set_options_cf(&self.map.cf()?, &options)
+            .map_err(typed_store_err_from_rocks_err)?;
+        Ok(true)
+    }
+}
+
+impl<K, V> Drop for BulkLoadGuard<'_, K, V> {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        if let Err(error) = self.release() {
+            tracing::error!(?error, cf = %self.map.cf, "failed to restore options after bulk load");
+        }
+    }
+}
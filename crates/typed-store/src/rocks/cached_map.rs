@@ -0,0 +1,230 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, HashMap},
+    fmt,
+    num::NonZeroUsize,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::DBMap;
+use crate::{Map, TypedStoreError};
+
+/// A [`DBMap`] with a bounded in-memory cache of recently read values.
+///
+/// Reads through [`CachedDBMap::get`] are served from the cache if possible, including for absent
+/// keys; the least recently used entries are evicted once the cache is full. Writes through the
+/// wrapper update or invalidate the cached entries of the written keys. Writes and cache fills
+/// of a key are serialized with the key locks of the database, so a read racing with a write
+/// never caches a stale value.
+///
+/// Writes that bypass the wrapper, e.g., through [`CachedDBMap::inner`], a [`DBBatch`], or
+/// another handle to the same column family, are not observed: the cache serves the old value
+/// until the key is evicted or explicitly invalidated with [`CachedDBMap::invalidate`].
+///
+/// [`DBBatch`]: super::DBBatch
+pub struct CachedDBMap<K, V> {
+    map: DBMap<K, V>,
+    cache: Mutex<LruCache<V>>,
+}
+
+impl<K, V> fmt::Debug for CachedDBMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CachedDBMap {{ cf: {} }}", self.map.cf)
+    }
+}
+
+impl<K, V> CachedDBMap<K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned + Clone,
+{
+    /// Wraps the map with a cache of at most `capacity` entries.
+    pub fn new(map: DBMap<K, V>, capacity: NonZeroUsize) -> Self {
+        Self {
+            map,
+            cache: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Returns the wrapped map. Writes through it bypass the cache.
+    pub fn inner(&self) -> &DBMap<K, V> {
+        &self.map
+    }
+
+    /// Returns the value for `key`, from the cache if possible.
+    pub fn get(&self, key: &K) -> Result<Option<V>, TypedStoreError> {
+        let key_buf = self.map.codec.encode_key(key)?;
+        if let Some(value) = self.lock_cache().get(&key_buf) {
+            return Ok(value);
+        }
+        let _guard = self.map.rocksdb.lock_key(&self.map.cf, &key_buf);
+        let value = self.map.get(key)?;
+        self.lock_cache().put(key_buf, value.clone());
+        Ok(value)
+    }
+
+    /// Inserts the key-value pair, updating the cache.
+    pub fn insert(&self, key: &K, value: &V) -> Result<(), TypedStoreError> {
+        let key_buf = self.map.codec.encode_key(key)?;
+        let _guard = self.map.rocksdb.lock_key(&self.map.cf, &key_buf);
+        let result = self.map.insert(key, value);
+        self.lock_cache()
+            .record_write(key_buf, &result, Some(value.clone()));
+        result
+    }
+
+    /// Removes the key, updating the cache.
+    pub fn remove(&self, key: &K) -> Result<(), TypedStoreError> {
+        let key_buf = self.map.codec.encode_key(key)?;
+        let _guard = self.map.rocksdb.lock_key(&self.map.cf, &key_buf);
+        let result = self.map.remove(key);
+        self.lock_cache().record_write(key_buf, &result, None);
+        result
+    }
+
+    /// Inserts the key-value pairs in a single batch, updating the cache.
+    pub fn multi_insert<J, U>(
+        &self,
+        key_val_pairs: impl IntoIterator<Item = (J, U)>,
+    ) -> Result<(), TypedStoreError>
+    where
+        J: Borrow<K>,
+        U: Borrow<V>,
+    {
+        let pairs: Vec<_> = key_val_pairs.into_iter().collect();
+        let keys_bytes = pairs
+            .iter()
+            .map(|(key, _)| self.map.codec.encode_key(key.borrow()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let _guards = self.map.rocksdb.lock_keys(&self.map.cf, &keys_bytes);
+        let result = self.map.multi_insert(
+            pairs
+                .iter()
+                .map(|(key, value)| (key.borrow(), value.borrow())),
+        );
+        let mut cache = self.lock_cache();
+        for (key_buf, (_, value)) in keys_bytes.into_iter().zip(&pairs) {
+            cache.record_write(key_buf, &result, Some(value.borrow().clone()));
+        }
+        result
+    }
+
+    /// Removes the keys in a single batch, updating the cache.
+    pub fn multi_remove<J>(&self, keys: impl IntoIterator<Item = J>) -> Result<(), TypedStoreError>
+    where
+        J: Borrow<K>,
+    {
+        let keys: Vec<_> = keys.into_iter().collect();
+        let keys_bytes = keys
+            .iter()
+            .map(|key| self.map.codec.encode_key(key.borrow()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let _guards = self.map.rocksdb.lock_keys(&self.map.cf, &keys_bytes);
+        let result = self.map.multi_remove(keys.iter().map(Borrow::borrow));
+        let mut cache = self.lock_cache();
+        for key_buf in keys_bytes {
+            cache.record_write(key_buf, &result, None);
+        }
+        result
+    }
+
+    /// Drops the cached entry of `key`, e.g., after writing it without going through the wrapper.
+    pub fn invalidate(&self, key: &K) -> Result<(), TypedStoreError> {
+        let key_buf = self.map.codec.encode_key(key)?;
+        self.lock_cache().remove(&key_buf);
+        Ok(())
+    }
+
+    /// Drops all cached entries.
+    pub fn invalidate_all(&self) {
+        self.lock_cache().clear();
+    }
+
+    /// Returns the number of cached entries.
+    pub fn cached_len(&self) -> usize {
+        self.lock_cache().entries.len()
+    }
+
+    fn lock_cache(&self) -> MutexGuard<'_, LruCache<V>> {
+        // Every operation leaves the cache consistent, so a poisoned lock can be ignored.
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A least-recently-used cache of values, including absent values, keyed by serialized keys.
+struct LruCache<V> {
+    capacity: NonZeroUsize,
+    /// The cached values with the time of their last use.
+    entries: HashMap<Vec<u8>, (Option<V>, u64)>,
+    /// The keys of the cached values by the time of their last use.
+    recency: BTreeMap<u64, Vec<u8>>,
+    /// The logical time, incremented on every use.
+    clock: u64,
+}
+
+impl<V: Clone> LruCache<V> {
+    fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Returns the cached value, where `Some(None)` means that the key is cached as absent.
+    fn get(&mut self, key: &[u8]) -> Option<Option<V>> {
+        let (value, last_used) = self.entries.get_mut(key)?;
+        let key = self
+            .recency
+            .remove(last_used)
+            .expect("cached keys have a recency");
+        self.clock += 1;
+        *last_used = self.clock;
+        self.recency.insert(self.clock, key);
+        Some(value.clone())
+    }
+
+    fn put(&mut self, key: Vec<u8>, value: Option<V>) {
+        self.remove(&key);
+        if self.entries.len() == self.capacity.get()
+            && let Some((_, evicted)) = self.recency.pop_first()
+        {
+            self.entries.remove(&evicted);
+        }
+        self.clock += 1;
+        self.recency.insert(self.clock, key.clone());
+        self.entries.insert(key, (value, self.clock));
+    }
+
+    /// Caches the value written for the key if the write succeeded, and drops the cached entry
+    /// otherwise, as a failed write may still have been applied.
+    fn record_write(
+        &mut self,
+        key: Vec<u8>,
+        result: &Result<(), TypedStoreError>,
+        value: Option<V>,
+    ) {
+        if result.is_ok() {
+            self.put(key, value);
+        } else {
+            self.remove(&key);
+        }
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        if let Some((_, last_used)) = self.entries.remove(key) {
+            self.recency.remove(&last_used);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fmt, sync::Arc};
+
+use bincode::Options;
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::be_fix_int_ser;
+use crate::{
+    TypedStoreError,
+    rocks::errors::{typed_store_err_from_bcs_err, typed_store_err_from_bincode_err},
+};
+
+/// Encodes values of type `T` to the bytes stored in RocksDB and decodes them back.
+///
+/// A [`DBMap`](super::DBMap) opened with [`DBMap::open_with_codec`](super::DBMap::open_with_codec)
+/// uses the given codecs for its keys and values instead of [`BigEndianCodec`] and [`BcsCodec`].
+/// Keys are ordered by their encoded bytes, so the key codec determines the iteration order.
+pub trait Codec<T>: Send + Sync {
+    /// Encodes the value.
+    fn encode(&self, value: &T) -> Result<Vec<u8>, TypedStoreError>;
+
+    /// Decodes a value previously encoded with [`Self::encode`].
+    fn decode(&self, bytes: &[u8]) -> Result<T, TypedStoreError>;
+}
+
+/// The default key codec: bincode with big-endian, fixed-size integers.
+///
+/// Integer keys, and tuples and structs of them, iterate in numeric order.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BigEndianCodec;
+
+impl<T: Serialize + DeserializeOwned> Codec<T> for BigEndianCodec {
+    fn encode(&self, value: &T) -> Result<Vec<u8>, TypedStoreError> {
+        be_fix_int_ser(value)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<T, TypedStoreError> {
+        be_fix_int_deser(bytes)
+    }
+}
+
+/// The default value codec: BCS.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BcsCodec;
+
+impl<T: Serialize + DeserializeOwned> Codec<T> for BcsCodec {
+    fn encode(&self, value: &T) -> Result<Vec<u8>, TypedStoreError> {
+        bcs::to_bytes(value).map_err(typed_store_err_from_bcs_err)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<T, TypedStoreError> {
+        bcs::from_bytes(bytes).map_err(typed_store_err_from_bcs_err)
+    }
+}
+
+/// Bincode with its default options: little-endian, variable-length integers.
+///
+/// More compact than [`BcsCodec`] for values with many small integers. As it is not
+/// order-preserving for integers, it is not suited as a key codec for range scans.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BincodeCodec;
+
+impl<T: Serialize + DeserializeOwned> Codec<T> for BincodeCodec {
+    fn encode(&self, value: &T) -> Result<Vec<u8>, TypedStoreError> {
+        bincode::DefaultOptions::new()
+            .serialize(value)
+            .map_err(typed_store_err_from_bincode_err)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<T, TypedStoreError> {
+        bincode::DefaultOptions::new()
+            .deserialize(bytes)
+            .map_err(typed_store_err_from_bincode_err)
+    }
+}
+
+/// Decodes a key encoded with [`be_fix_int_ser`].
+fn be_fix_int_deser<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, TypedStoreError> {
+    bincode::DefaultOptions::new()
+        .with_big_endian()
+        .with_fixint_encoding()
+        .deserialize(bytes)
+        .map_err(typed_store_err_from_bincode_err)
+}
+
+/// The key and value codecs of a map, falling back to the default codecs if none are set.
+pub(crate) struct MapCodec<K, V> {
+    key: Option<Arc<dyn Codec<K>>>,
+    value: Option<Arc<dyn Codec<V>>>,
+}
+
+impl<K, V> MapCodec<K, V> {
+    pub(crate) fn new(key: Arc<dyn Codec<K>>, value: Arc<dyn Codec<V>>) -> Self {
+        Self {
+            key: Some(key),
+            value: Some(value),
+        }
+    }
+
+    /// Returns true if keys are encoded with the default [`BigEndianCodec`].
+    pub(crate) fn has_default_key_codec(&self) -> bool {
+        self.key.is_none()
+    }
+
+    /// Returns the custom value codec, if any.
+    pub(crate) fn value_codec(&self) -> Option<Arc<dyn Codec<V>>> {
+        self.value.clone()
+    }
+
+    pub(crate) fn encode_key(&self, key: &K) -> Result<Vec<u8>, TypedStoreError>
+    where
+        K: Serialize,
+    {
+        match &self.key {
+            Some(codec) => codec.encode(key),
+            None => be_fix_int_ser(key),
+        }
+    }
+
+    pub(crate) fn decode_key(&self, bytes: &[u8]) -> Result<K, TypedStoreError>
+    where
+        K: DeserializeOwned,
+    {
+        match &self.key {
+            Some(codec) => codec.decode(bytes),
+            None => be_fix_int_deser(bytes),
+        }
+    }
+
+    pub(crate) fn encode_value(&self, value: &V) -> Result<Vec<u8>, TypedStoreError>
+    where
+        V: Serialize,
+    {
+        match &self.value {
+            Some(codec) => codec.encode(value),
+            None => bcs::to_bytes(value).map_err(typed_store_err_from_bcs_err),
+        }
+    }
+
+    pub(crate) fn decode_value(&self, bytes: &[u8]) -> Result<V, TypedStoreError>
+    where
+        V: DeserializeOwned,
+    {
+        match &self.value {
+            Some(codec) => codec.decode(bytes),
+            None => bcs::from_bytes(bytes).map_err(typed_store_err_from_bcs_err),
+        }
+    }
+}
+
+impl<K, V> Default for MapCodec<K, V> {
+    fn default() -> Self {
+        Self {
+            key: None,
+            value: None,
+        }
+    }
+}
+
+impl<K, V> Clone for MapCodec<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl<K, V> fmt::Debug for MapCodec<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapCodec")
+            .field("custom_key_codec", &self.key.is_some())
+            .field("custom_value_codec", &self.value.is_some())
+            .finish()
+    }
+}
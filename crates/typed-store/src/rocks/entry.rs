@@ -0,0 +1,195 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt;
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::DBMap;
+use crate::{Map, TypedStoreError, rocks::errors::typed_store_err_from_rocks_err};
+
+/// A view into a single key of a [`DBMap`], obtained through [`DBMap::entry`].
+///
+/// The entry does not lock the key while it lives, so code run between creating the entry and
+/// writing through it cannot block other writers. Instead, every write through the entry checks,
+/// under the key's lock and like [`DBMap::compare_and_swap`], that the key still has the value
+/// read when the entry was created. If the key was written in between, the write fails with
+/// [`TypedStoreError::RetryableTransactionError`] and leaves the key untouched, so the whole
+/// read-modify-write can be retried with a fresh entry.
+pub enum Entry<'a, K, V> {
+    /// The key is present in the map.
+    Occupied(OccupiedEntry<'a, K, V>),
+    /// The key is absent from the map.
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+impl<K, V> fmt::Debug for Entry<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Occupied(entry) => f.debug_tuple("Occupied").field(entry).finish(),
+            Self::Vacant(entry) => f.debug_tuple("Vacant").field(entry).finish(),
+        }
+    }
+}
+
+impl<'a, K, V> Entry<'a, K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    pub(super) fn new(
+        map: &'a DBMap<K, V>,
+        key: &'a K,
+        value_buf: Option<Vec<u8>>,
+    ) -> Result<Self, TypedStoreError> {
+        Ok(match value_buf {
+            Some(value_buf) => Self::Occupied(OccupiedEntry {
+                map,
+                key,
+                value: map.codec.decode_value(&value_buf)?,
+                value_buf,
+            }),
+            None => Self::Vacant(VacantEntry { map, key }),
+        })
+    }
+
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        match self {
+            Self::Occupied(entry) => entry.key,
+            Self::Vacant(entry) => entry.key,
+        }
+    }
+
+    /// Returns the value of the entry, inserting `default` if the key is absent.
+    pub fn or_insert(self, default: V) -> Result<V, TypedStoreError> {
+        self.or_insert_with(|| default)
+    }
+
+    /// Returns the value of the entry, inserting the result of `default` if the key is absent.
+    pub fn or_insert_with<F>(self, default: F) -> Result<V, TypedStoreError>
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Self::Occupied(entry) => Ok(entry.into_value()),
+            Self::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Modifies the value in place if the key is present.
+    ///
+    /// The modified value is only written back if `modify` changed its serialized form.
+    pub fn and_modify<F>(self, modify: F) -> Result<Self, TypedStoreError>
+    where
+        F: FnOnce(&mut V),
+    {
+        match self {
+            Self::Occupied(mut entry) => {
+                let before = entry.map.codec.encode_value(&entry.value)?;
+                modify(&mut entry.value);
+                let after = entry.map.codec.encode_value(&entry.value)?;
+                if after != before {
+                    write_if_unchanged(
+                        entry.map,
+                        entry.key,
+                        Some(&entry.value_buf),
+                        Some(&entry.value),
+                    )?;
+                    entry.value_buf = after;
+                }
+                Ok(Self::Occupied(entry))
+            }
+            vacant @ Self::Vacant(_) => Ok(vacant),
+        }
+    }
+}
+
+/// An entry of a key that is present in the map.
+pub struct OccupiedEntry<'a, K, V> {
+    map: &'a DBMap<K, V>,
+    key: &'a K,
+    value: V,
+    /// The stored value, as last read or written through the entry.
+    value_buf: Vec<u8>,
+}
+
+impl<K, V> fmt::Debug for OccupiedEntry<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "OccupiedEntry {{ cf: {:?} }}", self.map.cf)
+    }
+}
+
+impl<K, V> OccupiedEntry<'_, K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    /// Returns the value of the entry.
+    pub fn get(&self) -> &V {
+        &self.value
+    }
+
+    /// Consumes the entry, returning its value.
+    pub fn into_value(self) -> V {
+        self.value
+    }
+
+    /// Removes the key from the map, returning its value.
+    pub fn remove(self) -> Result<V, TypedStoreError> {
+        write_if_unchanged(self.map, self.key, Some(&self.value_buf), None)?;
+        Ok(self.value)
+    }
+}
+
+/// An entry of a key that is absent from the map.
+pub struct VacantEntry<'a, K, V> {
+    map: &'a DBMap<K, V>,
+    key: &'a K,
+}
+
+impl<K, V> fmt::Debug for VacantEntry<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VacantEntry {{ cf: {:?} }}", self.map.cf)
+    }
+}
+
+impl<K, V> VacantEntry<'_, K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    /// Inserts the value for the entry's key, returning it.
+    pub fn insert(self, value: V) -> Result<V, TypedStoreError> {
+        write_if_unchanged(self.map, self.key, None, Some(&value))?;
+        Ok(value)
+    }
+}
+
+/// Inserts `value` for `key`, or removes the key if `value` is `None`, provided that the stored
+/// value is still `expected`; otherwise, fails with [`TypedStoreError::RetryableTransactionError`].
+fn write_if_unchanged<K, V>(
+    map: &DBMap<K, V>,
+    key: &K,
+    expected: Option<&[u8]>,
+    value: Option<&V>,
+) -> Result<(), TypedStoreError>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    let key_buf = map.codec.encode_key(key)?;
+    let _guard = map.rocksdb.lock_key(&map.cf, &key_buf);
+    let current = map
+        .rocksdb
+        .get_pinned_cf_opt(&map.cf()?, &key_buf, &map.opts.readopts())
+        .map_err(typed_store_err_from_rocks_err)?;
+    if current.as_deref() != expected {
+        return Err(TypedStoreError::RetryableTransactionError);
+    }
+    drop(current);
+    match value {
+        Some(value) => map.insert(key, value),
+        None => map.remove(key),
+    }
+}
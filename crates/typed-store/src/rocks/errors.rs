@@ -89,12 +89,22 @@ pub fn typed_store_err_from_bcs_err(err: bcs::Error) -> TypedStoreError {
     TypedStoreError::SerializationError(format!("{err}"))
 }
 
+/// Convert the JSON error to the typed store error
+pub fn typed_store_err_from_json_err(err: serde_json::Error) -> TypedStoreError {
+    TypedStoreError::SerializationError(format!("{err}"))
+}
+
 /// Convert the rocksdb error to the typed store error
 pub fn typed_store_err_from_rocks_err(err: RocksError) -> TypedStoreError {
     match err.kind() {
         rocksdb::ErrorKind::Busy | rocksdb::ErrorKind::TryAgain => {
             TypedStoreError::RetryableTransactionError
         }
+        // Reads restricted to the cache report data that is only on disk as incomplete.
+        rocksdb::ErrorKind::Incomplete => TypedStoreError::NotInCache,
+        rocksdb::ErrorKind::ShutdownInProgress => TypedStoreError::DatabaseClosed,
+        // The message names the corrupt file, if known.
+        rocksdb::ErrorKind::Corruption => TypedStoreError::Corruption(err.into_string()),
         _ => TypedStoreError::RocksDBError(err.into_string()),
     }
 }
@@ -140,6 +150,48 @@ pub enum TypedStoreError {
     /// tokio tasks errors back to the caller.
     #[error("Task error: {0}")]
     TaskError(String),
+    /// A write was attempted through a database opened in read-only or secondary mode
+    #[error("cannot write to a database opened in read-only or secondary mode")]
+    ReadOnlyDatabase,
+    /// The key to be inserted is already present
+    #[error("the key already exists")]
+    KeyAlreadyExists,
+    /// A batch was rolled back to a save point without one being set
+    #[error("no save point is set on the batch")]
+    NoSavePoint,
+    /// The destination of a checkpoint already exists
+    #[error("the checkpoint destination {0} already exists")]
+    CheckpointExists(String),
+    /// The existing destination of a checkpoint is not a checkpoint that may be overwritten
+    #[error("refusing to overwrite checkpoint destination {0}")]
+    InvalidCheckpointDestination(String),
+    /// A key was added to an SST file that is not greater than the previously added key
+    #[error("keys must be added to an SST file in strictly increasing order")]
+    SstKeyOutOfOrder,
+    /// A read restricted to the cache required data that is not cached
+    #[error("the requested data is not in the cache")]
+    NotInCache,
+    /// The database was shut down
+    #[error("the database was shut down")]
+    DatabaseClosed,
+    /// The database cannot be closed while other handles to it exist
+    #[error("the database is still referenced by {0} other handles")]
+    DatabaseInUse(usize),
+    /// The database files are corrupt, e.g., a checksum does not match
+    #[error("database corruption: {0}")]
+    Corruption(String),
+    /// A secondary index of an indexed map is missing, duplicated, or misconfigured
+    #[error("secondary index error: {0}")]
+    IndexError(String),
+    /// The stored schema version of a map is newer than the version expected by the caller
+    #[error("the stored schema version {0} is newer than the expected version {1}")]
+    SchemaVersionTooNew(u64, u64),
+    /// An iteration did not complete before its deadline
+    #[error("the deadline of the iteration was exceeded")]
+    DeadlineExceeded,
+    /// A write read back in read-after-write verification mode does not match what was written
+    #[error("read-after-write verification failed: {0}")]
+    VerifyFailed(String),
 }
 
 /// The result type for the typed store
@@ -0,0 +1,167 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use rocksdb::properties;
+
+use super::RocksDB;
+
+/// An event affecting the write health of a database, reported to the event callback of its
+/// [`MetricConf`](super::MetricConf).
+///
+/// The Rust bindings do not expose RocksDB's `EventListener`, so events are detected by polling
+/// the database properties, see [`MetricConf::with_event_callback`](super::MetricConf). Changes
+/// between two polls are coalesced, e.g., several flushes of a column family are reported as a
+/// single event, and conditions that start and end between two polls go unnoticed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DbEvent {
+    /// Writes to the database started or stopped being delayed or stopped.
+    StallConditionsChanged {
+        /// The condition before the change.
+        previous: WriteStallCondition,
+        /// The condition after the change.
+        current: WriteStallCondition,
+    },
+    /// Memtables of a column family were flushed to SST files.
+    FlushCompleted {
+        /// The name of the column family.
+        cf_name: String,
+        /// The number of memtables flushed since the previous poll.
+        num_memtables: u64,
+    },
+    /// Background flushes or compactions failed.
+    BackgroundError {
+        /// The number of errors since the previous poll.
+        num_errors: u64,
+    },
+}
+
+/// Whether writes to a database are stalled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteStallCondition {
+    /// Writes proceed normally.
+    Normal,
+    /// Writes are delayed, e.g., because compactions fall behind.
+    Delayed,
+    /// Writes are stopped until background work catches up.
+    Stopped,
+}
+
+/// A callback invoked with every event of a database, see [`DbEvent`].
+pub type DbEventCallback = Arc<dyn Fn(DbEvent) + Send + Sync>;
+
+/// The state of a database from which events are derived.
+#[derive(Debug)]
+struct ObservedState {
+    stall_condition: WriteStallCondition,
+    background_errors: u64,
+    flushed_memtables: HashMap<String, u64>,
+}
+
+impl ObservedState {
+    fn read(db: &RocksDB, cf_names: &[String]) -> Self {
+        let property = |cf_name: &str, name| {
+            db.cf_handle(cf_name)
+                .and_then(|cf| db.property_int_value_cf(&cf, name).ok().flatten())
+                .unwrap_or_default()
+        };
+        // Database-wide properties can be read through any column family.
+        let default_cf = rocksdb::DEFAULT_COLUMN_FAMILY_NAME;
+        let stall_condition = if property(default_cf, properties::IS_WRITE_STOPPED) > 0 {
+            WriteStallCondition::Stopped
+        } else if property(default_cf, properties::ACTUAL_DELAYED_WRITE_RATE) > 0 {
+            WriteStallCondition::Delayed
+        } else {
+            WriteStallCondition::Normal
+        };
+        Self {
+            stall_condition,
+            background_errors: property(default_cf, properties::BACKGROUND_ERRORS),
+            flushed_memtables: cf_names
+                .iter()
+                .map(|cf_name| {
+                    let flushed = property(cf_name, properties::NUM_IMMUTABLE_MEM_TABLE_FLUSHED);
+                    (cf_name.clone(), flushed)
+                })
+                .collect(),
+        }
+    }
+
+    fn events_since(&self, previous: &Self) -> Vec<DbEvent> {
+        let mut events = vec![];
+        if self.stall_condition != previous.stall_condition {
+            events.push(DbEvent::StallConditionsChanged {
+                previous: previous.stall_condition,
+                current: self.stall_condition,
+            });
+        }
+        for (cf_name, flushed) in &self.flushed_memtables {
+            let previously_flushed = previous
+                .flushed_memtables
+                .get(cf_name)
+                .copied()
+                .unwrap_or_default();
+            if *flushed > previously_flushed {
+                events.push(DbEvent::FlushCompleted {
+                    cf_name: cf_name.clone(),
+                    num_memtables: flushed - previously_flushed,
+                });
+            }
+        }
+        if self.background_errors > previous.background_errors {
+            events.push(DbEvent::BackgroundError {
+                num_errors: self.background_errors - previous.background_errors,
+            });
+        }
+        events
+    }
+}
+
+/// Starts polling the database for events every `poll_interval`, until it is dropped.
+///
+/// Requires a tokio runtime; without one, no events are reported.
+pub(super) fn spawn_event_listener(
+    db: &Arc<RocksDB>,
+    poll_interval: Duration,
+    callback: DbEventCallback,
+) {
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        tracing::warn!("not reporting database events outside of a tokio runtime");
+        return;
+    };
+    let cf_names = db.cf_names();
+    let mut state = ObservedState::read(db, &cf_names);
+    let db = Arc::downgrade(db);
+    runtime.spawn(async move {
+        let mut interval = tokio::time::interval(poll_interval);
+        // The first tick completes immediately.
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let db = db.clone();
+            let cf_names = cf_names.clone();
+            let Ok(Some(current)) =
+                tokio::task::spawn_blocking(move || read_if_open(&db, &cf_names)).await
+            else {
+                break;
+            };
+            for event in current.events_since(&state) {
+                callback(event);
+            }
+            state = current;
+        }
+        tracing::debug!("stopped reporting database events");
+    });
+}
+
+/// Reads the state of the database, unless it was dropped or shut down.
+fn read_if_open(db: &Weak<RocksDB>, cf_names: &[String]) -> Option<ObservedState> {
+    let db = db.upgrade()?;
+    db.ensure_open().ok()?;
+    Some(ObservedState::read(&db, cf_names))
+}
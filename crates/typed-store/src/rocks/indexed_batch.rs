@@ -0,0 +1,100 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::{DBBatch, DBMap};
+use crate::{Map, TypedStoreError};
+
+/// A write batch that can read back its own staged writes.
+///
+/// Reads through [`IndexedBatch::get`] see the staged mutations of the batch overlaid on the
+/// committed state of the database, while the database itself only observes them once the batch is
+/// written. This provides the read-your-own-writes semantics of RocksDB's `WriteBatchWithIndex`,
+/// which the Rust bindings do not expose, by indexing the staged writes in memory alongside the
+/// underlying [`DBBatch`].
+pub struct IndexedBatch {
+    batch: DBBatch,
+    /// The latest staged value per column family and serialized key; `None` marks a deletion.
+    staged: HashMap<(String, Vec<u8>), Option<Vec<u8>>>,
+}
+
+impl fmt::Debug for IndexedBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IndexedBatch {{ staged: {} }}", self.staged.len())
+    }
+}
+
+impl IndexedBatch {
+    /// Creates an empty indexed batch wrapping the given batch.
+    pub fn new(batch: DBBatch) -> Self {
+        Self {
+            batch,
+            staged: HashMap::new(),
+        }
+    }
+
+    /// Stages an insertion of the key-value pair into the given map.
+    pub fn insert<K, V>(
+        &mut self,
+        db: &DBMap<K, V>,
+        key: &K,
+        value: &V,
+    ) -> Result<&mut Self, TypedStoreError>
+    where
+        K: Serialize,
+        V: Serialize,
+    {
+        self.check_db(db)?;
+        let key_buf = db.codec.encode_key(key)?;
+        let value_buf = db.codec.encode_value(value)?;
+        self.batch.record_key(&db.cf, &key_buf);
+        self.batch.batch.put_cf(&db.cf()?, &key_buf, &value_buf);
+        self.staged
+            .insert((db.cf.clone(), key_buf), Some(value_buf));
+        Ok(self)
+    }
+
+    /// Stages a removal of the key from the given map.
+    pub fn delete<K, V>(&mut self, db: &DBMap<K, V>, key: &K) -> Result<&mut Self, TypedStoreError>
+    where
+        K: Serialize,
+    {
+        self.check_db(db)?;
+        let key_buf = db.codec.encode_key(key)?;
+        self.batch.record_key(&db.cf, &key_buf);
+        self.batch.batch.delete_cf(&db.cf()?, &key_buf);
+        self.staged.insert((db.cf.clone(), key_buf), None);
+        Ok(self)
+    }
+
+    /// Returns the value for the key in the given map, taking staged writes into account.
+    pub fn get<K, V>(&self, db: &DBMap<K, V>, key: &K) -> Result<Option<V>, TypedStoreError>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        self.check_db(db)?;
+        let key_buf = db.codec.encode_key(key)?;
+        match self.staged.get(&(db.cf.clone(), key_buf)) {
+            Some(Some(value_buf)) => Ok(Some(db.codec.decode_value(value_buf)?)),
+            Some(None) => Ok(None),
+            None => db.get(key),
+        }
+    }
+
+    /// Consumes the batch and writes its operations to the database.
+    pub fn write(self) -> Result<(), TypedStoreError> {
+        self.batch.write()
+    }
+
+    fn check_db<K, V>(&self, db: &DBMap<K, V>) -> Result<(), TypedStoreError> {
+        if Arc::ptr_eq(&db.rocksdb, &self.batch.rocksdb) {
+            Ok(())
+        } else {
+            Err(TypedStoreError::CrossDBBatch)
+        }
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{any::Any, fmt, sync::Arc};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::{DBBatch, DBMap};
+use crate::{Map, TypedStoreError};
+
+/// A map with secondary indexes that are updated atomically with it.
+///
+/// Each index is a map from `(index key, primary key)` to `()` in a column family of the same
+/// database as the primary map, so several entries can share an index key. The extraction
+/// function of an index returns the index key of a value, or `None` to leave the entry out of the
+/// index.
+///
+/// [`IndexedMap::insert`] and [`IndexedMap::remove`] update the primary map and all indexes in a
+/// single batch, removing the index entries of a replaced value. Writes through the same
+/// `IndexedMap` are serialized per primary key, so concurrent writes to a key cannot leave stale
+/// index entries behind. Writes to the underlying maps bypass the indexes and must be avoided.
+pub struct IndexedMap<K, V> {
+    primary: DBMap<K, V>,
+    indexes: Vec<Box<dyn SecondaryIndex<K, V>>>,
+}
+
+impl<K, V> fmt::Debug for IndexedMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndexedMap")
+            .field("primary", &self.primary.cf)
+            .field(
+                "indexes",
+                &self
+                    .indexes
+                    .iter()
+                    .map(|index| index.name())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// The index-key-independent interface of an [`Index`].
+trait SecondaryIndex<K, V>: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Adds the index entry of the primary entry to the batch, if any.
+    fn add(&self, batch: &mut DBBatch, key: &K, value: &V) -> Result<(), TypedStoreError>;
+
+    /// Adds the removal of the index entry of the primary entry to the batch, if any.
+    fn remove(&self, batch: &mut DBBatch, key: &K, value: &V) -> Result<(), TypedStoreError>;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+struct Index<K, V, I> {
+    name: String,
+    map: DBMap<(I, K), ()>,
+    extract: Box<dyn Fn(&V) -> Option<I> + Send + Sync>,
+}
+
+impl<K, V, I> SecondaryIndex<K, V> for Index<K, V, I>
+where
+    K: Serialize + Clone + Send + Sync + 'static,
+    V: 'static,
+    I: Serialize + Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn add(&self, batch: &mut DBBatch, key: &K, value: &V) -> Result<(), TypedStoreError> {
+        if let Some(index_key) = (self.extract)(value) {
+            batch.insert_batch(&self.map, [((index_key, key.clone()), ())])?;
+        }
+        Ok(())
+    }
+
+    fn remove(&self, batch: &mut DBBatch, key: &K, value: &V) -> Result<(), TypedStoreError> {
+        if let Some(index_key) = (self.extract)(value) {
+            batch.delete_batch(&self.map, [(index_key, key.clone())])?;
+        }
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl<K, V> IndexedMap<K, V>
+where
+    K: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+    V: Serialize + DeserializeOwned + 'static,
+{
+    /// Wraps the primary map, without any indexes.
+    pub fn new(primary: DBMap<K, V>) -> Self {
+        Self {
+            primary,
+            indexes: vec![],
+        }
+    }
+
+    /// Adds an index named `name`, stored in `map`, with index keys extracted by `extract`.
+    ///
+    /// The index map must belong to the same database as the primary map and use the default key
+    /// codec. It must contain exactly the index entries of the primary entries, e.g., be empty for
+    /// an empty primary map, as existing entries are not indexed.
+    pub fn with_index<I>(
+        mut self,
+        name: &str,
+        map: DBMap<(I, K), ()>,
+        extract: impl Fn(&V) -> Option<I> + Send + Sync + 'static,
+    ) -> Result<Self, TypedStoreError>
+    where
+        I: Serialize + DeserializeOwned + Send + Sync + 'static,
+    {
+        if !Arc::ptr_eq(&map.rocksdb, &self.primary.rocksdb) {
+            return Err(TypedStoreError::CrossDBBatch);
+        }
+        if !map.codec.has_default_key_codec() {
+            return Err(TypedStoreError::IndexError(format!(
+                "the map of index {name} must use the default key codec"
+            )));
+        }
+        if self.indexes.iter().any(|index| index.name() == name) {
+            return Err(TypedStoreError::IndexError(format!(
+                "an index named {name} already exists"
+            )));
+        }
+        self.indexes.push(Box::new(Index {
+            name: name.to_owned(),
+            map,
+            extract: Box::new(extract),
+        }));
+        Ok(self)
+    }
+
+    /// Returns the primary map, e.g., for reads.
+    pub fn primary(&self) -> &DBMap<K, V> {
+        &self.primary
+    }
+
+    /// Returns the value for `key` in the primary map.
+    pub fn get(&self, key: &K) -> Result<Option<V>, TypedStoreError> {
+        self.primary.get(key)
+    }
+
+    /// Inserts the key-value pair and its index entries, replacing any previous value for `key`
+    /// along with its index entries.
+    pub fn insert(&self, key: &K, value: &V) -> Result<(), TypedStoreError> {
+        let key_buf = self.primary.codec.encode_key(key)?;
+        let _guard = self.primary.rocksdb.lock_key(&self.primary.cf, &key_buf);
+        let mut batch = self.primary.batch();
+        if let Some(previous) = self.primary.get(key)? {
+            for index in &self.indexes {
+                index.remove(&mut batch, key, &previous)?;
+            }
+        }
+        // Added after the removals, so that an unchanged index entry is kept.
+        for index in &self.indexes {
+            index.add(&mut batch, key, value)?;
+        }
+        batch.insert_batch(&self.primary, [(key, value)])?;
+        // Locking the index keys while holding the primary key's lock could deadlock.
+        batch.write_without_key_locks()
+    }
+
+    /// Removes `key` and its index entries, returning the removed value, if any.
+    pub fn remove(&self, key: &K) -> Result<Option<V>, TypedStoreError> {
+        let key_buf = self.primary.codec.encode_key(key)?;
+        let _guard = self.primary.rocksdb.lock_key(&self.primary.cf, &key_buf);
+        let Some(previous) = self.primary.get(key)? else {
+            return Ok(None);
+        };
+        let mut batch = self.primary.batch();
+        for index in &self.indexes {
+            index.remove(&mut batch, key, &previous)?;
+        }
+        batch.delete_batch(&self.primary, [key])?;
+        batch.write_without_key_locks()?;
+        Ok(Some(previous))
+    }
+
+    /// Returns the primary keys of the entries with the given key in the index named `name`, in
+    /// key order.
+    ///
+    /// Fails if there is no index named `name` with index keys of type `I`.
+    pub fn get_keys_by_index<I>(&self, name: &str, index_key: &I) -> Result<Vec<K>, TypedStoreError>
+    where
+        I: Serialize + DeserializeOwned + 'static,
+    {
+        let index = self
+            .indexes
+            .iter()
+            .find(|index| index.name() == name)
+            .and_then(|index| index.as_any().downcast_ref::<Index<K, V, I>>())
+            .ok_or_else(|| {
+                TypedStoreError::IndexError(format!("no index {name} with the given key type"))
+            })?;
+        index
+            .map
+            .seek_range_by_first(index_key)?
+            .map(|entry| entry.map(|((_, key), ())| key))
+            .collect()
+    }
+
+    /// Returns the values of the entries with the given key in the index named `name`, in the
+    /// order of their primary keys, see [`Self::get_keys_by_index`].
+    pub fn get_by_index<I>(&self, name: &str, index_key: &I) -> Result<Vec<V>, TypedStoreError>
+    where
+        I: Serialize + DeserializeOwned + 'static,
+    {
+        let keys = self.get_keys_by_index(name, index_key)?;
+        // Entries removed since reading the index are skipped.
+        Ok(self
+            .primary
+            .multi_get(keys)?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fmt, marker::PhantomData};
+
+use rocksdb::MergeOperands;
+use serde::{Serialize, de::DeserializeOwned};
+
+/// The signature of the RocksDB merge callbacks.
+type MergeFn = fn(&[u8], Option<&[u8]>, &MergeOperands) -> Option<Vec<u8>>;
+
+/// A value type that can be combined by [`MergeOperator::add`].
+pub trait AddMerge: Serialize + DeserializeOwned + 'static {
+    /// The value of a key that has only been merged into.
+    fn zero() -> Self;
+
+    /// Adds two values. Integer types wrap around on overflow.
+    fn merge_add(self, other: Self) -> Self;
+}
+
+macro_rules! impl_add_merge {
+    ($($int:ty),*) => {
+        $(
+            impl AddMerge for $int {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn merge_add(self, other: Self) -> Self {
+                    self.wrapping_add(other)
+                }
+            }
+        )*
+    };
+}
+
+impl_add_merge!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+/// A typed RocksDB merge operator for values of type `V`.
+///
+/// The operator must be installed on the column family options before the database is opened,
+/// e.g., via [`DBMap::open_with_merge`](super::DBMap::open_with_merge) or [`Self::apply`], and
+/// must stay installed on every subsequent open, as flushes and compactions invoke it to fold
+/// merge operands into values.
+pub struct MergeOperator<V> {
+    name: &'static str,
+    full_merge: MergeFn,
+    partial_merge: MergeFn,
+    _phantom: PhantomData<fn() -> V>,
+}
+
+impl<V> fmt::Debug for MergeOperator<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MergeOperator {{ name: {:?} }}", self.name)
+    }
+}
+
+impl<V> Clone for MergeOperator<V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for MergeOperator<V> {}
+
+impl<V> MergeOperator<V> {
+    /// Returns the name under which the operator is registered with RocksDB.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Installs the merge operator on the given options.
+    pub fn apply(&self, options: &mut rocksdb::Options) {
+        options.set_merge_operator(self.name, self.full_merge, self.partial_merge);
+    }
+}
+
+impl<V: AddMerge> MergeOperator<V> {
+    /// A merge operator that adds operands to the existing value, e.g., for counters.
+    ///
+    /// A key without a base value is treated as [`AddMerge::zero`].
+    pub fn add() -> Self {
+        Self {
+            name: "typed_store_add",
+            full_merge: add_merge::<V>,
+            partial_merge: add_merge::<V>,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> MergeOperator<Vec<T>> {
+    /// A merge operator that appends the elements of each operand to the existing list.
+    ///
+    /// Lists are concatenated on their serialized form, so merging never decodes the elements and
+    /// costs time linear in the total size of the operands. A key without a base value is treated
+    /// as an empty list.
+    pub fn append() -> Self {
+        Self {
+            name: "typed_store_append",
+            full_merge: append_full_merge,
+            partial_merge: append_partial_merge,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+fn add_merge<V: AddMerge>(
+    _key: &[u8],
+    existing: Option<&[u8]>,
+    operands: &MergeOperands,
+) -> Option<Vec<u8>> {
+    let mut sum = match existing {
+        Some(bytes) => decode_merge_value::<V>(bytes)?,
+        None => V::zero(),
+    };
+    for operand in operands.iter() {
+        sum = sum.merge_add(decode_merge_value::<V>(operand)?);
+    }
+    bcs::to_bytes(&sum)
+        .inspect_err(|error| tracing::error!(?error, "failed to encode merge result"))
+        .ok()
+}
+
+fn append_full_merge(
+    _key: &[u8],
+    existing: Option<&[u8]>,
+    operands: &MergeOperands,
+) -> Option<Vec<u8>> {
+    concat_sequences(existing.into_iter().chain(operands.iter()))
+}
+
+fn append_partial_merge(
+    _key: &[u8],
+    _existing: Option<&[u8]>,
+    operands: &MergeOperands,
+) -> Option<Vec<u8>> {
+    concat_sequences(operands.iter())
+}
+
+/// Concatenates BCS-encoded sequences, i.e., ULEB128 lengths followed by the encoded elements.
+fn concat_sequences<'a>(sequences: impl Iterator<Item = &'a [u8]>) -> Option<Vec<u8>> {
+    let mut total_len = 0u64;
+    let mut elements = Vec::new();
+    for sequence in sequences {
+        let Some((len, bytes)) = decode_uleb128(sequence) else {
+            tracing::error!("failed to decode the length of a merge operand");
+            return None;
+        };
+        total_len += len;
+        elements.extend_from_slice(bytes);
+    }
+    let mut merged = encode_uleb128(total_len);
+    merged.extend_from_slice(&elements);
+    Some(merged)
+}
+
+/// Splits a ULEB128-encoded integer off the front of `bytes`.
+fn decode_uleb128(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+fn encode_uleb128(mut value: u64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Decodes a value or operand; returning `None` makes RocksDB fail the merge as corrupted.
+fn decode_merge_value<V: DeserializeOwned>(bytes: &[u8]) -> Option<V> {
+    bcs::from_bytes(bytes)
+        .inspect_err(|error| tracing::error!(?error, "failed to decode merge operand"))
+        .ok()
+}
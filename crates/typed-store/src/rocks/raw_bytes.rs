@@ -0,0 +1,115 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fmt, ops::Deref};
+
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    de::{SeqAccess, Visitor},
+    ser::SerializeTuple,
+};
+
+/// Bytes that are stored as-is when used as the key or value type of a [`DBMap`].
+///
+/// [`DBMap`] normally length-prefixes byte vectors, both in the key and in the value encoding, so
+/// `Vec<u8>` keys are ordered by length first. `RawBytes` is encoded without a prefix instead:
+/// the stored bytes are exactly the wrapped bytes, and keys iterate in the lexicographic byte
+/// order of RocksDB's default comparator. This allows reading and writing column families shared
+/// with components that do not use the encodings of this crate.
+///
+/// Decoding consumes all remaining input, so `RawBytes` must only be used as the whole key or
+/// value type and not nested in other types. Exclusive lower and inclusive upper bounds of range
+/// iterators are derived by appending a zero byte to the bound, which matches the byte order for
+/// keys of any length.
+///
+/// [`DBMap`]: super::DBMap
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RawBytes(pub Vec<u8>);
+
+impl RawBytes {
+    /// Returns the wrapped bytes.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Deref for RawBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for RawBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for RawBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&[u8]> for RawBytes {
+    fn from(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl From<RawBytes> for Vec<u8> {
+    fn from(bytes: RawBytes) -> Self {
+        bytes.0
+    }
+}
+
+impl Serialize for RawBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Tuples are encoded without a length prefix by both the key and the value encoding.
+        let mut tuple = serializer.serialize_tuple(self.0.len())?;
+        for byte in &self.0 {
+            tuple.serialize_element(byte)?;
+        }
+        tuple.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for RawBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RawBytesVisitor {
+            is_human_readable: bool,
+        }
+
+        impl<'de> Visitor<'de> for RawBytesVisitor {
+            type Value = RawBytes;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a sequence of bytes")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<RawBytes, A::Error> {
+                let mut bytes = Vec::new();
+                loop {
+                    match seq.next_element() {
+                        Ok(Some(byte)) => bytes.push(byte),
+                        Ok(None) => break,
+                        // Without a length prefix, the bytes end where the input ends, which the
+                        // binary encodings report as an error on the next read. Reading a byte
+                        // from their input cannot fail otherwise.
+                        Err(_) if !self.is_human_readable => break,
+                        Err(error) => return Err(error),
+                    }
+                }
+                Ok(RawBytes(bytes))
+            }
+        }
+
+        let is_human_readable = deserializer.is_human_readable();
+        deserializer.deserialize_tuple(usize::MAX, RawBytesVisitor { is_human_readable })
+    }
+}
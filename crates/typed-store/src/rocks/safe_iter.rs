@@ -1,18 +1,17 @@
 // Copyright (c) Walrus Foundation
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{fmt, marker::PhantomData, sync::Arc};
+use std::{fmt, sync::Arc, time::Instant};
 
-use bincode::Options;
 use prometheus::{Histogram, HistogramTimer};
 use rocksdb::Direction;
 use serde::{Serialize, de::DeserializeOwned};
 
-use super::{RocksDBRawIter, be_fix_int_ser};
+use super::{Comparator, DbSnapshot, RocksDBRawIter, codec::MapCodec};
 use crate::{
     TypedStoreError,
     metrics::{DBMetrics, RocksDBPerfContext},
-    rocks::errors::typed_store_err_from_bincode_err,
+    rocks::errors::typed_store_err_from_rocks_err,
     traits::SeekableIterator,
 };
 
@@ -29,7 +28,7 @@ pub struct IterContext {
 pub struct SafeIter<'a, K, V> {
     cf_name: String,
     db_iter: RocksDBRawIter<'a>,
-    _phantom: PhantomData<(K, V)>,
+    codec: MapCodec<K, V>,
     direction: Direction,
     is_initialized: bool,
     iter_context: IterContext,
@@ -37,8 +36,22 @@ pub struct SafeIter<'a, K, V> {
     key_bytes_scanned_counter: usize,
     value_bytes_scanned_counter: usize,
     keys_returned_counter: usize,
+    /// The entry at the current position, if it was already read by a peek.
+    peeked: Option<Option<Result<(K, V), TypedStoreError>>>,
+    /// The snapshot the iterator reads from, if it is owned by the iterator. Declared after
+    /// `db_iter` so that it is released only after the iterator.
+    snapshot: Option<DbSnapshot<'a>>,
+    /// The time after which the iterator stops with an error, if any.
+    deadline: Option<Instant>,
+    /// Whether the deadline was exceeded, after which the iterator is exhausted.
+    deadline_exceeded: bool,
+    /// The order of the keys, if the column family does not use the default bytewise order.
+    comparator: Option<Comparator>,
 }
 
+/// The number of entries read between two checks of the deadline of a [`SafeIter`].
+const DEADLINE_CHECK_INTERVAL: usize = 64;
+
 impl<K: DeserializeOwned, V: DeserializeOwned> fmt::Debug for SafeIter<'_, K, V> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "SafeIter")
@@ -51,11 +64,12 @@ impl<'a, K: DeserializeOwned, V: DeserializeOwned> SafeIter<'a, K, V> {
         db_iter: RocksDBRawIter<'a>,
         iter_context: IterContext,
         db_metrics: Option<Arc<DBMetrics>>,
+        codec: MapCodec<K, V>,
     ) -> Self {
         Self {
             cf_name,
             db_iter,
-            _phantom: PhantomData,
+            codec,
             direction: Direction::Forward,
             is_initialized: false,
             iter_context,
@@ -63,14 +77,70 @@ impl<'a, K: DeserializeOwned, V: DeserializeOwned> SafeIter<'a, K, V> {
             key_bytes_scanned_counter: 0,
             value_bytes_scanned_counter: 0,
             keys_returned_counter: 0,
+            peeked: None,
+            snapshot: None,
+            deadline: None,
+            deadline_exceeded: false,
+            comparator: None,
         }
     }
-}
 
-impl<K: DeserializeOwned, V: DeserializeOwned> Iterator for SafeIter<'_, K, V> {
-    type Item = Result<(K, V), TypedStoreError>;
+    /// Makes the iterator yield [`TypedStoreError::DeadlineExceeded`] once `deadline` has passed,
+    /// after which it is exhausted.
+    ///
+    /// The deadline is checked before the first entry and then every
+    /// [`DEADLINE_CHECK_INTERVAL`] entries, so a few entries may still be returned after it
+    /// passed.
+    pub(super) fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Makes the iterator compare keys with the comparator of its column family, if any.
+    pub(super) fn with_comparator(mut self, comparator: Option<Comparator>) -> Self {
+        self.comparator = comparator;
+        self
+    }
+
+    /// Makes the iterator hold the snapshot its read options were set to until it is dropped.
+    pub(super) fn with_snapshot(mut self, snapshot: DbSnapshot<'a>) -> Self {
+        self.snapshot = Some(snapshot);
+        self
+    }
+
+    /// Returns the snapshot held by the iterator, see [`DBMap::snapshot_iter`].
+    ///
+    /// Point reads through the snapshot, e.g., with [`DBMap::get_with_snapshot`], observe the
+    /// same state of the database as the iterator.
+    ///
+    /// [`DBMap::snapshot_iter`]: super::DBMap::snapshot_iter
+    /// [`DBMap::get_with_snapshot`]: super::DBMap::get_with_snapshot
+    pub fn snapshot(&self) -> Option<&DbSnapshot<'a>> {
+        self.snapshot.as_ref()
+    }
+
+    /// Returns the next entry without advancing the iterator.
+    ///
+    /// A subsequent call to [`Iterator::next`] returns the same entry. If reading the next entry
+    /// fails, the error is returned here and again by [`Iterator::next`].
+    pub fn peek(&mut self) -> Result<Option<&(K, V)>, TypedStoreError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.read_current());
+        }
+        match &self.peeked {
+            Some(Some(Ok(entry))) => Ok(Some(entry)),
+            Some(Some(Err(error))) => Err(error.clone()),
+            Some(None) | None => Ok(None),
+        }
+    }
+
+    /// Returns the key of the next entry without advancing the iterator, see [`Self::peek`].
+    pub fn peek_key(&mut self) -> Result<Option<&K>, TypedStoreError> {
+        Ok(self.peek()?.map(|(key, _)| key))
+    }
+
+    /// Reads the entry at the current position without moving the iterator.
+    fn read_current(&mut self) -> Option<Result<(K, V), TypedStoreError>> {
         // Implicitly set iterator to the first entry in the column
         // family if it hasn't been initialized used for backward
         // compatibility
@@ -79,9 +149,6 @@ impl<K: DeserializeOwned, V: DeserializeOwned> Iterator for SafeIter<'_, K, V> {
             self.is_initialized = true;
         }
         if self.db_iter.valid() {
-            let config = bincode::DefaultOptions::new()
-                .with_big_endian()
-                .with_fixint_encoding();
             let raw_key = self
                 .db_iter
                 .key()
@@ -93,19 +160,44 @@ impl<K: DeserializeOwned, V: DeserializeOwned> Iterator for SafeIter<'_, K, V> {
             self.key_bytes_scanned_counter += raw_key.len();
             self.value_bytes_scanned_counter += raw_value.len();
             self.keys_returned_counter += 1;
-            let key = config.deserialize(raw_key).ok();
-            let value = bcs::from_bytes(raw_value).ok();
-            match self.direction {
-                Direction::Forward => self.db_iter.next(),
-                Direction::Reverse => self.db_iter.prev(),
-            }
+            let key = self.codec.decode_key(raw_key).ok();
+            let value = self.codec.decode_value(raw_value).ok();
             key.and_then(|k| value.map(|v| Ok((k, v))))
         } else {
             match self.db_iter.status() {
                 Ok(_) => None,
-                Err(err) => Some(Err(TypedStoreError::RocksDBError(format!("{err}")))),
+                Err(err) => Some(Err(typed_store_err_from_rocks_err(err))),
+            }
+        }
+    }
+}
+
+impl<K: DeserializeOwned, V: DeserializeOwned> Iterator for SafeIter<'_, K, V> {
+    type Item = Result<(K, V), TypedStoreError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.deadline_exceeded {
+            return None;
+        }
+        if let Some(deadline) = self.deadline
+            && self.keys_returned_counter % DEADLINE_CHECK_INTERVAL == 0
+            && Instant::now() >= deadline
+        {
+            self.deadline_exceeded = true;
+            self.peeked = None;
+            return Some(Err(TypedStoreError::DeadlineExceeded));
+        }
+        let item = match self.peeked.take() {
+            Some(item) => item,
+            None => self.read_current(),
+        };
+        if self.db_iter.valid() {
+            match self.direction {
+                Direction::Forward => self.db_iter.next(),
+                Direction::Reverse => self.db_iter.prev(),
             }
         }
+        item
     }
 }
 
@@ -135,26 +227,73 @@ impl<K, V> Drop for SafeIter<'_, K, V> {
     }
 }
 
+impl<'a, K, V> SafeIter<'a, K, V> {
+    /// Moves the iterator with `seek`, recording the latency if the iterator is sampled.
+    fn reposition(&mut self, seek: impl FnOnce(&mut RocksDBRawIter<'a>)) {
+        self.is_initialized = true;
+        self.peeked = None;
+        let start = Instant::now();
+        seek(&mut self.db_iter);
+        if self.iter_context._perf_ctx.is_some()
+            && let Some(db_metrics) = &self.db_metrics
+        {
+            db_metrics.observe_sampled_op_latency(&self.cf_name, "seek", start.elapsed());
+        }
+    }
+}
+
+impl<K: Serialize, V> SafeIter<'_, K, V> {
+    /// Fast-forwards the iterator to the first entry with a key greater than or equal to `key`,
+    /// with a single seek instead of reading the entries in between.
+    ///
+    /// Can be called between calls to [`Iterator::next`]. Unlike [`SeekableIterator::seek`], it
+    /// never moves the iterator backwards: if the next entry's key is already greater than or
+    /// equal to `key`, or the iterator is exhausted, it has no effect. For iterators in reverse
+    /// order, it skips to the first entry with a key less than or equal to `key`.
+    pub fn skip_to(&mut self, key: &K) -> Result<(), TypedStoreError> {
+        let key_buf = self.codec.encode_key(key)?;
+        if self.is_initialized {
+            let Some(current_key) = self.db_iter.key() else {
+                return Ok(());
+            };
+            let ordering = match &self.comparator {
+                Some(comparator) => comparator.compare(current_key, &key_buf),
+                None => current_key.cmp(key_buf.as_slice()),
+            };
+            let already_past = match self.direction {
+                Direction::Forward => ordering.is_ge(),
+                Direction::Reverse => ordering.is_le(),
+            };
+            if already_past {
+                return Ok(());
+            }
+        }
+        match self.direction {
+            Direction::Forward => self.reposition(|db_iter| db_iter.seek(key_buf)),
+            Direction::Reverse => self.reposition(|db_iter| db_iter.seek_for_prev(key_buf)),
+        }
+        Ok(())
+    }
+}
+
 impl<K: DeserializeOwned + Serialize, V> SeekableIterator<K> for SafeIter<'_, K, V> {
     fn seek_to_first(&mut self) {
-        self.is_initialized = true;
-        self.db_iter.seek_to_first();
+        self.reposition(|db_iter| db_iter.seek_to_first());
     }
 
     fn seek_to_last(&mut self) {
-        self.is_initialized = true;
-        self.db_iter.seek_to_last();
+        self.reposition(|db_iter| db_iter.seek_to_last());
     }
 
     fn seek(&mut self, key: &K) -> Result<(), TypedStoreError> {
-        self.is_initialized = true;
-        self.db_iter.seek(be_fix_int_ser(key)?);
+        let key_buf = self.codec.encode_key(key)?;
+        self.reposition(|db_iter| db_iter.seek(key_buf));
         Ok(())
     }
 
     fn seek_to_prev(&mut self, key: &K) -> Result<(), TypedStoreError> {
-        self.is_initialized = true;
-        self.db_iter.seek_for_prev(be_fix_int_ser(key)?);
+        let key_buf = self.codec.encode_key(key)?;
+        self.reposition(|db_iter| db_iter.seek_for_prev(key_buf));
         Ok(())
     }
 
@@ -166,15 +305,7 @@ impl<K: DeserializeOwned + Serialize, V> SeekableIterator<K> for SafeIter<'_, K,
         }
 
         let raw_key = self.db_iter.key();
-        raw_key
-            .map(|data| {
-                bincode::DefaultOptions::new()
-                    .with_big_endian()
-                    .with_fixint_encoding()
-                    .deserialize(data)
-                    .map_err(typed_store_err_from_bincode_err)
-            })
-            .transpose()
+        raw_key.map(|data| self.codec.decode_key(data)).transpose()
     }
 }
 /// An iterator with a reverted direction to the original. The `RevIter`
@@ -196,6 +327,16 @@ impl<'a, K: DeserializeOwned, V: DeserializeOwned> SafeRevIter<'a, K, V> {
         }
         Self { iter }
     }
+
+    /// Returns the next entry without advancing the iterator, see [`SafeIter::peek`].
+    pub fn peek(&mut self) -> Result<Option<&(K, V)>, TypedStoreError> {
+        self.iter.peek()
+    }
+
+    /// Returns the key of the next entry without advancing the iterator, see [`SafeIter::peek`].
+    pub fn peek_key(&mut self) -> Result<Option<&K>, TypedStoreError> {
+        self.iter.peek_key()
+    }
 }
 
 impl<K: DeserializeOwned, V: DeserializeOwned> Iterator for SafeRevIter<'_, K, V> {
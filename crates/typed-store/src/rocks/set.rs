@@ -0,0 +1,89 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{fmt, ops::RangeBounds, path::Path};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::{DBMap, MetricConf, ReadWriteOptions};
+use crate::{Map, TypedStoreError};
+
+/// A set of keys, stored in a column family as keys with zero-length values.
+///
+/// Inserts write the empty value directly instead of serializing a `()` value, and reads only
+/// check for the presence of keys. The set can be read as a [`DBMap<K, ()>`] through
+/// [`DBSet::inner`], as the default value codec encodes `()` as zero bytes; a custom value codec
+/// of the wrapped map is bypassed.
+pub struct DBSet<K> {
+    map: DBMap<K, ()>,
+}
+
+impl<K> fmt::Debug for DBSet<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DBSet {{ cf: {} }}", self.map.cf)
+    }
+}
+
+impl<K> DBSet<K>
+where
+    K: Serialize + DeserializeOwned,
+{
+    /// Wraps a map with unit values as a set.
+    pub fn new(map: DBMap<K, ()>) -> Self {
+        Self { map }
+    }
+
+    /// Opens a database with a single column family holding the set, see [`DBMap::open`].
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        metric_conf: MetricConf,
+        db_options: Option<rocksdb::Options>,
+        opt_cf: Option<&str>,
+        rw_options: &ReadWriteOptions,
+    ) -> Result<Self, TypedStoreError> {
+        DBMap::open(path, metric_conf, db_options, opt_cf, rw_options).map(Self::new)
+    }
+
+    /// Returns the wrapped map.
+    pub fn inner(&self) -> &DBMap<K, ()> {
+        &self.map
+    }
+
+    /// Adds the key to the set. Adding a key that is already present has no effect.
+    pub fn insert(&self, key: &K) -> Result<(), TypedStoreError> {
+        self.map.insert_raw(key, &[])
+    }
+
+    /// Returns whether the key is in the set.
+    pub fn contains(&self, key: &K) -> Result<bool, TypedStoreError> {
+        self.map.contains_key(key)
+    }
+
+    /// Removes the key from the set. Removing an absent key has no effect.
+    pub fn remove(&self, key: &K) -> Result<(), TypedStoreError> {
+        self.map.remove(key)
+    }
+
+    /// Returns whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns an iterator over the keys of the set, in key order.
+    pub fn iter(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<K, TypedStoreError>> + '_, TypedStoreError> {
+        self.range_iter(..)
+    }
+
+    /// Returns an iterator over the keys of the set in `range`, in key order.
+    pub fn range_iter(
+        &self,
+        range: impl RangeBounds<K>,
+    ) -> Result<impl Iterator<Item = Result<K, TypedStoreError>> + '_, TypedStoreError> {
+        Ok(self
+            .map
+            .safe_range_iter(range)?
+            .map(|entry| entry.map(|(key, ())| key)))
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use rocksdb::IngestExternalFileOptions;
+use serde::Serialize;
+
+use super::{DBMap, codec::MapCodec};
+use crate::{TypedStoreError, rocks::errors::typed_store_err_from_rocks_err};
+
+/// Options for ingesting external SST files with [`DBMap::ingest_external_sst`].
+#[derive(Clone, Debug, Default)]
+pub struct IngestOptions {
+    /// Whether to move the files into the database instead of copying them.
+    ///
+    /// Moving avoids copying the data, but the files are no longer available at their original
+    /// location after a successful ingestion.
+    pub move_files: bool,
+    /// Whether to fail the ingestion instead of flushing the memtable if it overlaps with the
+    /// ingested key range.
+    pub disallow_blocking_flush: bool,
+}
+
+impl IngestOptions {
+    pub(super) fn to_rocksdb_options(&self) -> IngestExternalFileOptions {
+        let mut opts = IngestExternalFileOptions::default();
+        opts.set_move_files(self.move_files);
+        opts.set_allow_blocking_flush(!self.disallow_blocking_flush);
+        opts
+    }
+}
+
+/// Writes typed key-value pairs of a [`DBMap`] to an SST file for bulk ingestion.
+///
+/// Keys must be added in strictly increasing order of their serialized form, which for the
+/// default big-endian key encoding of [`DBMap`] matches the numeric order of integer keys. Adding a
+/// key that is not greater than the previous one fails with [`TypedStoreError::SstKeyOutOfOrder`].
+/// The finished file can be ingested with [`DBMap::ingest_external_sst`].
+pub struct SstFileWriter<'a, K, V> {
+    writer: rocksdb::SstFileWriter<'a>,
+    path: PathBuf,
+    last_key: Option<Vec<u8>>,
+    codec: MapCodec<K, V>,
+}
+
+impl<K, V> fmt::Debug for SstFileWriter<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SstFileWriter {{ path: {:?} }}", self.path)
+    }
+}
+
+impl<'a, K, V> SstFileWriter<'a, K, V>
+where
+    K: Serialize,
+    V: Serialize,
+{
+    /// Creates the SST file at `path` for the given map, using the options of its database.
+    pub fn create(map: &'a DBMap<K, V>, path: impl AsRef<Path>) -> Result<Self, TypedStoreError> {
+        let path = path.as_ref().to_path_buf();
+        let writer = rocksdb::SstFileWriter::create(map.rocksdb.db_options());
+        writer.open(&path).map_err(typed_store_err_from_rocks_err)?;
+        Ok(Self {
+            writer,
+            path,
+            last_key: None,
+            codec: map.codec.clone(),
+        })
+    }
+
+    /// Adds the key-value pair to the file.
+    pub fn put(&mut self, key: &K, value: &V) -> Result<(), TypedStoreError> {
+        let key_buf = self.codec.encode_key(key)?;
+        if self
+            .last_key
+            .as_ref()
+            .is_some_and(|last_key| *last_key >= key_buf)
+        {
+            return Err(TypedStoreError::SstKeyOutOfOrder);
+        }
+        let value_buf = self.codec.encode_value(value)?;
+        self.writer
+            .put(&key_buf, value_buf)
+            .map_err(typed_store_err_from_rocks_err)?;
+        self.last_key = Some(key_buf);
+        Ok(())
+    }
+
+    /// Adds all key-value pairs to the file, in iteration order.
+    pub fn put_all(
+        &mut self,
+        key_vals: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<(), TypedStoreError> {
+        key_vals
+            .into_iter()
+            .try_for_each(|(key, value)| self.put(&key, &value))
+    }
+
+    /// Finalizes the file, returning its path.
+    ///
+    /// Fails if no key-value pair was added.
+    pub fn finish(mut self) -> Result<PathBuf, TypedStoreError> {
+        self.writer
+            .finish()
+            .map_err(typed_store_err_from_rocks_err)?;
+        Ok(self.path)
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{borrow::Borrow, fmt, sync::Arc};
+
+use serde::Serialize;
+
+use super::{DBBatch, DBMap};
+use crate::TypedStoreError;
+
+/// A write batch with a memory budget, which writes and clears itself whenever its size reaches
+/// the budget.
+///
+/// This bounds the memory of batches built from unbounded streams of writes. Each automatic write
+/// is atomic on its own, but the writes of the whole stream are not: if a write fails, or the
+/// batch is dropped without calling [`StreamingBatch::finish`], the operations written by earlier
+/// automatic writes remain in the database, and readers may observe a partially applied stream
+/// at any time.
+pub struct StreamingBatch {
+    batch: DBBatch,
+    budget_bytes: usize,
+    auto_writes: usize,
+}
+
+impl fmt::Debug for StreamingBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamingBatch")
+            .field("budget_bytes", &self.budget_bytes)
+            .field("size_in_bytes", &self.batch.size_in_bytes())
+            .field("auto_writes", &self.auto_writes)
+            .finish()
+    }
+}
+
+impl StreamingBatch {
+    /// Wraps the batch, writing it whenever [`DBBatch::size_in_bytes`] reaches `budget_bytes`.
+    pub fn new(batch: DBBatch, budget_bytes: usize) -> Self {
+        Self {
+            batch,
+            budget_bytes,
+            auto_writes: 0,
+        }
+    }
+
+    /// Adds the insertion of the key-value pairs into the given map, writing the batch whenever
+    /// it reaches the budget.
+    pub fn insert_batch<J, K, U, V>(
+        &mut self,
+        db: &DBMap<K, V>,
+        new_vals: impl IntoIterator<Item = (J, U)>,
+    ) -> Result<&mut Self, TypedStoreError>
+    where
+        J: Borrow<K>,
+        K: Serialize,
+        U: Borrow<V>,
+        V: Serialize,
+    {
+        self.check_db(db)?;
+        let cf = db.cf()?;
+        for (key, value) in new_vals {
+            let key_buf = db.codec.encode_key(key.borrow())?;
+            let value_buf = db.codec.encode_value(value.borrow())?;
+            self.batch.record_key(&db.cf, &key_buf);
+            self.batch.batch.put_cf(&cf, key_buf, value_buf);
+            self.write_if_full()?;
+        }
+        Ok(self)
+    }
+
+    /// Adds the removal of the keys from the given map, writing the batch whenever it reaches the
+    /// budget.
+    pub fn delete_batch<J, K, V>(
+        &mut self,
+        db: &DBMap<K, V>,
+        purged_vals: impl IntoIterator<Item = J>,
+    ) -> Result<&mut Self, TypedStoreError>
+    where
+        J: Borrow<K>,
+        K: Serialize,
+    {
+        self.check_db(db)?;
+        let cf = db.cf()?;
+        for key in purged_vals {
+            let key_buf = db.codec.encode_key(key.borrow())?;
+            self.batch.record_key(&db.cf, &key_buf);
+            self.batch.batch.delete_cf(&cf, key_buf);
+            self.write_if_full()?;
+        }
+        Ok(self)
+    }
+
+    /// Returns the number of times the batch was written because it reached the budget.
+    pub fn auto_writes(&self) -> usize {
+        self.auto_writes
+    }
+
+    /// Writes the remaining operations, returning the number of automatic writes before.
+    pub fn finish(mut self) -> Result<usize, TypedStoreError> {
+        self.batch.write_and_clear()?;
+        Ok(self.auto_writes)
+    }
+
+    fn write_if_full(&mut self) -> Result<(), TypedStoreError> {
+        if self.batch.size_in_bytes() >= self.budget_bytes {
+            self.batch.write_and_clear()?;
+            self.auto_writes += 1;
+        }
+        Ok(())
+    }
+
+    fn check_db<K, V>(&self, db: &DBMap<K, V>) -> Result<(), TypedStoreError> {
+        if Arc::ptr_eq(&db.rocksdb, &self.batch.rocksdb) {
+            Ok(())
+        } else {
+            Err(TypedStoreError::CrossDBBatch)
+        }
+    }
+}
@@ -1,12 +1,19 @@
 // Copyright (c) Walrus Foundation
 // SPDX-License-Identifier: Apache-2.0
 
+use futures::StreamExt;
 use rstest::rstest;
 
 use super::*;
 use crate::{
     retry_transaction,
-    rocks::safe_iter::{SafeIter, SafeRevIter},
+    rocks::{
+        codec::{BcsCodec, BigEndianCodec, Codec},
+        entry::Entry,
+        raw_bytes::RawBytes,
+        safe_iter::{SafeIter, SafeRevIter},
+        transaction::OptimisticTransaction,
+    },
     traits::SeekableIterator,
 };
 
@@ -143,6 +150,197 @@ async fn test_wrong_reopen() {
     assert!(db.is_err());
 }
 
+#[tokio::test]
+async fn test_read_after_write_verify() {
+    assert!(!MetricConf::default().read_after_write_verify);
+    let rocks = open_cf(
+        temp_dir(),
+        None,
+        MetricConf::default().with_read_after_write_verify(),
+        &["foo", "bar"],
+    )
+    .expect("Failed to open storage");
+    assert_eq!(rocks.verifies_writes(), cfg!(debug_assertions));
+    let foo =
+        DBMap::<u32, String>::reopen(&rocks, Some("foo"), &ReadWriteOptions::default(), false)
+            .expect("Failed to reopen");
+    let bar = DBMap::<u32, u64>::reopen(&rocks, Some("bar"), &ReadWriteOptions::default(), false)
+        .expect("Failed to reopen");
+
+    for i in 0..10 {
+        foo.insert(&i, &i.to_string()).expect("Failed to insert");
+        bar.insert(&i, &u64::from(i)).expect("Failed to insert");
+    }
+    foo.insert(&0, &"replaced".to_string())
+        .expect("Failed to overwrite");
+    foo.insert_raw(&10, &bcs::to_bytes("raw").unwrap())
+        .expect("Failed to insert raw");
+    foo.remove(&1).expect("Failed to remove");
+    foo.remove(&100).expect("Failed to remove a missing key");
+
+    assert_eq!(foo.get(&0).unwrap(), Some("replaced".to_string()));
+    assert_eq!(foo.get(&1).unwrap(), None);
+    assert_eq!(bar.get(&1).unwrap(), Some(1));
+}
+
+#[tokio::test]
+async fn test_reopen_with_options() {
+    let rocks = open_cf_with_compression(
+        temp_dir(),
+        None,
+        MetricConf::default(),
+        &["compressed"],
+        &[("compressed", Compression::Zstd { level: 3 })],
+    )
+    .expect("Failed to open rocksdb");
+    let reopen = |cf_opts: &[(&str, &str)]| {
+        DBMap::<u32, String>::reopen_with_options(
+            &rocks,
+            Some("compressed"),
+            &ReadWriteOptions::default(),
+            cf_opts,
+            false,
+        )
+    };
+
+    let db = reopen(&[
+        ("compression", "kZSTD"),
+        ("bottommost_compression", "kZSTD"),
+    ])
+    .expect("Failed to reopen with matching options");
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+    assert_eq!(db.get(&1).unwrap(), Some("1".to_string()));
+
+    // Conflicting options are rejected rather than applied.
+    assert!(matches!(
+        reopen(&[("compression", "kNoCompression")]),
+        Err(TypedStoreError::RocksDBError(_))
+    ));
+    assert!(reopen(&[("compression", "kZSTD"), ("num_levels", "3")]).is_err());
+    assert!(reopen(&[("no_such_option", "1")]).is_err());
+    let options = read_cf_options(rocks.path(), "compressed").unwrap();
+    assert_eq!(options["compression"], "kZSTD");
+    assert!(matches!(
+        DBMap::<u32, String>::reopen_with_options(
+            &rocks,
+            Some("missing"),
+            &ReadWriteOptions::default(),
+            &[],
+            false,
+        ),
+        Err(TypedStoreError::UnregisteredColumn(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_list_column_families() {
+    let path = temp_dir();
+    assert!(RocksDB::list_column_families(&path).is_err());
+
+    let rocks = open_rocksdb(&path, &["foo", "bar", "baz"]);
+    assert!(rocks.cf_exists("foo"));
+    assert!(rocks.cf_exists(rocksdb::DEFAULT_COLUMN_FAMILY_NAME));
+    assert!(!rocks.cf_exists("quux"));
+    rocks
+        .create_cf("quux", &default_db_options().options)
+        .expect("Failed to create column family");
+    assert!(rocks.cf_exists("quux"));
+
+    let mut names = RocksDB::list_column_families(&path).expect("Failed to list column families");
+    names.sort();
+    assert_eq!(names, vec!["bar", "baz", "default", "foo", "quux"]);
+    assert_eq!(rocks.cf_names(), names);
+    rocks.drop_cf("baz").expect("Failed to drop column family");
+    assert_eq!(rocks.cf_names(), vec!["bar", "default", "foo", "quux"]);
+    rocks.flush_all().expect("Failed to flush");
+    rocks.verify_all().expect("Failed to verify");
+
+    // Reopening exactly the listed column families gives access to all of them.
+    let checkpoint = temp_dir().join("checkpoint");
+    rocks
+        .checkpoint(&checkpoint)
+        .expect("Failed to create checkpoint");
+    let names = RocksDB::list_column_families(&checkpoint).expect("Failed to list column families");
+    let cfs: Vec<_> = names.iter().map(String::as_str).collect();
+    let reopened = open_rocksdb(&checkpoint, &cfs);
+    let mut sorted_names = names.clone();
+    sorted_names.sort();
+    assert_eq!(reopened.cf_names(), sorted_names);
+    for name in cfs {
+        assert!(reopened.cf_exists(name));
+        DBMap::<u8, u8>::reopen(&reopened, Some(name), &ReadWriteOptions::default(), false)
+            .expect("Failed to reopen a listed column family");
+    }
+}
+
+#[tokio::test]
+async fn test_create_and_drop_cf() {
+    let path = temp_dir();
+    let rocks = open_rocksdb(&path, &["base"]);
+    let reopen = |rocks: &Arc<RocksDB>| {
+        DBMap::<u32, String>::reopen(rocks, Some("epoch_1"), &ReadWriteOptions::default(), false)
+    };
+    assert_eq!(
+        reopen(&rocks).unwrap_err(),
+        TypedStoreError::UnregisteredColumn("epoch_1".to_string())
+    );
+
+    rocks
+        .create_cf("epoch_1", &default_db_options().options)
+        .expect("Failed to create column family");
+    let db = reopen(&rocks).expect("Failed to reopen the created column family");
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+    assert_eq!(db.get(&1).unwrap(), Some("1".to_string()));
+
+    // The new column family is part of the database from now on.
+    let checkpoint = temp_dir().join("checkpoint");
+    rocks
+        .checkpoint(&checkpoint)
+        .expect("Failed to create checkpoint");
+    let reopened = open_rocksdb(&checkpoint, &["base"]);
+    assert_eq!(
+        reopen(&reopened).unwrap().get(&1).unwrap(),
+        Some("1".to_string())
+    );
+
+    rocks
+        .drop_cf("epoch_1")
+        .expect("Failed to drop column family");
+    assert_eq!(
+        reopen(&rocks).unwrap_err(),
+        TypedStoreError::UnregisteredColumn("epoch_1".to_string())
+    );
+    assert_eq!(
+        db.get(&1),
+        Err(TypedStoreError::UnregisteredColumn("epoch_1".to_string()))
+    );
+
+    assert!(matches!(
+        rocks.drop_cf("epoch_1"),
+        Err(TypedStoreError::RocksDBError(_))
+    ));
+
+    // Recreating the column family starts out empty.
+    rocks
+        .create_cf("epoch_1", &default_db_options().options)
+        .expect("Failed to create column family");
+    let db = reopen(&rocks).expect("Failed to reopen the created column family");
+    assert_eq!(db.get(&1).unwrap(), None);
+
+    // Column families cannot be created in read-only databases.
+    let readonly = open_cf_opts_readonly(
+        &checkpoint,
+        None,
+        MetricConf::default(),
+        &[("base", default_db_options().options)],
+    )
+    .expect("Failed to open read-only");
+    assert_eq!(
+        readonly.create_cf("epoch_2", &default_db_options().options),
+        Err(TypedStoreError::ReadOnlyDatabase)
+    );
+}
+
 #[tokio::test]
 async fn test_contains_key() {
     let db = open_map(temp_dir(), None);
@@ -188,6 +386,39 @@ async fn test_multi_contain() {
     assert!(result[2]);
 }
 
+#[tokio::test]
+async fn test_multi_contains_prefix() {
+    let db = open_map::<_, (u32, u64), String>(temp_dir(), None);
+    db.multi_insert(
+        [(1, 0), (1, 7), (3, u64::MAX), (0x100, 1), (u32::MAX, 2)]
+            .map(|key| (key, format!("{key:?}"))),
+    )
+    .expect("Failed to multi-insert");
+
+    let result = db
+        .multi_contains_prefix([0u32, 1, 2, 3, 0xff, 0x100, u32::MAX - 1, u32::MAX])
+        .expect("Failed to check prefix existence");
+    assert_eq!(
+        result,
+        vec![false, true, false, true, false, true, false, true]
+    );
+
+    // Full keys and keys longer than any stored key are prefixes too.
+    let result = db
+        .multi_contains_prefix([(1u32, 7u64), (1, 8), (u32::MAX, 2)])
+        .expect("Failed to check prefix existence");
+    assert_eq!(result, vec![true, false, true]);
+    let result = db
+        .multi_contains_prefix([(u32::MAX, 2u64, 0u8)])
+        .expect("Failed to check prefix existence");
+    assert_eq!(result, vec![false]);
+    assert!(
+        db.multi_contains_prefix(Vec::<u32>::new())
+            .unwrap()
+            .is_empty()
+    );
+}
+
 #[tokio::test]
 async fn test_get() {
     let db = open_map(temp_dir(), None);
@@ -218,6 +449,104 @@ async fn test_multi_get() {
     assert_eq!(result[2], None);
 }
 
+#[tokio::test]
+async fn test_multi_get_duplicate_keys() {
+    let db = open_map(temp_dir(), None);
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+    db.insert(&2, &"2".to_string()).expect("Failed to insert");
+
+    let keys = [2, 1, 3, 2, 2, 3, 1];
+    let expected: Vec<_> = keys.iter().map(|k| db.get(k).unwrap()).collect();
+    assert_eq!(db.multi_get(keys).expect("Failed to multi get"), expected);
+    assert_eq!(
+        db.par_multi_get(keys).expect("Failed to multi get"),
+        expected
+    );
+}
+
+#[tokio::test]
+async fn test_get_raw_and_insert_raw() {
+    let db = open_map::<_, u32, String>(temp_dir(), None);
+    let value_bytes = bcs::to_bytes(&"raw".to_string()).expect("Failed to serialize");
+    db.insert_raw(&1, &value_bytes)
+        .expect("Failed to insert raw");
+    assert_eq!(db.get(&1).expect("Failed to get"), Some("raw".to_string()));
+    assert_eq!(
+        db.get_raw(&1).expect("Failed to get raw"),
+        Some(value_bytes)
+    );
+
+    db.insert(&2, &"typed".to_string())
+        .expect("Failed to insert");
+    let typed_bytes = db.get_raw(&2).expect("Failed to get raw").unwrap();
+    db.insert_raw(&3, &typed_bytes)
+        .expect("Failed to insert raw");
+    assert_eq!(
+        db.get(&3).expect("Failed to get"),
+        Some("typed".to_string())
+    );
+    assert_eq!(db.get_raw(&4).expect("Failed to get raw"), None);
+
+    // Bytes that are not a valid encoding are stored, but cannot be read back as values.
+    db.insert_raw(&5, &[0xff]).expect("Failed to insert raw");
+    assert!(db.get(&5).is_err());
+}
+
+#[tokio::test]
+async fn test_multi_get_raw() {
+    let db = open_map(temp_dir(), None);
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+    db.insert(&2, &"two".to_string()).expect("Failed to insert");
+
+    let keys = [2, 3, 1, 2];
+    let raw = db.multi_get_raw(keys).expect("Failed to multi get");
+    assert_eq!(raw[1], None);
+    let decoded: Vec<Option<String>> = raw
+        .iter()
+        .map(|value| value.as_ref().map(|bytes| bcs::from_bytes(bytes).unwrap()))
+        .collect();
+    assert_eq!(decoded, db.multi_get(keys).expect("Failed to multi get"));
+}
+
+#[tokio::test]
+async fn test_par_multi_get_matches_get_loop() {
+    let db = open_map::<_, u32, Vec<u8>>(temp_dir(), None);
+    let mut batch = db.batch();
+    batch
+        .insert_batch(
+            &db,
+            (0..20_000u32)
+                .step_by(2)
+                .map(|i| (i, i.to_be_bytes().repeat(16))),
+        )
+        .expect("Failed to batch insert");
+    batch.write().expect("Failed to execute batch");
+
+    // Shuffled keys with duplicates and gaps, spanning many parallel chunks.
+    let keys: Vec<u32> = (0..50_000u32).map(|i| (i * 7_919) % 21_000).collect();
+
+    let start = std::time::Instant::now();
+    let expected: Vec<_> = keys.iter().map(|k| db.get(k).unwrap()).collect();
+    let loop_elapsed = start.elapsed();
+    let start = std::time::Instant::now();
+    let batched = db.multi_get(&keys).expect("Failed to multi get");
+    let batched_elapsed = start.elapsed();
+    let start = std::time::Instant::now();
+    let parallel = db.par_multi_get(&keys).expect("Failed to multi get");
+    let parallel_elapsed = start.elapsed();
+    tracing::info!(
+        ?loop_elapsed,
+        ?batched_elapsed,
+        ?parallel_elapsed,
+        "looked up {} keys",
+        keys.len()
+    );
+
+    assert_eq!(batched, expected);
+    assert_eq!(parallel, expected);
+    assert!(parallel.iter().any(Option::is_none));
+}
+
 #[tokio::test]
 async fn test_skip() {
     let db = open_map(temp_dir(), None);
@@ -271,6 +600,35 @@ async fn test_reverse_iter_with_bounds() {
     assert_eq!(iter.next().unwrap(), Ok((999, "999".to_string())));
 }
 
+#[tokio::test]
+async fn test_reverse_iter_with_both_bounds() {
+    let db = open_map(temp_dir(), None);
+    for i in (10..=100).step_by(10) {
+        db.insert(&i, &i.to_string()).expect("Failed to insert");
+    }
+    let reverse_keys = |lower_bound, upper_bound| -> Vec<i32> {
+        get_reverse_iter(&db, lower_bound, upper_bound)
+            .map(|item| item.expect("Failed to iterate").0)
+            .collect()
+    };
+
+    // Bounds that are existing keys are both included.
+    assert_eq!(reverse_keys(Some(30), Some(60)), vec![60, 50, 40, 30]);
+    // Bounds between keys stop at the closest keys within the bounds.
+    assert_eq!(reverse_keys(Some(25), Some(65)), vec![60, 50, 40, 30]);
+    assert_eq!(reverse_keys(Some(31), Some(59)), vec![50, 40]);
+    // A lower bound alone stops the descent at the bound.
+    assert_eq!(reverse_keys(Some(80), None), vec![100, 90, 80]);
+    assert_eq!(reverse_keys(Some(85), None), vec![100, 90]);
+    // Bounds outside of the keys.
+    assert_eq!(reverse_keys(Some(0), Some(25)), vec![20, 10]);
+    assert_eq!(reverse_keys(Some(101), None), Vec::<i32>::new());
+    assert_eq!(reverse_keys(None, Some(5)), Vec::<i32>::new());
+    // Equal bounds and bounds without keys in between.
+    assert_eq!(reverse_keys(Some(50), Some(50)), vec![50]);
+    assert_eq!(reverse_keys(Some(51), Some(59)), Vec::<i32>::new());
+}
+
 #[tokio::test]
 async fn test_remove() {
     let db = open_map(temp_dir(), None);
@@ -436,6 +794,100 @@ async fn test_delete_batch() {
     }
 }
 
+#[tokio::test]
+async fn test_batch_len_and_size() {
+    let db = open_map::<_, i32, String>(temp_dir(), None);
+
+    let mut batch = db.batch();
+    assert!(batch.is_empty());
+    assert_eq!(batch.len(), 0);
+    let empty_size = batch.size_in_bytes();
+
+    batch
+        .insert_batch(&db, (0..10).map(|i| (i, i.to_string())))
+        .expect("Failed to batch insert");
+    assert!(!batch.is_empty());
+    assert_eq!(batch.len(), 10);
+    let size_after_inserts = batch.size_in_bytes();
+    assert!(size_after_inserts > empty_size);
+
+    // Deleting keys adds operations rather than cancelling the pending inserts.
+    batch
+        .delete_batch(&db, 0..3)
+        .expect("Failed to batch delete");
+    assert_eq!(batch.len(), 13);
+    assert!(batch.size_in_bytes() > size_after_inserts);
+
+    // Clearing resets the batch to its initial state.
+    batch.clear();
+    assert!(batch.is_empty());
+    assert_eq!(batch.size_in_bytes(), empty_size);
+
+    batch
+        .insert_batch(&db, [(42, "42".to_string())])
+        .expect("Failed to batch insert");
+    assert_eq!(batch.len(), 1);
+    batch.write().expect("Failed to execute batch");
+    assert_eq!(
+        get_iter(&db).collect::<Vec<_>>(),
+        vec![(42, "42".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn test_batch_save_points() {
+    let db = open_map::<_, i32, String>(temp_dir(), None);
+    db.insert(&100, &"100".to_string())
+        .expect("Failed to insert");
+
+    let mut batch = db.batch();
+    assert_eq!(
+        batch.rollback_to_save_point(),
+        Err(TypedStoreError::NoSavePoint)
+    );
+
+    batch
+        .insert_batch(&db, (0..3).map(|i| (i, i.to_string())))
+        .expect("Failed to batch insert");
+    batch.set_save_point();
+    batch
+        .insert_batch(&db, (3..6).map(|i| (i, i.to_string())))
+        .expect("Failed to batch insert");
+    batch.set_save_point();
+    batch
+        .delete_batch(&db, [100])
+        .expect("Failed to batch delete");
+
+    // Rolling back to the inner save point keeps the second round of inserts.
+    batch.rollback_to_save_point().expect("Failed to roll back");
+    assert_eq!(batch.len(), 6);
+    // Rolling back to the outer save point drops them as well.
+    batch.rollback_to_save_point().expect("Failed to roll back");
+    assert_eq!(batch.len(), 3);
+    assert_eq!(
+        batch.rollback_to_save_point(),
+        Err(TypedStoreError::NoSavePoint)
+    );
+
+    // Popping a save point keeps the operations added since.
+    batch.set_save_point();
+    batch
+        .insert_batch(&db, [(7, "7".to_string())])
+        .expect("Failed to batch insert");
+    batch.pop_save_point().expect("Failed to pop save point");
+    assert_eq!(batch.len(), 4);
+    assert_eq!(batch.pop_save_point(), Err(TypedStoreError::NoSavePoint));
+
+    // The batch stays usable after a rollback.
+    batch
+        .insert_batch(&db, [(10, "10".to_string())])
+        .expect("Failed to batch insert");
+    batch.write().expect("Failed to execute batch");
+
+    let keys: Vec<_> = get_iter(&db).map(|(k, _)| k).collect();
+    assert_eq!(keys, vec![0, 1, 2, 7, 10, 100]);
+}
+
 #[tokio::test]
 async fn test_delete_range() {
     let db: DBMap<i32, String> = DBMap::open(
@@ -476,83 +928,395 @@ async fn test_delete_range() {
 }
 
 #[tokio::test]
-async fn test_clear() {
-    let db = DBMap::<i32, String>::open(
+async fn test_delete_range_batch_chaining() {
+    let db: DBMap<i32, String> = DBMap::open(
         temp_dir(),
         MetricConf::default(),
         None,
-        Some("table"),
-        &ReadWriteOptions::default(),
+        None,
+        &ReadWriteOptions::default().set_ignore_range_deletions(false),
     )
     .expect("Failed to open storage");
-    // Test clear of empty map
-    let _ = db.unsafe_clear();
+    db.multi_insert((0..100).map(|i| (i, i.to_string())))
+        .expect("Failed to insert");
 
-    let keys_vals = (0..101).map(|i| (i, i.to_string()));
-    let mut insert_batch = db.batch();
-    insert_batch
-        .insert_batch(&db, keys_vals)
-        .expect("Failed to batch insert");
+    let mut batch = db.batch();
+    batch
+        .delete_range_batch(&db, &10, &20)
+        .and_then(|batch| batch.delete_range_batch(&db, &50, &60))
+        .and_then(|batch| batch.insert_batch(&db, [(15, "fifteen".to_owned())]))
+        .expect("Failed to build batch");
+    batch.write().expect("Failed to execute batch");
 
-    insert_batch.write().expect("Failed to execute batch");
+    let expected: Vec<_> = (0..10).chain([15]).chain(20..50).chain(60..100).collect();
+    let keys: Vec<_> = get_iter(&db).map(|(k, _)| k).collect();
+    assert_eq!(keys, expected);
+    assert_eq!(db.get(&15).unwrap(), Some("fifteen".to_owned()));
+}
 
-    // Check we have multiple entries
-    assert!(db.safe_iter().expect("failed to get iterator").count() > 1);
-    let _ = db.unsafe_clear();
-    assert_eq!(db.safe_iter().expect("failed to get iterator").count(), 0);
-    // Clear again to ensure safety when clearing empty map
-    let _ = db.unsafe_clear();
-    assert_eq!(db.safe_iter().expect("failed to get iterator").count(), 0);
-    // Clear with one item
-    let _ = db.insert(&1, &"e".to_string());
-    assert_eq!(db.safe_iter().expect("failed to get iterator").count(), 1);
-    let _ = db.unsafe_clear();
-    assert_eq!(db.safe_iter().expect("failed to get iterator").count(), 0);
+#[tokio::test]
+async fn test_batch_from_ops() {
+    let db = open_map::<_, i32, String>(temp_dir(), None);
+    DBBatch::from_inserts(&db, (0..10).map(|i| (i, i.to_string())))
+        .expect("Failed to build batch")
+        .write()
+        .expect("Failed to execute batch");
+
+    // Deletes the odd keys and re-inserts 3 after deleting it.
+    let ops = (0..10)
+        .filter(|i| i % 2 == 1)
+        .map(BatchOp::Delete)
+        .chain([BatchOp::Insert(3, "three".to_owned())])
+        .chain((10..12).map(|i| BatchOp::Insert(i, i.to_string())));
+    let batch = DBBatch::from_ops(&db, ops).expect("Failed to build batch");
+    assert_eq!(batch.len(), 8);
+    batch.write().expect("Failed to execute batch");
+
+    let entries: Vec<_> = get_iter(&db).collect();
+    assert_eq!(
+        entries,
+        vec![
+            (0, "0".to_owned()),
+            (2, "2".to_owned()),
+            (3, "three".to_owned()),
+            (4, "4".to_owned()),
+            (6, "6".to_owned()),
+            (8, "8".to_owned()),
+            (10, "10".to_owned()),
+            (11, "11".to_owned()),
+        ]
+    );
+
+    let other = open_map::<_, i32, String>(temp_dir(), None);
+    assert!(matches!(
+        db.batch().apply_ops(&other, [BatchOp::Delete(0)]),
+        Err(TypedStoreError::CrossDBBatch)
+    ));
 }
 
 #[tokio::test]
-async fn test_iter_with_bounds() {
-    let db = open_map(temp_dir(), None);
+async fn test_clear_range() {
+    let db: DBMap<i32, String> = DBMap::open(
+        temp_dir(),
+        MetricConf::default(),
+        None,
+        None,
+        &ReadWriteOptions::default().set_ignore_range_deletions(false),
+    )
+    .expect("Failed to open storage");
 
-    // Add [1, 50) and (50, 100) in the db
-    for i in 1..100 {
-        if i != 50 {
-            db.insert(&i, &i.to_string()).unwrap();
-        }
+    // Note that the last element is (100, "100".to_owned()) here
+    db.multi_insert((0..101).map(|i| (i, i.to_string())))
+        .expect("Failed to multi-insert");
+
+    db.clear_range(&50, &100).expect("Failed to clear range");
+
+    for k in 0..50 {
+        assert!(db.contains_key(&k).expect("Failed to query legal key"));
+    }
+    for k in 50..100 {
+        assert!(!db.contains_key(&k).expect("Failed to query legal key"));
     }
 
-    // Tests basic bounded scan.
-    let db_iter = get_iter_with_bounds(&db, Some(20), Some(90));
+    // range operator is not inclusive of to
+    assert!(db.contains_key(&100).expect("Failed to query legal key"));
+
+    // Range deletes are not supported by the optimistic engine.
+    let optimistic: DBMap<i32, String> = open_optimistic_map(temp_dir(), "cf");
+    assert!(optimistic.clear_range(&0, &10).is_err());
+}
+
+#[tokio::test]
+async fn test_delete_prefix() {
+    let db: DBMap<(u32, u64), String> = DBMap::open(
+        temp_dir(),
+        MetricConf::default(),
+        None,
+        None,
+        &ReadWriteOptions::default().set_ignore_range_deletions(false),
+    )
+    .expect("Failed to open storage");
+    let partitions = [0, 1, 2, 0xff, 0x100, u32::MAX - 1, u32::MAX];
+    db.multi_insert(
+        partitions
+            .iter()
+            .flat_map(|&p| [0, 1, u64::MAX].map(|i| ((p, i), format!("{p}-{i}")))),
+    )
+    .expect("Failed to multi-insert");
+    let remaining_partitions = |db: &DBMap<(u32, u64), String>| {
+        let mut partitions: Vec<u32> = db
+            .safe_iter()
+            .map(|entry| entry.expect("Failed to read entry").0.0)
+            .collect();
+        partitions.dedup();
+        partitions
+    };
+
+    db.delete_prefix(&1u32).expect("Failed to delete prefix");
     assert_eq!(
-        (20..50)
-            .chain(51..90)
-            .map(|i| (i, i.to_string()))
-            .collect::<Vec<_>>(),
-        db_iter.collect::<Vec<_>>()
+        remaining_partitions(&db),
+        vec![0, 2, 0xff, 0x100, u32::MAX - 1, u32::MAX]
     );
+    assert_eq!(db.get(&(2, 0)).unwrap(), Some("2-0".to_owned()));
 
-    // Don't specify upper bound.
-    let db_iter = get_iter_with_bounds(&db, Some(20), None);
+    // The upper bound of a prefix ending in 0xff carries into the preceding bytes.
+    db.delete_prefix(&0xffu32).expect("Failed to delete prefix");
     assert_eq!(
-        (20..50)
-            .chain(51..100)
-            .map(|i| (i, i.to_string()))
-            .collect::<Vec<_>>(),
-        db_iter.collect::<Vec<_>>()
+        remaining_partitions(&db),
+        vec![0, 2, 0x100, u32::MAX - 1, u32::MAX]
     );
 
-    // Don't specify lower bound.
-    let db_iter = get_iter_with_bounds(&db, None, Some(90));
+    // A prefix of only 0xff bytes has no upper bound of the same length.
+    db.delete_prefix(&u32::MAX)
+        .expect("Failed to delete prefix");
+    assert_eq!(remaining_partitions(&db), vec![0, 2, 0x100, u32::MAX - 1]);
+    db.delete_prefix(&u32::MAX)
+        .expect("Failed to delete missing prefix");
+
+    // A full key is a prefix of itself.
+    db.delete_prefix(&(0u32, 1u64))
+        .expect("Failed to delete prefix");
+    assert_eq!(db.get(&(0, 0)).unwrap(), Some("0-0".to_owned()));
+    assert_eq!(db.get(&(0, 1)).unwrap(), None);
     assert_eq!(
-        (1..50)
-            .chain(51..90)
-            .map(|i| (i, i.to_string()))
-            .collect::<Vec<_>>(),
-        db_iter.collect::<Vec<_>>()
+        db.get(&(0, u64::MAX)).unwrap(),
+        Some("0-18446744073709551615".to_owned())
     );
 
-    // Don't specify any bounds.
-    let db_iter = get_iter_with_bounds(&db, None, None);
+    let optimistic: DBMap<(u32, u64), String> = open_optimistic_map(temp_dir(), "cf");
+    assert!(optimistic.delete_prefix(&0u32).is_err());
+}
+
+#[tokio::test]
+async fn test_compact_range_after_delete_range() {
+    fn sst_size(db: &DBMap<u32, Vec<u8>>) -> i64 {
+        db.flush().expect("Failed to flush");
+        DBMap::<u32, Vec<u8>>::get_i64_property(
+            &db.rocksdb,
+            &db.cf().unwrap(),
+            properties::TOTAL_SST_FILES_SIZE,
+        )
+        .expect("Failed to get property")
+    }
+
+    let db = open_map::<_, u32, Vec<u8>>(temp_dir(), None);
+    let mut batch = db.batch();
+    batch
+        .insert_batch(&db, (0..10_000u32).map(|i| (i, vec![i as u8; 100])))
+        .expect("Failed to batch insert");
+    batch.write().expect("Failed to execute batch");
+    let size_before = sst_size(&db);
+
+    let cap = db
+        .rocksdb
+        .as_range_delete()
+        .expect("range delete supported");
+    let mut batch = db.batch();
+    batch
+        .schedule_delete_range(&db, &0, &9_000, &cap)
+        .expect("Failed to delete range");
+    batch.write().expect("Failed to execute batch");
+    // The tombstone alone does not free any space.
+    assert!(sst_size(&db) >= size_before);
+
+    db.compact_range(Some(&0), Some(&9_000))
+        .expect("Failed to compact");
+    let size_after = sst_size(&db);
+    assert!(
+        size_after < size_before / 2,
+        "{size_after} should be well below {size_before}"
+    );
+
+    db.compact_all().expect("Failed to compact");
+    assert_eq!(db.safe_iter().unwrap().count(), 1_000);
+    assert_eq!(db.get(&9_000).unwrap(), Some(vec![9_000u32 as u8; 100]));
+}
+
+#[tokio::test]
+async fn test_wait_for_compactions() {
+    let db = open_map::<_, u32, Vec<u8>>(temp_dir(), None);
+    let files_at_level = |level: usize| {
+        DBMap::<u32, Vec<u8>>::get_i64_property(
+            &db.rocksdb,
+            &db.cf().unwrap(),
+            &num_files_at_level(level),
+        )
+        .expect("Failed to get property")
+    };
+
+    // Create several overlapping level 0 files.
+    for round in 0..4u32 {
+        db.multi_insert((0..1_000u32).map(|i| (i, vec![round as u8; 100])))
+            .expect("Failed to insert");
+        db.flush().expect("Failed to flush");
+    }
+    assert!(files_at_level(0) > 0);
+
+    db.compact_all().expect("Failed to compact");
+    db.rocksdb
+        .wait_for_compactions()
+        .expect("Failed to wait for compactions");
+
+    // All data was moved out of level 0 and the file layout no longer changes.
+    assert_eq!(files_at_level(0), 0);
+    let layout: Vec<_> = (0..7).map(files_at_level).collect();
+    assert!(layout.iter().sum::<i64>() > 0);
+    db.rocksdb
+        .wait_for_compactions()
+        .expect("Failed to wait for compactions");
+    assert_eq!((0..7).map(files_at_level).collect::<Vec<_>>(), layout);
+    assert_eq!(db.get(&0).unwrap(), Some(vec![3; 100]));
+}
+
+#[tokio::test]
+async fn test_clear() {
+    let db = DBMap::<i32, String>::open(
+        temp_dir(),
+        MetricConf::default(),
+        None,
+        Some("table"),
+        &ReadWriteOptions::default(),
+    )
+    .expect("Failed to open storage");
+    // Test clear of empty map
+    let _ = db.unsafe_clear();
+
+    let keys_vals = (0..101).map(|i| (i, i.to_string()));
+    let mut insert_batch = db.batch();
+    insert_batch
+        .insert_batch(&db, keys_vals)
+        .expect("Failed to batch insert");
+
+    insert_batch.write().expect("Failed to execute batch");
+
+    // Check we have multiple entries
+    assert!(db.safe_iter().expect("failed to get iterator").count() > 1);
+    let _ = db.unsafe_clear();
+    assert_eq!(db.safe_iter().expect("failed to get iterator").count(), 0);
+    // Clear again to ensure safety when clearing empty map
+    let _ = db.unsafe_clear();
+    assert_eq!(db.safe_iter().expect("failed to get iterator").count(), 0);
+    // Clear with one item
+    let _ = db.insert(&1, &"e".to_string());
+    assert_eq!(db.safe_iter().expect("failed to get iterator").count(), 1);
+    let _ = db.unsafe_clear();
+    assert_eq!(db.safe_iter().expect("failed to get iterator").count(), 0);
+}
+
+#[tokio::test]
+async fn test_clear_via_range_delete() {
+    let rocks = open_rocksdb(temp_dir(), &["table"]);
+    let rw_options = ReadWriteOptions::default().set_ignore_range_deletions(false);
+    let db = DBMap::<i32, String>::reopen(&rocks, Some("table"), &rw_options, false)
+        .expect("Failed to reopen");
+    let other = DBMap::<i32, String>::reopen(&rocks, Some("table"), &rw_options, false)
+        .expect("Failed to reopen");
+
+    // Clearing an empty map has no effect.
+    db.clear_via_range_delete().expect("Failed to clear");
+    db.multi_insert((-50..=50).map(|i| (i, i.to_string())))
+        .expect("Failed to multi-insert");
+    assert_eq!(db.safe_iter().unwrap().count(), 101);
+
+    db.clear_via_range_delete().expect("Failed to clear");
+    assert!(db.is_empty());
+    assert_eq!(db.get(&50).unwrap(), None);
+    assert_eq!(other.safe_iter().unwrap().count(), 0);
+
+    // Both handles remain usable.
+    db.insert(&1, &"one".to_string()).expect("Failed to insert");
+    other
+        .insert(&2, &"two".to_string())
+        .expect("Failed to insert");
+    let entries: Vec<_> = db.safe_iter().unwrap().map(Result::unwrap).collect();
+    assert_eq!(
+        entries,
+        vec![(1, "one".to_string()), (2, "two".to_string())]
+    );
+    db.clear_via_range_delete().expect("Failed to clear");
+    assert!(other.is_empty());
+
+    let optimistic: DBMap<i32, String> = open_optimistic_map(temp_dir(), "cf");
+    assert!(optimistic.clear_via_range_delete().is_err());
+}
+
+#[tokio::test]
+async fn test_delete_files_in_range() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), Some("table"));
+    let value = "x".repeat(1024);
+
+    // Write ten chunks of keys, moving each to its own SST file outside of level 0, as files in
+    // level 0 are never deleted.
+    for chunk in 0..10u32 {
+        let (start, end) = (chunk * 1_000, chunk * 1_000 + 999);
+        db.multi_insert((start..=end).map(|i| (i, value.clone())))
+            .expect("Failed to multi-insert");
+        db.flush().expect("Failed to flush");
+        db.compact_range_to_bottom(&start, &end)
+            .expect("Failed to compact");
+    }
+    let size_before = db.total_sst_files_size().expect("Failed to read sst size");
+
+    // Only the six files fully within the range are deleted.
+    db.delete_files_in_range(&1_500, &7_999)
+        .expect("Failed to delete files");
+    let keys: Vec<_> = db
+        .safe_iter()
+        .expect("Failed to create iterator")
+        .map(|entry| entry.expect("Failed to read entry").0)
+        .collect();
+    assert_eq!(keys, (0..2_000).chain(8_000..10_000).collect::<Vec<_>>());
+    assert_eq!(db.get(&5_000).unwrap(), None);
+    let size_after = db.total_sst_files_size().expect("Failed to read sst size");
+    assert!(
+        size_after < size_before / 2,
+        "{size_after} bytes after deleting files, {size_before} before"
+    );
+}
+
+#[tokio::test]
+async fn test_iter_with_bounds() {
+    let db = open_map(temp_dir(), None);
+
+    // Add [1, 50) and (50, 100) in the db
+    for i in 1..100 {
+        if i != 50 {
+            db.insert(&i, &i.to_string()).unwrap();
+        }
+    }
+
+    // Tests basic bounded scan.
+    let db_iter = get_iter_with_bounds(&db, Some(20), Some(90));
+    assert_eq!(
+        (20..50)
+            .chain(51..90)
+            .map(|i| (i, i.to_string()))
+            .collect::<Vec<_>>(),
+        db_iter.collect::<Vec<_>>()
+    );
+
+    // Don't specify upper bound.
+    let db_iter = get_iter_with_bounds(&db, Some(20), None);
+    assert_eq!(
+        (20..50)
+            .chain(51..100)
+            .map(|i| (i, i.to_string()))
+            .collect::<Vec<_>>(),
+        db_iter.collect::<Vec<_>>()
+    );
+
+    // Don't specify lower bound.
+    let db_iter = get_iter_with_bounds(&db, None, Some(90));
+    assert_eq!(
+        (1..50)
+            .chain(51..90)
+            .map(|i| (i, i.to_string()))
+            .collect::<Vec<_>>(),
+        db_iter.collect::<Vec<_>>()
+    );
+
+    // Don't specify any bounds.
+    let db_iter = get_iter_with_bounds(&db, None, None);
     assert_eq!(
         (1..50)
             .chain(51..100)
@@ -575,6 +1339,45 @@ async fn test_iter_with_bounds() {
     );
 }
 
+#[tokio::test]
+async fn test_iter_with_bounds_inclusive() {
+    let db = open_map(temp_dir(), None);
+
+    // Add [1, 50) and (50, 100) in the db
+    for i in 1..100 {
+        if i != 50 {
+            db.insert(&i, &i.to_string()).unwrap();
+        }
+    }
+
+    // The upper key is yielded when present.
+    let db_iter = TestIteratorWrapper::SafeIter(
+        db.safe_iter_with_bounds_inclusive(Some(20), Some(40))
+            .expect("failed to get iterator"),
+    );
+    assert_eq!(
+        (20..=40).map(|i| (i, i.to_string())).collect::<Vec<_>>(),
+        db_iter.collect::<Vec<_>>()
+    );
+
+    // The upper key is omitted when absent.
+    let db_iter = TestIteratorWrapper::SafeIter(
+        db.safe_iter_with_bounds_inclusive(Some(45), Some(50))
+            .expect("failed to get iterator"),
+    );
+    assert_eq!(
+        (45..50).map(|i| (i, i.to_string())).collect::<Vec<_>>(),
+        db_iter.collect::<Vec<_>>()
+    );
+
+    // The last key in the map is included.
+    let db_iter = TestIteratorWrapper::SafeIter(
+        db.safe_iter_with_bounds_inclusive(None, Some(99))
+            .expect("failed to get iterator"),
+    );
+    assert_eq!(db_iter.count(), 98);
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_range_iter() {
@@ -616,6 +1419,69 @@ async fn test_range_iter() {
     );
 }
 
+#[tokio::test]
+async fn test_range_iter_rev() {
+    let db = open_map(temp_dir(), None);
+
+    // Add [1, 50) and (50, 100) in the db
+    for i in 1..100 {
+        if i != 50 {
+            db.insert(&i, &i.to_string()).unwrap();
+        }
+    }
+    let range_iter_rev = |range| -> Vec<(i32, String)> {
+        db.safe_range_iter_rev(range)
+            .expect("Failed to get iterator")
+            .collect::<Result<_, _>>()
+            .expect("Failed to iterate")
+    };
+
+    // Tests basic range iterating with inclusive end.
+    assert_eq!(
+        (10..21)
+            .rev()
+            .map(|i| (i, i.to_string()))
+            .collect::<Vec<_>>(),
+        range_iter_rev((Bound::Included(10), Bound::Included(20)))
+    );
+
+    // Tests range with min start and exclusive end.
+    assert_eq!(
+        (1..20)
+            .rev()
+            .map(|i| (i, i.to_string()))
+            .collect::<Vec<_>>(),
+        range_iter_rev((Bound::Unbounded, Bound::Excluded(20)))
+    );
+
+    // Tests range with max end.
+    assert_eq!(
+        (60..100)
+            .rev()
+            .map(|i| (i, i.to_string()))
+            .collect::<Vec<_>>(),
+        range_iter_rev((Bound::Included(60), Bound::Unbounded))
+    );
+
+    // Skip to last key in the bound, which is absent (bound is [45, 50])
+    assert_eq!(
+        (45..50)
+            .rev()
+            .map(|i| (i, i.to_string()))
+            .collect::<Vec<_>>(),
+        range_iter_rev((Bound::Included(45), Bound::Included(50)))
+    );
+
+    // Tests range with exclusive start.
+    assert_eq!(
+        (91..100)
+            .rev()
+            .map(|i| (i, i.to_string()))
+            .collect::<Vec<_>>(),
+        range_iter_rev((Bound::Excluded(90), Bound::Unbounded))
+    );
+}
+
 #[tokio::test]
 async fn test_is_empty() {
     let db = DBMap::<i32, String>::open(
@@ -650,6 +1516,169 @@ async fn test_is_empty() {
     assert!(db.is_empty());
 }
 
+static NUM_COUNTED_VALUES_DESERIALIZED: std::sync::atomic::AtomicUsize =
+    std::sync::atomic::AtomicUsize::new(0);
+
+/// A value that counts how often it is deserialized.
+#[derive(Serialize)]
+struct CountedValue(u64);
+
+impl<'de> Deserialize<'de> for CountedValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        NUM_COUNTED_VALUES_DESERIALIZED.fetch_add(1, Ordering::Relaxed);
+        u64::deserialize(deserializer).map(Self)
+    }
+}
+
+#[tokio::test]
+async fn test_is_empty_does_not_deserialize_values() {
+    let db = open_map::<_, u64, CountedValue>(temp_dir(), None);
+    db.multi_insert((0..1_000u64).map(|i| (i, CountedValue(i))))
+        .expect("Failed to insert");
+    db.flush().expect("Failed to flush");
+
+    let start = std::time::Instant::now();
+    for _ in 0..1_000 {
+        assert!(!db.is_empty());
+    }
+    tracing::info!("1000 calls to is_empty took {:?}", start.elapsed());
+    assert_eq!(NUM_COUNTED_VALUES_DESERIALIZED.load(Ordering::Relaxed), 0);
+
+    assert!(db.get(&1).unwrap().is_some());
+    assert_eq!(NUM_COUNTED_VALUES_DESERIALIZED.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_first_and_last() {
+    let db = open_map::<_, i32, String>(temp_dir(), Some("table"));
+    assert_eq!(db.first().unwrap(), None);
+    assert_eq!(db.last().unwrap(), None);
+
+    db.multi_insert([5, -3, 42, 7].map(|i| (i, i.to_string())))
+        .expect("Failed to insert");
+    assert_eq!(db.first().unwrap(), Some((-3, "-3".to_string())));
+    assert_eq!(db.last().unwrap(), Some((42, "42".to_string())));
+    let mut rev_iter = db.reversed_safe_iter_with_bounds(None, None).unwrap();
+    assert_eq!(db.last().unwrap(), Some(rev_iter.next().unwrap().unwrap()));
+
+    db.multi_remove([-3, 42]).expect("Failed to remove");
+    assert_eq!(db.first().unwrap(), Some((5, "5".to_string())));
+    assert_eq!(db.last().unwrap(), Some((7, "7".to_string())));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn test_pop_first_and_last_concurrent() {
+    const NUM_ENTRIES: u32 = 1_000;
+    let db: Arc<DBMap<u32, u32>> = Arc::new(open_map(temp_dir(), None));
+    assert_eq!(db.pop_first().unwrap(), None);
+    assert_eq!(db.pop_last().unwrap(), None);
+    db.multi_insert((0..NUM_ENTRIES).map(|i| (i, i)))
+        .expect("Failed to insert");
+    assert_eq!(db.pop_first().unwrap(), Some((0, 0)));
+    assert_eq!(
+        db.pop_last().unwrap(),
+        Some((NUM_ENTRIES - 1, NUM_ENTRIES - 1))
+    );
+
+    let tasks: Vec<_> = (0..8)
+        .map(|task| {
+            let db = db.clone();
+            tokio::spawn(async move {
+                let mut popped = Vec::new();
+                loop {
+                    let entry = if task % 2 == 0 {
+                        db.pop_first()
+                    } else {
+                        db.pop_last()
+                    };
+                    match entry.expect("Failed to pop") {
+                        Some((key, value)) => {
+                            assert_eq!(key, value);
+                            popped.push(key);
+                        }
+                        None => break popped,
+                    }
+                }
+            })
+        })
+        .collect();
+    let mut popped = Vec::new();
+    for task in tasks {
+        popped.extend(task.await.expect("task should not panic"));
+    }
+    popped.sort_unstable();
+    assert_eq!(popped, (1..NUM_ENTRIES - 1).collect::<Vec<_>>());
+    assert!(db.is_empty());
+}
+
+#[tokio::test]
+async fn test_count() {
+    let db = open_map::<_, u64, String>(temp_dir(), None);
+    assert_eq!(db.count().unwrap(), 0);
+
+    db.multi_insert((0..1_000u64).map(|i| (i, i.to_string())))
+        .expect("Failed to insert");
+    db.flush().expect("Failed to flush");
+    db.multi_insert((1_000..1_500u64).map(|i| (i, i.to_string())))
+        .expect("Failed to insert");
+    db.multi_remove(0..100u64).expect("Failed to remove");
+
+    assert_eq!(db.count().unwrap(), 1_400);
+    assert_eq!(db.count().unwrap(), db.safe_iter().unwrap().count());
+}
+
+#[tokio::test]
+async fn test_count_below_and_above() {
+    let db = open_map::<_, u32, String>(temp_dir(), None);
+    assert_eq!(db.count_below(&10).unwrap(), 0);
+    assert_eq!(db.count_above(&10).unwrap(), 0);
+
+    // The even keys 0, 2, ..., 198.
+    db.multi_insert((0..100u32).map(|i| (2 * i, i.to_string())))
+        .expect("Failed to insert");
+
+    // Present keys are excluded from both counts.
+    assert_eq!(db.count_below(&50).unwrap(), 25);
+    assert_eq!(db.count_above(&50).unwrap(), 74);
+    // Absent keys split the entries.
+    assert_eq!(db.count_below(&51).unwrap(), 26);
+    assert_eq!(db.count_above(&51).unwrap(), 74);
+    assert_eq!(db.count_below(&0).unwrap(), 0);
+    assert_eq!(db.count_above(&0).unwrap(), 99);
+    assert_eq!(db.count_below(&198).unwrap(), 99);
+    assert_eq!(db.count_above(&198).unwrap(), 0);
+    assert_eq!(db.count_below(&u32::MAX).unwrap(), 100);
+    assert_eq!(db.count_above(&u32::MAX).unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_scan_diagnostics() {
+    let db = open_map::<_, u32, String>(temp_dir(), Some("table"));
+    db.multi_insert((0..10_000).map(|i| (i, i.to_string())))
+        .expect("Failed to insert");
+    db.flush().expect("Failed to flush");
+    db.multi_remove(0..9_000).expect("Failed to remove");
+
+    let stats = db.scan_diagnostics(..).expect("Failed to scan");
+    assert_eq!(stats.keys_seen, 1_000);
+    assert!(stats.deletions_skipped >= 9_000);
+    assert!(stats.internal_keys_skipped >= stats.deletions_skipped);
+
+    // The scan restores the perf stats level of an enclosing measurement.
+    let previous_level = set_perf_level(PerfStatsLevel::EnableTime);
+    let stats = db.scan_diagnostics(9_500..).expect("Failed to scan");
+    assert_eq!(stats.keys_seen, 500);
+    assert!(matches!(
+        set_perf_level(previous_level),
+        PerfStatsLevel::EnableTime
+    ));
+
+    db.compact_range(None, None).expect("Failed to compact");
+    let stats = db.scan_diagnostics(..).expect("Failed to scan");
+    assert_eq!(stats.keys_seen, 1_000);
+    assert_eq!(stats.deletions_skipped, 0);
+}
+
 #[tokio::test]
 async fn test_multi_insert() {
     // Init a DB
@@ -704,20 +1733,157 @@ async fn test_checkpoint() {
 }
 
 #[tokio::test]
-async fn test_multi_remove() {
-    // Init a DB
-    let db: DBMap<i32, String> = open_map(temp_dir(), Some("table"));
+async fn test_checkpoint_existing_destination() {
+    let path_prefix = temp_dir();
+    let db: DBMap<i32, String> = open_map(path_prefix.join("db"), Some("table"));
+    db.insert(&1, &"first".to_string()).unwrap();
 
-    // Create kv pairs
-    let keys_vals = (0..101).map(|i| (i, i.to_string()));
+    // Overwriting is a no-op for a fresh destination.
+    let checkpoint_path = path_prefix.join("checkpoint");
+    db.checkpoint_db_with_options(&checkpoint_path, true)
+        .expect("Failed to create db checkpoint");
 
-    db.multi_insert(keys_vals.clone())
-        .expect("Failed to multi-insert");
+    // A stale checkpoint is only replaced when overwriting.
+    db.insert(&2, &"second".to_string()).unwrap();
+    assert_eq!(
+        db.checkpoint_db(&checkpoint_path),
+        Err(TypedStoreError::CheckpointExists(
+            checkpoint_path.display().to_string()
+        ))
+    );
+    db.checkpoint_db_with_options(&checkpoint_path, true)
+        .expect("Failed to overwrite db checkpoint");
+    let checkpointed_db: DBMap<i32, String> = open_map(&checkpoint_path, Some("table"));
+    assert_eq!(checkpointed_db.get(&2).unwrap(), Some("second".to_string()));
 
-    // Check insertion
-    for (k, v) in keys_vals.clone() {
-        let val = db.get(&k).expect("Failed to get inserted key");
-        assert_eq!(Some(v), val);
+    // A checkpoint that was opened as a database is not removed, even once it is closed.
+    drop(checkpointed_db);
+    assert!(matches!(
+        db.checkpoint_db_with_options(&checkpoint_path, true),
+        Err(TypedStoreError::InvalidCheckpointDestination(_))
+    ));
+    assert!(checkpoint_path.join("CURRENT").exists());
+
+    // Directories that are not checkpoints are never removed.
+    let other_path = path_prefix.join("other");
+    std::fs::create_dir(&other_path).unwrap();
+    std::fs::write(other_path.join("data"), b"data").unwrap();
+    assert!(matches!(
+        db.checkpoint_db_with_options(&other_path, true),
+        Err(TypedStoreError::InvalidCheckpointDestination(_))
+    ));
+    assert!(other_path.join("data").exists());
+    assert!(matches!(
+        db.checkpoint_db_with_options(&path_prefix.join("db"), true),
+        Err(TypedStoreError::InvalidCheckpointDestination(_))
+    ));
+    assert_eq!(db.get(&1).unwrap(), Some("first".to_string()));
+}
+
+#[tokio::test]
+async fn test_multi_insert_and_remove_chunked() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), Some("table"));
+
+    // A chunk size that does not divide the number of pairs leaves a partial last chunk.
+    db.multi_insert_chunked((0..100_000).map(|i| (i, i.to_string())), 7_777)
+        .expect("Failed to multi-insert");
+    let entries: Vec<_> = db
+        .safe_iter()
+        .expect("Failed to get iterator")
+        .collect::<Result<_, _>>()
+        .expect("Failed to iterate");
+    assert_eq!(
+        entries,
+        (0..100_000).map(|i| (i, i.to_string())).collect::<Vec<_>>()
+    );
+
+    db.multi_remove_chunked(
+        (0..100_000).filter(|i| i % 2 == 0),
+        DEFAULT_WRITE_CHUNK_SIZE,
+    )
+    .expect("Failed to multi-remove");
+    assert_eq!(db.safe_iter().unwrap().count(), 50_000);
+    assert!(db.safe_iter().unwrap().all(|item| item.unwrap().0 % 2 == 1));
+
+    // Empty inputs and a zero chunk size are fine.
+    db.multi_insert_chunked(std::iter::empty::<(u32, String)>(), 10)
+        .unwrap();
+    db.multi_insert_chunked([(1, "one".to_string())], 0)
+        .unwrap();
+    assert_eq!(db.get(&1).unwrap(), Some("one".to_string()));
+}
+
+#[tokio::test]
+async fn test_multi_remove_reporting() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), Some("table"));
+    db.multi_insert((0..10).map(|i| (i * 2, i.to_string())))
+        .expect("Failed to multi-insert");
+
+    let existed = db
+        .multi_remove_reporting([4, 5, 0, 19, 18, 4])
+        .expect("Failed to multi-remove");
+    assert_eq!(existed, vec![true, false, true, false, true, true]);
+    for key in [0, 4, 5, 18, 19] {
+        assert_eq!(db.get(&key).unwrap(), None);
+    }
+    assert_eq!(db.safe_iter().unwrap().count(), 7);
+
+    // Removing again reports the keys as absent.
+    assert_eq!(
+        db.multi_remove_reporting([0, 2]).unwrap(),
+        vec![false, true]
+    );
+    assert!(
+        db.multi_remove_reporting(Vec::<u32>::new())
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_multi_insert_if_absent() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), Some("table"));
+    db.multi_insert((0..5).map(|i| (i * 2, "old".to_string())))
+        .expect("Failed to multi-insert");
+
+    let inserted = db
+        .multi_insert_if_absent([
+            (1, "new".to_string()),
+            (2, "new".to_string()),
+            (5, "first".to_string()),
+            (8, "new".to_string()),
+            (5, "second".to_string()),
+        ])
+        .expect("Failed to multi-insert");
+    assert_eq!(inserted, vec![true, false, true, false, false]);
+    assert_eq!(db.get(&1).unwrap(), Some("new".to_string()));
+    assert_eq!(db.get(&2).unwrap(), Some("old".to_string()));
+    assert_eq!(db.get(&5).unwrap(), Some("first".to_string()));
+    assert_eq!(db.get(&8).unwrap(), Some("old".to_string()));
+    assert_eq!(db.safe_iter().unwrap().count(), 7);
+
+    assert!(
+        db.multi_insert_if_absent(Vec::<(u32, String)>::new())
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_multi_remove() {
+    // Init a DB
+    let db: DBMap<i32, String> = open_map(temp_dir(), Some("table"));
+
+    // Create kv pairs
+    let keys_vals = (0..101).map(|i| (i, i.to_string()));
+
+    db.multi_insert(keys_vals.clone())
+        .expect("Failed to multi-insert");
+
+    // Check insertion
+    for (k, v) in keys_vals.clone() {
+        let val = db.get(&k).expect("Failed to get inserted key");
+        assert_eq!(Some(v), val);
     }
 
     // Remove 50 items
@@ -735,6 +1901,71 @@ async fn test_multi_remove() {
     }
 }
 
+#[tokio::test]
+async fn test_copy_to() {
+    let rocks = open_rocksdb(temp_dir(), &["source", "dest"]);
+    let source =
+        DBMap::<u32, String>::reopen(&rocks, Some("source"), &ReadWriteOptions::default(), false)
+            .expect("Failed to reopen");
+    source
+        .multi_insert((0..1_005).map(|i| (i, i.to_string())))
+        .expect("Failed to multi-insert");
+    let entries = |db: &DBMap<u32, String>| -> Vec<(u32, String)> {
+        db.safe_iter().unwrap().map(Result::unwrap).collect()
+    };
+
+    // Into another column family of the same database.
+    let dest =
+        DBMap::<u32, String>::reopen(&rocks, Some("dest"), &ReadWriteOptions::default(), false)
+            .expect("Failed to reopen");
+    assert_eq!(source.copy_to(&dest, 100).expect("Failed to copy"), 1_005);
+    assert_eq!(entries(&dest), entries(&source));
+
+    // Into a fresh database.
+    let other: DBMap<u32, String> = open_map(temp_dir(), Some("copy"));
+    other
+        .insert(&0, &"stale".to_string())
+        .expect("Failed to insert");
+    assert_eq!(source.copy_to(&other, 0).expect("Failed to copy"), 1_005);
+    assert_eq!(entries(&other), entries(&source));
+
+    let empty: DBMap<u32, String> = open_map(temp_dir(), None);
+    assert_eq!(empty.copy_to(&other, 100).expect("Failed to copy"), 0);
+}
+
+#[tokio::test]
+async fn test_remove_range_where() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), Some("table"));
+    db.multi_insert((0..30_000).map(|i| (i, i.to_string())))
+        .expect("Failed to multi-insert");
+
+    // More matches than fit into a single batch.
+    let removed = db
+        .remove_range_where(1_000..26_000, |key, value| {
+            assert_eq!(*value, key.to_string());
+            key % 2 == 1
+        })
+        .expect("Failed to remove range");
+    assert_eq!(removed, 12_500);
+    for key in [999, 1_000, 1_001, 1_002, 25_999, 26_001] {
+        let expected = !(1_000..26_000).contains(&key) || key % 2 == 0;
+        assert_eq!(db.contains_key(&key).unwrap(), expected, "key {key}");
+    }
+    assert_eq!(db.safe_iter().unwrap().count(), 30_000 - 12_500);
+
+    assert_eq!(
+        db.remove_range_where(..100, |_, value| value.ends_with('7'))
+            .expect("Failed to remove range"),
+        10
+    );
+    assert_eq!(
+        db.remove_range_where(.., |_, _| false)
+            .expect("Failed to remove range"),
+        0
+    );
+    assert_eq!(db.safe_iter().unwrap().count(), 30_000 - 12_510);
+}
+
 fn open_map<P: AsRef<Path>, K, V>(path: P, opt_cf: Option<&str>) -> DBMap<K, V> {
     DBMap::<K, V>::open(
         path,
@@ -782,6 +2013,179 @@ async fn test_sampling_time() {
     assert!(sampling_interval.sample());
 }
 
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn test_sampling_adaptive() {
+    async fn samples_in_one_sec(sampling_interval: &SamplingInterval, ops: usize) -> usize {
+        let samples = (0..ops).filter(|_| sampling_interval.sample()).count();
+        tokio::time::advance(Duration::from_secs(1)).await;
+        samples
+    }
+
+    let sampling_interval = SamplingInterval::new_adaptive(10);
+    // Every operation is sampled until the load was measured once.
+    assert_eq!(samples_in_one_sec(&sampling_interval, 1_000).await, 1_000);
+    assert_eq!(sampling_interval.effective_num_ops(), 99);
+    assert_eq!(samples_in_one_sec(&sampling_interval, 1_000).await, 10);
+
+    // A burst is undersampled for one period before the rate catches up.
+    assert_eq!(samples_in_one_sec(&sampling_interval, 10_000).await, 100);
+    assert_eq!(samples_in_one_sec(&sampling_interval, 10_000).await, 10);
+    assert_eq!(sampling_interval.effective_num_ops(), 999);
+
+    // After the load drops, the rate recovers as well.
+    assert_eq!(samples_in_one_sec(&sampling_interval, 100).await, 1);
+    assert_eq!(samples_in_one_sec(&sampling_interval, 100).await, 10);
+    // Below the target rate, every operation is sampled.
+    assert_eq!(samples_in_one_sec(&sampling_interval, 5).await, 1);
+    assert_eq!(samples_in_one_sec(&sampling_interval, 5).await, 5);
+    assert_eq!(sampling_interval.effective_num_ops(), 0);
+}
+
+#[test]
+fn test_sampling_adaptive_without_runtime() {
+    let sampling_interval = SamplingInterval::new_adaptive(10);
+    let copy = sampling_interval.new_from_self();
+    assert!(sampling_interval.sample());
+    assert!(copy.sample());
+    assert_eq!(copy.effective_num_ops(), 0);
+}
+
+#[tokio::test]
+async fn test_sampled_op_latency_histograms() {
+    const CF: &str = "sampled_op_latency";
+    fn sample_count(op: &str) -> u64 {
+        DBMetrics::get()
+            .registry()
+            .gather()
+            .iter()
+            .filter(|family| family.get_name() == "rocksdb_sampled_op_latency_seconds")
+            .flat_map(|family| family.get_metric())
+            .filter(|metric| {
+                let labels = metric.get_label();
+                labels
+                    .iter()
+                    .any(|l| l.get_name() == "cf_name" && l.get_value() == CF)
+                    && labels
+                        .iter()
+                        .any(|l| l.get_name() == "op" && l.get_value() == op)
+            })
+            .map(|metric| metric.get_histogram().get_sample_count())
+            .sum()
+    }
+
+    let every_op = || SamplingInterval::new(Duration::ZERO, 0);
+    let metric_conf = MetricConf {
+        read_sample_interval: every_op(),
+        write_sample_interval: every_op(),
+        iter_sample_interval: every_op(),
+        ..MetricConf::new("sampled_op_latency")
+    };
+    let rocks = open_cf_opts(
+        temp_dir(),
+        None,
+        metric_conf,
+        &[(CF, default_db_options().options)],
+    )
+    .expect("Failed to open database");
+    let db: DBMap<u32, String> =
+        DBMap::reopen(&rocks, Some(CF), &ReadWriteOptions::default(), true)
+            .expect("Failed to open storage");
+
+    let ops = ["get", "multi_get", "insert", "seek"];
+    let before = ops.map(sample_count);
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+    db.insert(&2, &"2".to_string()).expect("Failed to insert");
+    db.get(&1).expect("Failed to get");
+    db.multi_get([1u32, 2]).expect("Failed to multi-get");
+    let mut iter = db.safe_iter().expect("Failed to create iterator");
+    iter.seek(&2).expect("Failed to seek");
+    iter.seek_to_first();
+    drop(iter);
+
+    let increments: Vec<_> = ops
+        .map(sample_count)
+        .iter()
+        .zip(before)
+        .map(|(after, before)| after - before)
+        .collect();
+    assert_eq!(increments, [1, 1, 2, 2]);
+}
+
+#[tokio::test]
+async fn test_slow_op_callback() {
+    const SLOW_MERGE: Duration = Duration::from_millis(100);
+    // A merge operator that stalls every read of a merged key.
+    fn slow_merge(
+        _key: &[u8],
+        existing: Option<&[u8]>,
+        operands: &rocksdb::MergeOperands,
+    ) -> Option<Vec<u8>> {
+        std::thread::sleep(SLOW_MERGE);
+        operands.iter().last().or(existing).map(<[u8]>::to_vec)
+    }
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events_cloned = events.clone();
+    let metric_conf = MetricConf::new("slow_ops").with_slow_op_callback(
+        SLOW_MERGE / 2,
+        Arc::new(move |event| events_cloned.lock().unwrap().push(event)),
+    );
+    let mut cf_options = default_db_options().options;
+    cf_options.set_merge_operator_associative("slow_merge", slow_merge);
+    let rocks = open_cf_opts(temp_dir(), None, metric_conf, &[("slow", cf_options)])
+        .expect("Failed to open database");
+    let db: DBMap<u32, String> =
+        DBMap::reopen(&rocks, Some("slow"), &ReadWriteOptions::default(), true)
+            .expect("Failed to open storage");
+
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+    db.merge(&1, &"2".to_string()).expect("Failed to merge");
+    assert_eq!(db.get(&1).unwrap(), Some("2".to_string()));
+
+    let events = events.lock().unwrap();
+    let slow_gets: Vec<_> = events.iter().filter(|event| event.op == "get").collect();
+    assert_eq!(slow_gets.len(), 1);
+    assert_eq!(slow_gets[0].cf_name.as_deref(), Some("slow"));
+    assert_eq!(slow_gets[0].key_bytes, 4);
+    assert!(slow_gets[0].elapsed >= SLOW_MERGE);
+}
+
+#[tokio::test]
+async fn test_event_callback_flush_completed() {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let metric_conf = MetricConf::new("events").with_event_callback(
+        Duration::from_millis(20),
+        Arc::new(move |event| {
+            let _ = tx.send(event);
+        }),
+    );
+    let rocks = open_cf_opts(
+        temp_dir(),
+        None,
+        metric_conf,
+        &[("events", default_db_options().options)],
+    )
+    .expect("Failed to open database");
+    let db: DBMap<u32, String> =
+        DBMap::reopen(&rocks, Some("events"), &ReadWriteOptions::default(), true)
+            .expect("Failed to open storage");
+
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+    db.flush().expect("Failed to flush");
+
+    let flushed_cf = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            match rx.recv().await.expect("Failed to receive event") {
+                DbEvent::FlushCompleted { cf_name, .. } => break cf_name,
+                _ => continue,
+            }
+        }
+    })
+    .await
+    .expect("Failed to observe the flush");
+    assert_eq!(flushed_cf, "events");
+}
+
 #[tokio::test]
 async fn test_iterator_seek() {
     let db: DBMap<u32, String> = open_map(temp_dir(), None);
@@ -1100,3 +2504,3119 @@ async fn test_optimistic_transaction_conflict_and_retry() {
 
     assert_eq!(db.get(&1).unwrap(), Some("final".to_string()));
 }
+
+#[tokio::test]
+async fn test_bottommost_zstd_compression() {
+    fn compacted_sst_size(db: &DBMap<u32, String>) -> i64 {
+        db.flush().expect("Failed to flush");
+        db.compact_range_to_bottom(&0u32, &u32::MAX)
+            .expect("Failed to compact");
+        DBMap::<u32, String>::get_i64_property(
+            &db.rocksdb,
+            &db.cf().expect("cf handle"),
+            properties::TOTAL_SST_FILES_SIZE,
+        )
+        .expect("Failed to read sst size")
+    }
+
+    let mut uncompressed_options = default_db_options().options;
+    uncompressed_options.set_compression_type(rocksdb::DBCompressionType::None);
+    uncompressed_options.set_bottommost_compression_type(rocksdb::DBCompressionType::None);
+
+    let uncompressed: DBMap<u32, String> = DBMap::open(
+        temp_dir(),
+        MetricConf::default(),
+        Some(uncompressed_options.clone()),
+        None,
+        &ReadWriteOptions::default(),
+    )
+    .expect("Failed to open storage");
+    let compressed: DBMap<u32, String> = DBMap::open_with_bottommost_compression(
+        temp_dir(),
+        MetricConf::default(),
+        Some(uncompressed_options.clone()),
+        None,
+        &ReadWriteOptions::default(),
+        &BottommostCompressionConfig::default(),
+    )
+    .expect("Failed to open storage");
+
+    // Highly repetitive values compress well with a trained dictionary.
+    let prefix = "walrus blob metadata ".repeat(16);
+    let keys_vals = (0..5_000u32).map(|i| (i, format!("{prefix}{i}")));
+    uncompressed
+        .multi_insert(keys_vals.clone())
+        .expect("Failed to multi-insert");
+    compressed
+        .multi_insert(keys_vals.clone())
+        .expect("Failed to multi-insert");
+
+    let uncompressed_size = compacted_sst_size(&uncompressed);
+    let compressed_size = compacted_sst_size(&compressed);
+    assert!(
+        compressed_size * 2 < uncompressed_size,
+        "expected bottommost zstd to shrink the data: {compressed_size} vs {uncompressed_size}"
+    );
+
+    for (k, v) in keys_vals {
+        assert_eq!(compressed.get(&k).expect("Failed to get"), Some(v));
+    }
+
+    // Only the map's column family is configured.
+    let path = temp_dir();
+    let _table: DBMap<u32, String> = DBMap::open_with_bottommost_compression(
+        &path,
+        MetricConf::default(),
+        Some(uncompressed_options),
+        Some("table"),
+        &ReadWriteOptions::default(),
+        &BottommostCompressionConfig::default(),
+    )
+    .expect("Failed to open storage");
+    let bottommost_compression =
+        |cf_name| read_cf_options(&path, cf_name).unwrap()["bottommost_compression"].clone();
+    assert_eq!(bottommost_compression("table"), "kZSTD");
+    assert_eq!(
+        bottommost_compression(rocksdb::DEFAULT_COLUMN_FAMILY_NAME),
+        "kNoCompression"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_async_map_concurrent_get() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), None);
+    db.multi_insert((0..1_000u32).map(|i| (i, i.to_string())))
+        .expect("Failed to multi-insert");
+    let db = async_map::AsyncDBMap::new(db, 4);
+
+    let tasks: Vec<_> = (0..64u32)
+        .map(|task| {
+            let db = db.clone();
+            tokio::spawn(async move {
+                for i in (task..1_000).step_by(64) {
+                    assert_eq!(db.get(i).await.expect("Failed to get"), Some(i.to_string()));
+                }
+                assert_eq!(db.get(1_000 + task).await.expect("Failed to get"), None);
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.expect("task should not panic");
+    }
+
+    let values = db
+        .multi_get(vec![1, 1_001, 999])
+        .await
+        .expect("Failed to multi get");
+    assert_eq!(
+        values,
+        vec![Some("1".to_string()), None, Some("999".to_string())]
+    );
+
+    let mut iter = db.iter().await;
+    let mut count = 0;
+    while let Some(item) = iter.next().await {
+        let (k, v) = item.expect("Failed to iterate");
+        assert_eq!(k, count);
+        assert_eq!(v, count.to_string());
+        count += 1;
+    }
+    assert_eq!(count, 1_000);
+}
+
+#[tokio::test]
+async fn test_compare_and_swap() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), None);
+
+    // `None` requires the key to be absent.
+    assert!(db.compare_and_swap(&1, None, &"a".to_string()).unwrap());
+    assert!(!db.compare_and_swap(&1, None, &"b".to_string()).unwrap());
+    assert_eq!(db.get(&1).unwrap(), Some("a".to_string()));
+
+    // A mismatching expectation leaves the value untouched.
+    assert!(
+        !db.compare_and_swap(&1, Some(&"x".to_string()), &"b".to_string())
+            .unwrap()
+    );
+    assert!(
+        db.compare_and_swap(&1, Some(&"a".to_string()), &"b".to_string())
+            .unwrap()
+    );
+    assert_eq!(db.get(&1).unwrap(), Some("b".to_string()));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn test_compare_and_swap_concurrent() {
+    let db: Arc<DBMap<u32, u64>> = Arc::new(open_map(temp_dir(), None));
+    assert!(db.compare_and_swap(&1, None, &0).unwrap());
+
+    for generation in 0..20u64 {
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let db = db.clone();
+                tokio::spawn(async move {
+                    db.compare_and_swap(&1, Some(&generation), &(generation + 1))
+                        .expect("Failed to compare and swap")
+                })
+            })
+            .collect();
+        let mut wins = 0;
+        for task in tasks {
+            if task.await.expect("task should not panic") {
+                wins += 1;
+            }
+        }
+        assert_eq!(wins, 1, "exactly one CAS must win generation {generation}");
+    }
+    assert_eq!(db.get(&1).unwrap(), Some(20));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn test_plain_writes_take_key_locks() {
+    let db: Arc<DBMap<u32, u64>> = Arc::new(open_map(temp_dir(), None));
+    let key_buf = db.codec.encode_key(&1).unwrap();
+    let guard = db.rocksdb.lock_key(&db.cf, &key_buf);
+    // The locks are reentrant, so the thread holding the lock can still write the key.
+    db.insert(&1, &1).expect("Failed to insert");
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let writers: Vec<_> = (0..3)
+        .map(|writer| {
+            let db = db.clone();
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                match writer {
+                    0 => db.insert(&1, &2).expect("Failed to insert"),
+                    1 => db.remove(&1).expect("Failed to remove"),
+                    _ => {
+                        let mut batch = db.batch();
+                        batch.insert_batch(&db, [(1, 3)]).unwrap();
+                        batch.write().expect("Failed to write batch");
+                    }
+                }
+                sender.send(writer).unwrap();
+            })
+        })
+        .collect();
+    assert!(
+        receiver.recv_timeout(Duration::from_millis(200)).is_err(),
+        "writes of a locked key must wait for its lock"
+    );
+    assert_eq!(db.get(&1).unwrap(), Some(1));
+
+    drop(guard);
+    for writer in writers {
+        writer.join().expect("writer should not panic");
+    }
+    assert_eq!(receiver.try_iter().count(), 3);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn test_range_delete_locks_only_its_column_family() {
+    let rocks = open_rocksdb(temp_dir(), &["cf1", "cf2"]);
+    let db_cf1: Arc<DBMap<u32, u64>> = Arc::new(
+        DBMap::reopen(&rocks, Some("cf1"), &ReadWriteOptions::default(), false)
+            .expect("Failed to open cf1"),
+    );
+    let db_cf2: Arc<DBMap<u32, u64>> = Arc::new(
+        DBMap::reopen(&rocks, Some("cf2"), &ReadWriteOptions::default(), false)
+            .expect("Failed to open cf2"),
+    );
+
+    // Hold the locks of a range delete in cf1.
+    let mut range_locks = BatchKeyLocks::default();
+    range_locks.record_range("cf1");
+    let guard = rocks.lock_batch_keys(&range_locks);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let writers: Vec<_> = [db_cf1.clone(), db_cf2.clone()]
+        .into_iter()
+        .enumerate()
+        .map(|(writer, db)| {
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                db.insert(&1, &1).expect("Failed to insert");
+                let mut batch = db.batch();
+                batch.insert_batch(&db, [(2, 2)]).unwrap();
+                batch.write().expect("Failed to write batch");
+                sender.send(writer).unwrap();
+            })
+        })
+        .collect();
+
+    // Writes to cf2 proceed while those to cf1 wait for the range delete.
+    assert_eq!(
+        receiver.recv_timeout(Duration::from_secs(10)),
+        Ok(1),
+        "writes to another column family must not wait for the range delete"
+    );
+    assert_eq!(db_cf2.get(&2).unwrap(), Some(2));
+    assert!(
+        receiver.recv_timeout(Duration::from_millis(200)).is_err(),
+        "writes to the column family of the range delete must wait for it"
+    );
+    assert_eq!(db_cf1.get(&1).unwrap(), None);
+
+    drop(guard);
+    for writer in writers {
+        writer.join().expect("writer should not panic");
+    }
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![0]);
+    assert_eq!(db_cf1.get(&2).unwrap(), Some(2));
+}
+
+#[tokio::test]
+async fn test_get_or_default() {
+    let db: DBMap<u32, Vec<u64>> = open_map(temp_dir(), None);
+    db.insert(&1, &vec![1, 2]).unwrap();
+    db.insert(&2, &vec![]).unwrap();
+
+    assert_eq!(db.get_or_default(&1).unwrap(), vec![1, 2]);
+    assert_eq!(db.get_or_default(&2).unwrap(), Vec::<u64>::new());
+    assert_eq!(db.get_or_default(&3).unwrap(), Vec::<u64>::new());
+    // The default is not inserted.
+    assert!(!db.contains_key(&3).unwrap());
+}
+
+#[tokio::test]
+async fn test_get_or_insert_with() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), None);
+
+    // Absent: the default is inserted and returned.
+    assert_eq!(
+        db.get_or_insert_with(&1, || "default".to_string()).unwrap(),
+        "default"
+    );
+    assert_eq!(db.get(&1).unwrap(), Some("default".to_string()));
+
+    // Present: the existing value is returned and the default is not evaluated.
+    db.insert(&2, &"existing".to_string()).unwrap();
+    assert_eq!(
+        db.get_or_insert_with(&2, || panic!("default must not be evaluated"))
+            .unwrap(),
+        "existing"
+    );
+}
+
+#[tokio::test]
+async fn test_get_or_insert_with_default_writes_db() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), None);
+
+    // The default may write other keys and even the key itself; a value inserted by the default
+    // wins over the returned default.
+    let value = db
+        .get_or_insert_with(&1, || {
+            db.insert(&2, &"other".to_string()).unwrap();
+            db.insert(&1, &"inner".to_string()).unwrap();
+            "outer".to_string()
+        })
+        .unwrap();
+    assert_eq!(value, "inner");
+    assert_eq!(db.get(&1).unwrap(), Some("inner".to_string()));
+    assert_eq!(db.get(&2).unwrap(), Some("other".to_string()));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn test_get_or_insert_with_concurrent() {
+    let db: Arc<DBMap<u32, u64>> = Arc::new(open_map(temp_dir(), None));
+    let tasks: Vec<_> = (0..16u64)
+        .map(|task| {
+            let db = db.clone();
+            tokio::spawn(async move {
+                db.get_or_insert_with(&7, || task)
+                    .expect("Failed to get or insert")
+            })
+        })
+        .collect();
+    let mut values = Vec::new();
+    for task in tasks {
+        values.push(task.await.expect("task should not panic"));
+    }
+
+    // All tasks observe the single value that won the race.
+    let stored = db.get(&7).unwrap().expect("value must be stored");
+    assert!(values.iter().all(|value| *value == stored));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn test_take_concurrent() {
+    let db: Arc<DBMap<u32, u64>> = Arc::new(open_map(temp_dir(), None));
+    assert_eq!(db.take(&1).unwrap(), None);
+
+    for generation in 0..20u64 {
+        db.insert(&1, &generation).expect("Failed to insert");
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let db = db.clone();
+                tokio::spawn(async move { db.take(&1).expect("Failed to take") })
+            })
+            .collect();
+        let mut taken = Vec::new();
+        for task in tasks {
+            taken.extend(task.await.expect("task should not panic"));
+        }
+        assert_eq!(taken, vec![generation], "exactly one take must win");
+        assert_eq!(db.get(&1).unwrap(), None);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn test_swap_concurrent() {
+    let db: Arc<DBMap<u32, u64>> = Arc::new(open_map(temp_dir(), None));
+    assert_eq!(db.swap(&1, &0).unwrap(), None);
+
+    let tasks: Vec<_> = (0..8u64)
+        .map(|task| {
+            let db = db.clone();
+            tokio::spawn(async move {
+                (0..100u64)
+                    .filter_map(|i| db.swap(&1, &(1 + task * 100 + i)).expect("Failed to swap"))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let mut values = Vec::new();
+    for task in tasks {
+        values.extend(task.await.expect("task should not panic"));
+    }
+    values.push(db.get(&1).unwrap().expect("the key should be present"));
+    values.sort_unstable();
+    // Every written value is either returned by exactly one swap or still stored.
+    assert_eq!(values, (0..=800).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn test_try_insert() {
+    let db = open_map::<_, u32, String>(temp_dir(), None);
+
+    db.try_insert(&1, &"first".to_string())
+        .expect("Failed to insert a fresh key");
+    assert_eq!(db.get(&1).unwrap(), Some("first".to_string()));
+
+    assert_eq!(
+        db.try_insert(&1, &"second".to_string()),
+        Err(TypedStoreError::KeyAlreadyExists)
+    );
+    assert_eq!(db.get(&1).unwrap(), Some("first".to_string()));
+
+    // A removed key can be inserted again.
+    db.remove(&1).expect("Failed to remove");
+    db.try_insert(&1, &"third".to_string())
+        .expect("Failed to insert a removed key");
+    assert_eq!(db.get(&1).unwrap(), Some("third".to_string()));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn test_try_insert_concurrent() {
+    let db: Arc<DBMap<u32, u64>> = Arc::new(open_map(temp_dir(), None));
+    let tasks: Vec<_> = (0..16u64)
+        .map(|task| {
+            let db = db.clone();
+            tokio::spawn(async move { db.try_insert(&7, &task).map(|()| task) })
+        })
+        .collect();
+    let mut winners = Vec::new();
+    for task in tasks {
+        match task.await.expect("task should not panic") {
+            Ok(value) => winners.push(value),
+            Err(error) => assert_eq!(error, TypedStoreError::KeyAlreadyExists),
+        }
+    }
+
+    // Exactly one task inserted the key, and its value is the one stored.
+    assert_eq!(winners.len(), 1);
+    assert_eq!(db.get(&7).unwrap(), Some(winners[0]));
+}
+
+#[tokio::test]
+async fn test_entry_vacant_and_occupied() {
+    let db = open_map::<_, u32, String>(temp_dir(), None);
+
+    // A vacant entry inserts the default.
+    let entry = db.entry(&1).expect("Failed to get entry");
+    assert!(matches!(entry, Entry::Vacant(_)));
+    assert_eq!(entry.key(), &1);
+    assert_eq!(entry.or_insert("first".to_string()).unwrap(), "first");
+    assert_eq!(db.get(&1).unwrap(), Some("first".to_string()));
+
+    // An occupied entry returns the stored value and leaves it untouched.
+    let entry = db.entry(&1).expect("Failed to get entry");
+    assert!(matches!(entry, Entry::Occupied(_)));
+    let value = entry
+        .or_insert_with(|| panic!("default must not be evaluated for an occupied entry"))
+        .unwrap();
+    assert_eq!(value, "first");
+    assert_eq!(db.get(&1).unwrap(), Some("first".to_string()));
+
+    // Removing through an occupied entry returns the old value.
+    let Entry::Occupied(entry) = db.entry(&1).unwrap() else {
+        panic!("entry must be occupied");
+    };
+    assert_eq!(entry.get(), "first");
+    assert_eq!(entry.remove().unwrap(), "first");
+    assert_eq!(db.get(&1).unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_entry_and_modify() {
+    let db = open_map::<_, u32, u64>(temp_dir(), None);
+
+    // Modifying a vacant entry is a no-op, so the default is inserted as is.
+    let value = db
+        .entry(&1)
+        .unwrap()
+        .and_modify(|value| *value += 1)
+        .unwrap()
+        .or_insert(10)
+        .unwrap();
+    assert_eq!(value, 10);
+    assert_eq!(db.get(&1).unwrap(), Some(10));
+
+    // Modifying an occupied entry writes the new value in place.
+    let value = db
+        .entry(&1)
+        .unwrap()
+        .and_modify(|value| *value += 1)
+        .unwrap()
+        .or_insert(0)
+        .unwrap();
+    assert_eq!(value, 11);
+    assert_eq!(db.get(&1).unwrap(), Some(11));
+
+    // A closure that leaves the value unchanged does not alter the stored value.
+    db.entry(&1).unwrap().and_modify(|_| ()).unwrap();
+    assert_eq!(db.get(&1).unwrap(), Some(11));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+async fn test_entry_and_modify_concurrent() {
+    let db: Arc<DBMap<u32, u64>> = Arc::new(open_map(temp_dir(), None));
+    let tasks: Vec<_> = (0..16)
+        .map(|_| {
+            let db = db.clone();
+            tokio::spawn(async move {
+                for _ in 0..100 {
+                    loop {
+                        match db
+                            .entry(&7)
+                            .and_then(|entry| entry.and_modify(|value| *value += 1))
+                            .and_then(|entry| entry.or_insert(1))
+                        {
+                            Err(TypedStoreError::RetryableTransactionError) => continue,
+                            result => {
+                                result.expect("Failed to update entry");
+                                break;
+                            }
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.expect("task should not panic");
+    }
+
+    // No increment is lost, as conflicting writes through entries fail and are retried.
+    assert_eq!(db.get(&7).unwrap(), Some(1600));
+}
+
+#[tokio::test]
+async fn test_entry_conflicting_write() {
+    let db = open_map::<_, u32, u64>(temp_dir(), None);
+    db.insert(&1, &1).unwrap();
+
+    // The entry does not lock the key, so other writes of the key proceed.
+    let entry = db.entry(&1).unwrap();
+    db.insert(&1, &2)
+        .expect("Failed to insert while an entry is alive");
+    // The write through the stale entry fails and leaves the key untouched.
+    assert_eq!(
+        entry.and_modify(|value| *value += 10).unwrap_err(),
+        TypedStoreError::RetryableTransactionError
+    );
+    assert_eq!(db.get(&1).unwrap(), Some(2));
+
+    let Entry::Occupied(entry) = db.entry(&1).unwrap() else {
+        panic!("entry must be occupied");
+    };
+    db.remove(&1).unwrap();
+    assert_eq!(
+        entry.remove().unwrap_err(),
+        TypedStoreError::RetryableTransactionError
+    );
+
+    let entry = db.entry(&1).unwrap();
+    db.insert(&1, &3).unwrap();
+    assert_eq!(
+        entry.or_insert(4).unwrap_err(),
+        TypedStoreError::RetryableTransactionError
+    );
+    assert_eq!(db.get(&1).unwrap(), Some(3));
+
+    // Successive writes through the same entry validate against its own writes.
+    let entry = db
+        .entry(&1)
+        .unwrap()
+        .and_modify(|value| *value += 1)
+        .unwrap()
+        .and_modify(|value| *value += 1)
+        .unwrap();
+    let Entry::Occupied(entry) = entry else {
+        panic!("entry must be occupied");
+    };
+    assert_eq!(entry.remove().unwrap(), 5);
+    assert_eq!(db.get(&1).unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_merge_add() {
+    let path_prefix = temp_dir();
+    let open = |path: PathBuf| -> DBMap<u32, u64> {
+        DBMap::open_with_merge(
+            path,
+            MetricConf::default(),
+            None,
+            Some("counters"),
+            &ReadWriteOptions::default(),
+            merge_operator::MergeOperator::add(),
+        )
+        .expect("Failed to open storage")
+    };
+    let db = open(path_prefix.join("db"));
+
+    for i in 1..=100 {
+        db.merge(&1, &i).expect("Failed to merge");
+    }
+    assert_eq!(db.get(&1).unwrap(), Some(5050));
+
+    // Merges apply on top of an existing base value.
+    db.insert(&2, &10).unwrap();
+    let mut batch = db.batch();
+    batch
+        .merge_batch(&db, (0..10).map(|_| (2, 5)))
+        .expect("Failed to batch merge");
+    batch.write().expect("Failed to execute batch");
+    assert_eq!(db.get(&2).unwrap(), Some(60));
+
+    // Operands survive a flush and compaction, and further merges fold into the result.
+    db.flush().expect("Failed to flush");
+    db.compact_range_to_bottom(&0, &u32::MAX)
+        .expect("Failed to compact");
+    db.merge(&1, &1).expect("Failed to merge");
+    assert_eq!(db.get(&1).unwrap(), Some(5051));
+
+    // Reopening a copy of the database with the same operator sees the merged values.
+    let checkpoint_path = path_prefix.join("checkpoint");
+    db.checkpoint_db(&checkpoint_path)
+        .expect("Failed to create checkpoint");
+    let reopened = open(checkpoint_path);
+    assert_eq!(reopened.get(&1).unwrap(), Some(5051));
+    assert_eq!(reopened.get(&2).unwrap(), Some(60));
+}
+
+#[tokio::test]
+async fn test_merge_append() {
+    let db: DBMap<u32, Vec<String>> = DBMap::open_with_merge(
+        temp_dir(),
+        MetricConf::default(),
+        None,
+        Some("events"),
+        &ReadWriteOptions::default(),
+        merge_operator::MergeOperator::append(),
+    )
+    .expect("Failed to open storage");
+
+    db.insert(&1, &vec!["base".to_string()]).unwrap();
+    let mut expected = vec!["base".to_string()];
+    for chunk in 0..50 {
+        let items: Vec<_> = (0..3).map(|i| format!("{chunk}-{i}")).collect();
+        db.append(&1, &items).expect("Failed to append");
+        expected.extend(items);
+        if chunk % 10 == 0 {
+            assert_eq!(db.get(&1).unwrap(), Some(expected.clone()));
+        }
+        if chunk == 25 {
+            // Let compaction fold the operands written so far.
+            db.flush().expect("Failed to flush");
+            db.compact_range_to_bottom(&0, &u32::MAX)
+                .expect("Failed to compact");
+        }
+    }
+    assert_eq!(db.get(&1).unwrap(), Some(expected));
+
+    // A key without a base value starts out as an empty list.
+    db.append(&2, &["only".to_string()]).unwrap();
+    db.append(&2, &[]).unwrap();
+    assert_eq!(db.get(&2).unwrap(), Some(vec!["only".to_string()]));
+}
+
+#[tokio::test]
+async fn test_count_in_range() {
+    let db = open_map::<_, u64, Vec<u8>>(temp_dir(), None);
+    let mut batch = db.batch();
+    batch
+        .insert_batch(&db, (0..10_000u64).map(|i| (i, vec![7u8; 100])))
+        .expect("Failed to batch insert");
+    batch.write().expect("Failed to write batch");
+    db.flush().expect("Failed to flush");
+    db.compact_range_to_bottom(&0u64, &10_000u64)
+        .expect("Failed to compact");
+
+    assert_eq!(db.count_in_range(1_000..2_000).unwrap(), 1_000);
+    assert_eq!(db.count_in_range(1_000..=2_000).unwrap(), 1_001);
+    assert_eq!(db.count_in_range(9_990..).unwrap(), 10);
+    assert_eq!(db.count_in_range(..).unwrap(), 10_000);
+    assert_eq!(db.count_in_range(20_000..).unwrap(), 0);
+
+    let estimate = db.estimate_keys_in_range(1_000..2_000).unwrap();
+    assert!(
+        (500..=2_000).contains(&estimate),
+        "estimate {estimate} too far off"
+    );
+    let estimate = db.estimate_keys_in_range(..).unwrap();
+    assert!(
+        (5_000..=20_000).contains(&estimate),
+        "estimate {estimate} too far off"
+    );
+}
+
+#[tokio::test]
+async fn test_int_properties() {
+    let db = open_map::<_, u64, Vec<u8>>(temp_dir(), None);
+    assert_eq!(db.total_sst_files_size().unwrap(), 0);
+
+    let mut batch = db.batch();
+    batch
+        .insert_batch(&db, (0..10_000u64).map(|i| (i, vec![7u8; 100])))
+        .expect("Failed to batch insert");
+    batch.write().expect("Failed to write batch");
+    let mem_tables = db
+        .get_int_property("rocksdb.cur-size-all-mem-tables")
+        .unwrap()
+        .expect("Property should be known");
+    assert!(mem_tables > 0);
+
+    db.flush().expect("Failed to flush");
+    let estimate = db.estimate_num_keys().unwrap();
+    assert!(
+        (9_000..=11_000).contains(&estimate),
+        "estimate {estimate} too far off"
+    );
+    assert!(db.total_sst_files_size().unwrap() > 10_000 * 100 / 2);
+    assert_eq!(
+        db.get_int_property("rocksdb.no-such-property").unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_memory_usage() {
+    let db = open_map::<_, u64, Vec<u8>>(temp_dir(), None);
+    let empty = db.memory_usage().unwrap();
+
+    let mut batch = db.batch();
+    batch
+        .insert_batch(&db, (0..10_000u64).map(|i| (i, vec![7u8; 100])))
+        .expect("Failed to batch insert");
+    batch.write().expect("Failed to write batch");
+    let written = db.memory_usage().unwrap();
+    assert!(written.mem_tables > empty.mem_tables + 10_000 * 100 / 2);
+    assert!(written.unflushed_mem_tables > empty.unflushed_mem_tables);
+    assert!(written.total() > empty.total());
+
+    // Flushed data is read through the table readers and block cache instead.
+    db.flush().expect("Failed to flush");
+    assert_eq!(db.safe_iter().unwrap().count(), 10_000);
+    let flushed = db.memory_usage().unwrap();
+    assert!(flushed.unflushed_mem_tables < written.unflushed_mem_tables);
+    assert!(flushed.block_cache > empty.block_cache);
+}
+
+#[tokio::test]
+async fn test_open_readonly_alongside_writer() {
+    let path = temp_dir();
+    let writer = open_map::<_, u32, String>(&path, None);
+    writer
+        .insert(&1, &"1".to_string())
+        .expect("Failed to insert");
+
+    let reader = DBMap::<u32, String>::open_readonly(
+        &path,
+        MetricConf::default(),
+        None,
+        None,
+        &ReadWriteOptions::default(),
+    )
+    .expect("Failed to open read-only");
+    assert_eq!(reader.rocksdb.access_mode(), AccessMode::ReadOnly);
+    assert_eq!(reader.get(&1).unwrap(), Some("1".to_string()));
+
+    assert_eq!(
+        reader.insert(&2, &"2".to_string()),
+        Err(TypedStoreError::ReadOnlyDatabase)
+    );
+    assert_eq!(reader.remove(&1), Err(TypedStoreError::ReadOnlyDatabase));
+    let mut batch = reader.batch();
+    batch
+        .insert_batch(&reader, [(3, "3".to_string())])
+        .expect("Failed to batch insert");
+    assert_eq!(batch.write(), Err(TypedStoreError::ReadOnlyDatabase));
+
+    // The writer is unaffected, but the read-only handle does not observe new writes.
+    writer
+        .insert(&4, &"4".to_string())
+        .expect("Failed to insert");
+    assert_eq!(reader.get(&4).unwrap(), None);
+    assert_eq!(reader.get(&1).unwrap(), Some("1".to_string()));
+}
+
+#[tokio::test]
+async fn test_open_secondary_catches_up() {
+    let primary_path = temp_dir();
+    let writer = open_map::<_, u32, String>(&primary_path, None);
+    writer
+        .insert(&1, &"1".to_string())
+        .expect("Failed to insert");
+
+    let follower = DBMap::<u32, String>::open_secondary(
+        &primary_path,
+        temp_dir(),
+        MetricConf::default(),
+        None,
+        None,
+        &ReadWriteOptions::default(),
+    )
+    .expect("Failed to open secondary");
+    assert_eq!(follower.rocksdb.access_mode(), AccessMode::Secondary);
+    assert_eq!(follower.get(&1).unwrap(), Some("1".to_string()));
+
+    writer
+        .insert(&2, &"2".to_string())
+        .expect("Failed to insert");
+    writer.flush().expect("Failed to flush");
+    assert_eq!(follower.get(&2).unwrap(), None);
+
+    follower
+        .try_catch_up_with_primary()
+        .expect("Failed to catch up with primary");
+    assert_eq!(follower.get(&2).unwrap(), Some("2".to_string()));
+    assert_eq!(
+        follower.insert(&3, &"3".to_string()),
+        Err(TypedStoreError::ReadOnlyDatabase)
+    );
+}
+
+#[tokio::test]
+async fn test_snapshot_across_column_families() {
+    let rocks = open_rocksdb(temp_dir(), &["first", "second"]);
+    let first =
+        DBMap::<u32, String>::reopen(&rocks, Some("first"), &ReadWriteOptions::default(), false)
+            .expect("Failed to open storage");
+    let second =
+        DBMap::<u32, String>::reopen(&rocks, Some("second"), &ReadWriteOptions::default(), false)
+            .expect("Failed to open storage");
+    first
+        .insert(&1, &"first-old".to_string())
+        .expect("Failed to insert");
+    second
+        .insert(&1, &"second-old".to_string())
+        .expect("Failed to insert");
+
+    let snapshot = rocks.snapshot();
+
+    let mut batch = first.batch();
+    batch
+        .insert_batch(
+            &first,
+            [(1, "first-new".to_string()), (2, "first-new".to_string())],
+        )
+        .expect("Failed to batch insert")
+        .insert_batch(&second, [(2, "second-new".to_string())])
+        .expect("Failed to batch insert");
+    batch
+        .delete_batch(&second, [1])
+        .expect("Failed to batch delete");
+    batch.write().expect("Failed to write batch");
+
+    assert_eq!(
+        first.get_with_snapshot(&1, &snapshot).unwrap(),
+        Some("first-old".to_string())
+    );
+    assert_eq!(first.get_with_snapshot(&2, &snapshot).unwrap(), None);
+    assert_eq!(
+        second.get_with_snapshot(&1, &snapshot).unwrap(),
+        Some("second-old".to_string())
+    );
+    assert_eq!(second.get_with_snapshot(&2, &snapshot).unwrap(), None);
+
+    let entries: Vec<_> = first
+        .safe_iter_with_snapshot(&snapshot)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(entries, vec![(1, "first-old".to_string())]);
+    let entries: Vec<_> = second
+        .safe_iter_with_snapshot(&snapshot)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(entries, vec![(1, "second-old".to_string())]);
+
+    // Reads without the snapshot observe the latest state.
+    assert_eq!(first.get(&1).unwrap(), Some("first-new".to_string()));
+    assert_eq!(second.get(&1).unwrap(), None);
+
+    // A snapshot of another database is rejected.
+    let other = open_map::<_, u32, String>(temp_dir(), None);
+    assert!(other.get_with_snapshot(&1, &snapshot).is_err());
+}
+
+#[tokio::test]
+async fn test_contains_key_with_snapshot() {
+    let db = open_map::<_, u32, String>(temp_dir(), None);
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+
+    let snapshot = db.rocksdb.snapshot();
+    db.remove(&1).expect("Failed to remove");
+    db.insert(&2, &"2".to_string()).expect("Failed to insert");
+
+    assert!(db.contains_key_with_snapshot(&1, &snapshot).unwrap());
+    assert!(!db.contains_key_with_snapshot(&2, &snapshot).unwrap());
+    assert!(!db.contains_key(&1).unwrap());
+    assert!(db.contains_key(&2).unwrap());
+
+    let other = open_map::<_, u32, String>(temp_dir(), None);
+    assert!(other.contains_key_with_snapshot(&1, &snapshot).is_err());
+}
+
+#[tokio::test]
+async fn test_safe_iter_with_readahead() {
+    let db = open_map::<_, u32, Vec<u8>>(temp_dir(), None);
+    let value = |i: u32| i.to_be_bytes().repeat(256);
+    db.multi_insert((0..20_000).map(|i| (i, value(i))))
+        .expect("Failed to multi-insert");
+    // Readahead applies to reads from SST files.
+    db.flush().expect("Failed to flush");
+
+    let mut count = 0;
+    for (i, entry) in db
+        .safe_iter_with_readahead(2 << 20)
+        .expect("Failed to create iterator")
+        .enumerate()
+    {
+        assert_eq!(
+            entry.expect("Failed to read entry"),
+            (i as u32, value(i as u32))
+        );
+        count += 1;
+    }
+    assert_eq!(count, 20_000);
+
+    let keys: Vec<_> = db
+        .safe_range_iter_with_readahead(5_000..15_000, 2 << 20)
+        .expect("Failed to create iterator")
+        .map(|entry| entry.expect("Failed to read entry").0)
+        .collect();
+    assert_eq!(keys, (5_000..15_000).collect::<Vec<_>>());
+
+    // A readahead of 0 disables it without affecting the result.
+    assert_eq!(
+        db.safe_range_iter_with_readahead(..=10, 0)
+            .expect("Failed to create iterator")
+            .count(),
+        11
+    );
+}
+
+#[tokio::test]
+async fn test_safe_iter_with_deadline() {
+    let db = open_map::<_, u32, u32>(temp_dir(), None);
+    db.multi_insert((0..10_000).map(|i| (i, i)))
+        .expect("Failed to multi-insert");
+
+    let mut iter = db
+        .safe_iter_with_deadline(Instant::now() + Duration::from_secs(3600))
+        .expect("Failed to create iterator");
+    assert_eq!(iter.by_ref().count(), 10_000);
+
+    // An expired deadline stops the iteration before the first entry.
+    let mut iter = db
+        .safe_iter_with_deadline(Instant::now())
+        .expect("Failed to create iterator");
+    assert!(matches!(
+        iter.next(),
+        Some(Err(TypedStoreError::DeadlineExceeded))
+    ));
+    assert!(iter.next().is_none());
+
+    // A deadline passing during the scan terminates it early.
+    let deadline = Instant::now() + Duration::from_millis(100);
+    let mut iter = db
+        .safe_iter_with_deadline(deadline)
+        .expect("Failed to create iterator");
+    for i in 0..10 {
+        assert_eq!(iter.next().unwrap().unwrap(), (i, i));
+    }
+    std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+    let rest: Vec<_> = iter.by_ref().collect();
+    assert!(
+        rest.len() <= 64,
+        "read {} entries past the deadline",
+        rest.len()
+    );
+    assert!(matches!(
+        rest.last(),
+        Some(Err(TypedStoreError::DeadlineExceeded))
+    ));
+    assert!(rest[..rest.len() - 1].iter().all(Result::is_ok));
+    assert!(iter.next().is_none());
+}
+
+#[tokio::test]
+async fn test_safe_iter_raw_keys() {
+    let db = open_map::<_, (u32, String), u64>(temp_dir(), None);
+    db.multi_insert((0..100u32).map(|i| ((i % 7, i.to_string()), u64::from(i))))
+        .expect("Failed to multi-insert");
+
+    let raw_entries: Vec<_> = db
+        .safe_iter_raw_keys()
+        .expect("Failed to create iterator")
+        .map(Result::unwrap)
+        .collect();
+    let entries: Vec<_> = db.safe_iter().unwrap().map(Result::unwrap).collect();
+    assert_eq!(raw_entries.len(), 100);
+    for ((key_bytes, raw_value), (key, value)) in raw_entries.iter().zip(&entries) {
+        assert_eq!(
+            &db.codec
+                .decode_key(key_bytes)
+                .expect("Failed to decode key"),
+            key
+        );
+        assert_eq!(raw_value, value);
+        assert!(db.get_raw(key).unwrap().is_some());
+    }
+
+    let empty = open_map::<_, u32, u64>(temp_dir(), None);
+    assert_eq!(empty.safe_iter_raw_keys().unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_snapshot_iter() {
+    let db = open_map::<_, u32, String>(temp_dir(), None);
+    let num_snapshots = |db: &DBMap<u32, String>| {
+        db.get_int_property("rocksdb.num-snapshots")
+            .unwrap()
+            .expect("Property should be known")
+    };
+    db.multi_insert([(1, "1".to_string()), (3, "3".to_string())])
+        .expect("Failed to insert");
+
+    let mut iter = db.snapshot_iter().expect("Failed to create iterator");
+    assert_eq!(num_snapshots(&db), 1);
+    assert_eq!(iter.next().unwrap().unwrap(), (1, "1".to_string()));
+
+    db.multi_insert([(2, "2".to_string()), (4, "4".to_string())])
+        .expect("Failed to insert");
+    db.insert(&3, &"new".to_string()).expect("Failed to insert");
+    let snapshot = iter.snapshot().expect("Iterator should hold a snapshot");
+    assert_eq!(db.get_with_snapshot(&4, snapshot).unwrap(), None);
+
+    let remaining: Vec<_> = iter.by_ref().collect::<Result<_, _>>().unwrap();
+    assert_eq!(remaining, vec![(3, "3".to_string())]);
+    drop(iter);
+    assert_eq!(num_snapshots(&db), 0);
+    assert_eq!(db.safe_iter().unwrap().count(), 4);
+}
+
+#[tokio::test]
+async fn test_typed_optimistic_transaction_conflict() {
+    let rocks = open_optimistic_rocksdb(temp_dir(), &["balances", "log"]);
+    let balances = DBMap::<u32, u64>::reopen(
+        &rocks,
+        Some("balances"),
+        &ReadWriteOptions::default(),
+        false,
+    )
+    .expect("Failed to open storage");
+    let log =
+        DBMap::<u64, String>::reopen(&rocks, Some("log"), &ReadWriteOptions::default(), false)
+            .expect("Failed to open storage");
+    balances.insert(&1, &100).expect("Failed to insert");
+
+    let tx1 = OptimisticTransaction::new(&rocks).expect("Failed to start transaction");
+    let tx2 = OptimisticTransaction::new(&rocks).expect("Failed to start transaction");
+    for (tx, amount) in [(&tx1, 10), (&tx2, 20)] {
+        let balance = tx
+            .get_for_update(&balances, &1)
+            .expect("Failed to read for update")
+            .expect("balance must exist");
+        tx.put(&balances, &1, &(balance + amount))
+            .expect("Failed to put");
+        tx.put(&log, &amount, &format!("+{amount}"))
+            .expect("Failed to put");
+        assert_eq!(tx.get(&balances, &1).unwrap(), Some(balance + amount));
+    }
+
+    tx1.commit().expect("Failed to commit");
+    assert_eq!(
+        tx2.commit(),
+        Err(TypedStoreError::RetryableTransactionError)
+    );
+
+    // Only the writes of the first transaction were applied, across both column families.
+    assert_eq!(balances.get(&1).unwrap(), Some(110));
+    assert_eq!(log.get(&10).unwrap(), Some("+10".to_string()));
+    assert_eq!(log.get(&20).unwrap(), None);
+
+    // Retrying the losing transaction on the new state succeeds.
+    let tx = OptimisticTransaction::new(&rocks).expect("Failed to start transaction");
+    let balance = tx.get_for_update(&balances, &1).unwrap().unwrap();
+    tx.put(&balances, &1, &(balance + 20)).unwrap();
+    tx.delete(&log, &10).unwrap();
+    tx.commit().expect("Failed to commit");
+    assert_eq!(balances.get(&1).unwrap(), Some(130));
+    assert_eq!(log.get(&10).unwrap(), None);
+
+    // Reads see the snapshot taken when the transaction started, not later commits.
+    let tx = OptimisticTransaction::new(&rocks).expect("Failed to start transaction");
+    balances.insert(&1, &200).expect("Failed to insert");
+    assert_eq!(tx.get(&balances, &1).unwrap(), Some(130));
+    assert_eq!(tx.get_for_update(&balances, &1).unwrap(), Some(130));
+    tx.put(&balances, &1, &140).unwrap();
+    assert_eq!(tx.commit(), Err(TypedStoreError::RetryableTransactionError));
+    assert_eq!(balances.get(&1).unwrap(), Some(200));
+
+    // Maps of other databases are rejected.
+    let other = open_optimistic_map::<_, u32, u64>(temp_dir(), "balances");
+    let tx = OptimisticTransaction::new(&rocks).expect("Failed to start transaction");
+    assert_eq!(tx.put(&other, &1, &1), Err(TypedStoreError::CrossDBBatch));
+    assert!(OptimisticTransaction::new(&open_rocksdb(temp_dir(), &["cf"])).is_err());
+}
+
+#[tokio::test]
+async fn test_streaming_batch() {
+    let rocks = open_rocksdb(temp_dir(), &["first", "second"]);
+    let first =
+        DBMap::<u32, Vec<u8>>::reopen(&rocks, Some("first"), &ReadWriteOptions::default(), false)
+            .expect("Failed to reopen");
+    let second =
+        DBMap::<u32, u32>::reopen(&rocks, Some("second"), &ReadWriteOptions::default(), false)
+            .expect("Failed to reopen");
+    second
+        .multi_insert((0..100).map(|i| (i, i)))
+        .expect("Failed to multi-insert");
+
+    let mut batch = first.streaming_batch(4 << 10);
+    batch
+        .insert_batch(&first, (0..10_000u32).map(|i| (i, vec![i as u8; 100])))
+        .expect("Failed to insert")
+        .delete_batch(&second, 0..50u32)
+        .expect("Failed to delete");
+    // Entries are written as the batch fills up.
+    let auto_writes = batch.auto_writes();
+    assert!(auto_writes >= 100, "{auto_writes} automatic writes");
+    assert!(first.get(&0).unwrap().is_some());
+    assert_eq!(batch.finish().expect("Failed to finish"), auto_writes);
+
+    assert_eq!(first.safe_iter().unwrap().count(), 10_000);
+    assert_eq!(first.get(&9_999).unwrap(), Some(vec![15; 100]));
+    let remaining: Vec<_> = second
+        .safe_iter()
+        .unwrap()
+        .map(|entry| entry.unwrap().0)
+        .collect();
+    assert_eq!(remaining, (50..100).collect::<Vec<_>>());
+
+    let other: DBMap<u32, Vec<u8>> = open_map(temp_dir(), None);
+    assert!(matches!(
+        other
+            .streaming_batch(1 << 20)
+            .insert_batch(&first, [(0u32, Vec::<u8>::new())]),
+        Err(TypedStoreError::CrossDBBatch)
+    ));
+}
+
+#[tokio::test]
+async fn test_indexed_batch_reads_own_writes() {
+    let rocks = open_rocksdb(temp_dir(), &["first", "second"]);
+    let first =
+        DBMap::<u32, String>::reopen(&rocks, Some("first"), &ReadWriteOptions::default(), false)
+            .expect("Failed to open storage");
+    let second =
+        DBMap::<u32, String>::reopen(&rocks, Some("second"), &ReadWriteOptions::default(), false)
+            .expect("Failed to open storage");
+    first
+        .insert(&1, &"committed".to_string())
+        .expect("Failed to insert");
+    first
+        .insert(&2, &"committed".to_string())
+        .expect("Failed to insert");
+
+    let mut batch = first.indexed_batch();
+    batch
+        .insert(&first, &3, &"staged".to_string())
+        .expect("Failed to stage insert")
+        .insert(&first, &1, &"overwritten".to_string())
+        .expect("Failed to stage insert")
+        .delete(&first, &2)
+        .expect("Failed to stage delete")
+        .insert(&second, &3, &"other cf".to_string())
+        .expect("Failed to stage insert");
+
+    // The batch sees its staged writes overlaid on the committed state.
+    assert_eq!(batch.get(&first, &3).unwrap(), Some("staged".to_string()));
+    assert_eq!(
+        batch.get(&first, &1).unwrap(),
+        Some("overwritten".to_string())
+    );
+    assert_eq!(batch.get(&first, &2).unwrap(), None);
+    assert_eq!(
+        batch.get(&second, &3).unwrap(),
+        Some("other cf".to_string())
+    );
+    assert_eq!(batch.get(&second, &1).unwrap(), None);
+
+    // The database does not observe them until the batch is written.
+    assert_eq!(first.get(&3).unwrap(), None);
+    assert_eq!(first.get(&1).unwrap(), Some("committed".to_string()));
+    assert_eq!(first.get(&2).unwrap(), Some("committed".to_string()));
+
+    batch.write().expect("Failed to write batch");
+    assert_eq!(first.get(&3).unwrap(), Some("staged".to_string()));
+    assert_eq!(first.get(&1).unwrap(), Some("overwritten".to_string()));
+    assert_eq!(first.get(&2).unwrap(), None);
+    assert_eq!(second.get(&3).unwrap(), Some("other cf".to_string()));
+}
+
+/// Copies the files of a live database, as left behind by a crash.
+fn copy_db(from: &Path) -> PathBuf {
+    let to = temp_dir();
+    for entry in std::fs::read_dir(from).expect("Failed to read database directory") {
+        let entry = entry.unwrap();
+        std::fs::copy(entry.path(), to.join(entry.file_name())).expect("Failed to copy file");
+    }
+    to
+}
+
+#[tokio::test]
+async fn test_flush_persists_unlogged_writes() {
+    let path = temp_dir();
+    let rocks = open_rocksdb(&path, &["first", "second"]);
+    let first =
+        DBMap::<u32, String>::reopen(&rocks, Some("first"), &ReadWriteOptions::default(), false)
+            .expect("Failed to open storage");
+    let second =
+        DBMap::<u32, String>::reopen(&rocks, Some("second"), &ReadWriteOptions::default(), false)
+            .expect("Failed to open storage");
+
+    // Bypass the write-ahead log so that only flushed writes survive.
+    let mut unlogged = WriteOptions::default();
+    unlogged.disable_wal(true);
+    let put = |db: &DBMap<u32, String>, key: u32| {
+        rocks
+            .put_cf(
+                &db.cf().unwrap(),
+                be_fix_int_ser(&key).unwrap(),
+                bcs::to_bytes(&key.to_string()).unwrap(),
+                &unlogged,
+            )
+            .expect("Failed to put");
+    };
+
+    put(&first, 1);
+    first.flush().expect("Failed to flush");
+    put(&first, 2);
+    put(&second, 2);
+    rocks.flush_all().expect("Failed to flush");
+    put(&first, 3);
+
+    let copy = copy_db(&path);
+    let recovered = open_rocksdb(&copy, &["first", "second"]);
+    let first = DBMap::<u32, String>::reopen(
+        &recovered,
+        Some("first"),
+        &ReadWriteOptions::default(),
+        false,
+    )
+    .expect("Failed to open storage");
+    let second = DBMap::<u32, String>::reopen(
+        &recovered,
+        Some("second"),
+        &ReadWriteOptions::default(),
+        false,
+    )
+    .expect("Failed to open storage");
+    assert_eq!(first.get(&1).unwrap(), Some("1".to_string()));
+    assert_eq!(first.get(&2).unwrap(), Some("2".to_string()));
+    assert_eq!(second.get(&2).unwrap(), Some("2".to_string()));
+    // The write after the last flush was lost.
+    assert_eq!(first.get(&3).unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_write_options_durability() {
+    let open = |path: &Path| {
+        let rocks = open_rocksdb(path, &["cache"]);
+        let db =
+            DBMap::<u32, String>::reopen(&rocks, Some("cache"), &ReadWriteOptions::default(), true)
+                .expect("Failed to open storage");
+        (rocks, db)
+    };
+    let unlogged = DBWriteOptions::default().disable_wal(true);
+    let synced = DBWriteOptions::default().sync(true);
+
+    let path = temp_dir();
+    let (rocks, db) = open(&path);
+    db.insert_with_options(&1, &"unlogged".to_string(), &unlogged)
+        .expect("Failed to insert");
+    db.insert_with_options(&2, &"synced".to_string(), &synced)
+        .expect("Failed to insert");
+    let mut batch = db.batch();
+    batch
+        .insert_batch(&db, [(3, "unlogged batch".to_string())])
+        .unwrap();
+    batch
+        .write_with_options(&unlogged)
+        .expect("Failed to write batch");
+    let mut batch = db.batch();
+    batch
+        .insert_batch(&db, [(4, "synced batch".to_string())])
+        .unwrap();
+    batch
+        .write_with_options(&synced)
+        .expect("Failed to write batch");
+
+    // A crash loses the unlogged writes but keeps the logged ones.
+    let (_crashed_rocks, crashed) = open(&copy_db(&path));
+    assert_eq!(crashed.get(&1).unwrap(), None);
+    assert_eq!(crashed.get(&2).unwrap(), Some("synced".to_string()));
+    assert_eq!(crashed.get(&3).unwrap(), None);
+    assert_eq!(crashed.get(&4).unwrap(), Some("synced batch".to_string()));
+
+    // A clean close flushes the unlogged writes.
+    drop(db);
+    drop(rocks);
+    let (_rocks, db) = open(&path);
+    for key in 1..=4 {
+        assert!(db.get(&key).unwrap().is_some());
+    }
+
+    // Syncing requires the write-ahead log.
+    assert!(
+        db.insert_with_options(&5, &"5".to_string(), &unlogged.clone().sync(true))
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_get_pinned() {
+    let db = open_map::<_, u32, Vec<u8>>(temp_dir(), None);
+    let blob: Vec<u8> = (0..1 << 20).map(|i| i as u8).collect();
+    db.insert(&1, &blob).expect("Failed to insert");
+    assert!(db.get_pinned(&2).unwrap().is_none());
+    assert!(db.get_pinned_value(&2).unwrap().is_none());
+
+    let raw = db.get_pinned(&1).unwrap().expect("value must exist");
+    assert_eq!(&raw[..], &bcs::to_bytes(&blob).unwrap()[..]);
+
+    for flushed in [false, true] {
+        if flushed {
+            db.flush().expect("Failed to flush");
+        }
+        let pinned = db.get_pinned_value(&1).unwrap().expect("value must exist");
+        assert_eq!(pinned.deserialize().unwrap(), blob);
+
+        // The borrowed value points into the pinned memory, so the blob was never copied.
+        let borrowed: &[u8] = pinned.deserialize_borrowed().unwrap();
+        assert_eq!(borrowed, &blob[..]);
+        let pinned_range = pinned.as_bytes().as_ptr_range();
+        assert!(pinned_range.contains(&borrowed.as_ptr()));
+        assert_eq!(borrowed.as_ptr_range().end, pinned_range.end);
+    }
+}
+
+#[tokio::test]
+async fn test_incremental_backup_and_restore() {
+    let path_prefix = temp_dir();
+    let backup_dir = path_prefix.join("backups");
+    let db: DBMap<u32, String> = open_map(path_prefix.join("db"), Some("table"));
+    let mut engine = backup::BackupEngine::open(&backup_dir).expect("Failed to open engine");
+    assert!(engine.list_backups().is_empty());
+
+    db.multi_insert((0..100).map(|i| (i, i.to_string())))
+        .expect("Failed to multi-insert");
+    let first = engine
+        .create_backup(&db.rocksdb)
+        .expect("Failed to create backup");
+    db.multi_insert((100..200).map(|i| (i, i.to_string())))
+        .expect("Failed to multi-insert");
+    let second = engine
+        .create_backup(&db.rocksdb)
+        .expect("Failed to create backup");
+
+    assert!(second.id > first.id);
+    assert!(first.size > 0 && first.num_files > 0);
+    assert!(second.timestamp >= first.timestamp);
+    assert_eq!(engine.list_backups(), vec![first, second]);
+
+    let first_path = path_prefix.join("restored_first");
+    backup::BackupEngine::restore(&backup_dir, first.id, &first_path)
+        .expect("Failed to restore backup");
+    let latest_path = path_prefix.join("restored_latest");
+    backup::BackupEngine::restore_from_latest(&backup_dir, &latest_path)
+        .expect("Failed to restore latest backup");
+
+    for (path, count) in [(first_path, 100), (latest_path, 200)] {
+        let restored: DBMap<u32, String> = open_map(path, Some("table"));
+        let entries: Vec<_> = restored
+            .safe_iter()
+            .expect("Failed to get iterator")
+            .collect::<Result<_, _>>()
+            .expect("Failed to iterate");
+        assert_eq!(
+            entries,
+            (0..count).map(|i| (i, i.to_string())).collect::<Vec<_>>()
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_ingest_external_sst() {
+    let path_prefix = temp_dir();
+    let db: DBMap<u64, String> = open_map(path_prefix.join("db"), Some("table"));
+    db.insert(&5, &"stale".to_string()).unwrap();
+    db.insert(&5_000, &"untouched".to_string()).unwrap();
+
+    let sst_path = path_prefix.join("load.sst");
+    let mut writer = sst::SstFileWriter::create(&db, &sst_path).expect("Failed to create SST");
+    writer
+        .put_all((0..1_000).map(|i| (i, i.to_string())))
+        .expect("Failed to write SST");
+    assert_eq!(
+        writer.put(&10, &"10".to_string()),
+        Err(TypedStoreError::SstKeyOutOfOrder)
+    );
+    assert_eq!(
+        writer.put(&999, &"999".to_string()),
+        Err(TypedStoreError::SstKeyOutOfOrder)
+    );
+    let sst_path = writer.finish().expect("Failed to finish SST");
+
+    db.ingest_external_sst(&[sst_path], sst::IngestOptions::default())
+        .expect("Failed to ingest SST");
+
+    for i in 0..1_000 {
+        assert_eq!(db.get(&i).unwrap(), Some(i.to_string()));
+    }
+    assert_eq!(db.get(&5_000).unwrap(), Some("untouched".to_string()));
+    assert_eq!(db.safe_iter().unwrap().count(), 1_001);
+}
+
+#[tokio::test]
+async fn test_safe_stream_matches_safe_iter() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), None);
+    assert!(std::pin::pin!(db.safe_stream()).next().await.is_none());
+
+    // Span several chunks, with a partial last one.
+    db.multi_insert((0..1_000).rev().map(|i| (i, i.to_string())))
+        .expect("Failed to multi-insert");
+
+    let streamed: Vec<_> = db
+        .safe_stream()
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .expect("Failed to stream");
+    let iterated: Vec<_> = db
+        .safe_iter()
+        .expect("Failed to get iterator")
+        .collect::<Result<_, _>>()
+        .expect("Failed to iterate");
+    assert_eq!(streamed.len(), 1_000);
+    assert_eq!(streamed, iterated);
+
+    // A partially consumed stream can be dropped.
+    let mut stream = std::pin::pin!(db.safe_stream());
+    assert_eq!(stream.next().await.unwrap().unwrap(), (0, "0".to_string()));
+}
+
+#[tokio::test]
+async fn test_async_point_operations() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), None);
+    assert_eq!(db.get_async(1).await.unwrap(), None);
+
+    for i in 0..100 {
+        db.insert_async(i, i.to_string())
+            .await
+            .expect("Failed to insert");
+    }
+    for i in 0..100 {
+        assert_eq!(db.get_async(i).await.unwrap(), db.get(&i).unwrap());
+        assert_eq!(db.get(&i).unwrap(), Some(i.to_string()));
+    }
+
+    let keys: Vec<u32> = (90..110).collect();
+    let values = db.multi_get_async(keys.clone()).await.unwrap();
+    assert_eq!(values, db.multi_get(&keys).unwrap());
+    assert_eq!(values.iter().flatten().count(), 10);
+
+    // Async calls can run concurrently on clones of the map.
+    let tasks: Vec<_> = (100..116)
+        .map(|i| {
+            let db = db.clone();
+            tokio::spawn(async move { db.insert_async(i, i.to_string()).await })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap().expect("Failed to insert");
+    }
+    assert_eq!(db.safe_iter().unwrap().count(), 116);
+}
+
+#[tokio::test]
+async fn test_async_map_stream_matches_safe_iter() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), None);
+    let async_db = async_map::AsyncDBMap::new(db.clone(), 1);
+    assert!(
+        std::pin::pin!(async_db.stream().await)
+            .next()
+            .await
+            .is_none()
+    );
+
+    // Span several chunks, with a partial last one.
+    db.multi_insert((0..1_000).rev().map(|i| (i, i.to_string())))
+        .expect("Failed to multi-insert");
+
+    let streamed: Vec<_> = async_db
+        .stream()
+        .await
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .expect("Failed to stream");
+    let iterated: Vec<_> = db
+        .safe_iter()
+        .expect("Failed to get iterator")
+        .collect::<Result<_, _>>()
+        .expect("Failed to iterate");
+    assert_eq!(streamed.len(), 1_000);
+    assert_eq!(streamed, iterated);
+
+    // A partially consumed stream can be dropped, releasing its pool slot.
+    {
+        let mut stream = std::pin::pin!(async_db.stream().await);
+        assert_eq!(stream.next().await.unwrap().unwrap(), (0, "0".to_string()));
+    }
+    assert_eq!(async_db.get(0).await.unwrap(), Some("0".to_string()));
+}
+
+#[tokio::test]
+async fn test_async_map_insert() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), None);
+    let async_db = async_map::AsyncDBMap::new(db.clone(), 4);
+    assert_eq!(async_db.get(1).await.unwrap(), None);
+
+    for i in 0..100 {
+        async_db
+            .insert(i, i.to_string())
+            .await
+            .expect("Failed to insert");
+    }
+    for i in 0..100 {
+        assert_eq!(async_db.get(i).await.unwrap(), Some(i.to_string()));
+        assert_eq!(db.get(&i).unwrap(), Some(i.to_string()));
+    }
+
+    // Inserts can run concurrently on clones of the map.
+    let tasks: Vec<_> = (100..116)
+        .map(|i| {
+            let async_db = async_db.clone();
+            tokio::spawn(async move { async_db.insert(i, i.to_string()).await })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap().expect("Failed to insert");
+    }
+    assert_eq!(db.safe_iter().unwrap().count(), 116);
+}
+
+#[tokio::test]
+async fn test_batch_write_async() {
+    let sync_db: DBMap<u32, String> = open_map(temp_dir(), None);
+    let async_db: DBMap<u32, String> = open_map(temp_dir(), None);
+    for db in [&sync_db, &async_db] {
+        db.multi_insert((0..10).map(|i| (i, i.to_string())))
+            .expect("Failed to multi-insert");
+    }
+    let fill_batch = |db: &DBMap<u32, String>| {
+        let mut batch = db.batch();
+        batch
+            .insert_batch(db, (10..1_000).map(|i| (i, i.to_string())))
+            .expect("Failed to batch insert");
+        batch
+            .delete_batch(db, 0..5)
+            .expect("Failed to batch delete");
+        batch
+    };
+
+    fill_batch(&sync_db).write().expect("Failed to write batch");
+    fill_batch(&async_db)
+        .write_async()
+        .await
+        .expect("Failed to write batch");
+
+    let sync_entries: Vec<_> = sync_db.safe_iter().unwrap().map(Result::unwrap).collect();
+    let async_entries: Vec<_> = async_db.safe_iter().unwrap().map(Result::unwrap).collect();
+    assert_eq!(sync_entries.len(), 995);
+    assert_eq!(async_entries, sync_entries);
+}
+
+#[tokio::test]
+async fn test_read_options_fill_cache_and_cache_only() {
+    let db: DBMap<u32, Vec<u8>> = open_map(temp_dir(), None);
+    let cache_only = DBReadOptions::default().read_tier(ReadTier::CacheOnly);
+
+    // Unflushed values are served from the memtable.
+    db.multi_insert((0..1_000).map(|i| (i, vec![i as u8; 1024])))
+        .expect("Failed to multi-insert");
+    assert_eq!(
+        db.get_with_read_options(&7, &cache_only).unwrap(),
+        Some(vec![7; 1024])
+    );
+
+    // Once flushed, the values are only on disk.
+    db.flush().expect("Failed to flush");
+    assert_eq!(
+        db.get_with_read_options(&7, &cache_only),
+        Err(TypedStoreError::NotInCache)
+    );
+    assert!(
+        db.safe_iter_with_read_options(&cache_only)
+            .unwrap()
+            .any(|item| item == Err(TypedStoreError::NotInCache))
+    );
+
+    // A scan without filling the cache leaves it cold.
+    let scan = |opts: &DBReadOptions| {
+        db.safe_iter_with_read_options(opts)
+            .expect("Failed to get iterator")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Failed to iterate")
+    };
+    assert_eq!(
+        scan(&DBReadOptions::default().fill_cache(false)).len(),
+        1_000
+    );
+    assert_eq!(
+        db.get_with_read_options(&7, &cache_only),
+        Err(TypedStoreError::NotInCache)
+    );
+
+    // A scan filling the cache makes all values available to cache-only reads.
+    assert_eq!(scan(&DBReadOptions::default()).len(), 1_000);
+    for i in 0..1_000 {
+        assert_eq!(
+            db.get_with_read_options(&i, &cache_only).unwrap(),
+            Some(vec![i as u8; 1024])
+        );
+    }
+    assert_eq!(scan(&cache_only).len(), 1_000);
+}
+
+#[tokio::test]
+async fn test_shared_block_cache() {
+    let cache = BlockCache::new(64 << 20);
+    assert_eq!(cache.capacity(), 64 << 20);
+    let open = |path: PathBuf| -> DBMap<u32, Vec<u8>> {
+        let mut options = default_db_options().options;
+        cache.apply(&mut options);
+        DBMap::open(
+            path,
+            MetricConf::default(),
+            Some(options),
+            None,
+            &ReadWriteOptions::default(),
+        )
+        .expect("Failed to open storage")
+    };
+    let first = open(temp_dir());
+    let second = open(temp_dir());
+
+    // Reads the flushed values of the map back through the block cache.
+    let load = |db: &DBMap<u32, Vec<u8>>| {
+        db.multi_insert((0..1_000).map(|i| (i, vec![i as u8; 1024])))
+            .expect("Failed to multi-insert");
+        db.flush().expect("Failed to flush");
+        assert_eq!(db.safe_iter().unwrap().count(), 1_000);
+    };
+
+    let initial_usage = cache.usage();
+    load(&first);
+    let first_usage = cache.usage();
+    assert!(first_usage > initial_usage);
+    load(&second);
+    let both_usage = cache.usage();
+    assert!(both_usage > first_usage);
+    assert!(both_usage <= cache.capacity());
+
+    // Values of both maps are served from the shared cache.
+    let cache_only = DBReadOptions::default().read_tier(ReadTier::CacheOnly);
+    for db in [&first, &second] {
+        assert_eq!(
+            db.get_with_read_options(&7, &cache_only).unwrap(),
+            Some(vec![7; 1024])
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_safe_iter_peek() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), None);
+    let mut iter = db.safe_iter().expect("Failed to get iterator");
+    assert_eq!(iter.peek_key().unwrap(), None);
+    assert!(iter.next().is_none());
+
+    db.multi_insert((1..=3).map(|i| (i, i.to_string())))
+        .expect("Failed to multi-insert");
+    let mut iter = db.safe_iter().expect("Failed to get iterator");
+    assert_eq!(iter.peek_key().unwrap(), Some(&1));
+    // Peeking repeatedly does not advance the iterator.
+    assert_eq!(iter.peek().unwrap(), Some(&(1, "1".to_string())));
+    assert_eq!(iter.next().unwrap().unwrap(), (1, "1".to_string()));
+    assert_eq!(iter.peek_key().unwrap(), Some(&2));
+    assert_eq!(iter.key().unwrap(), Some(2));
+    // Seeking discards the peeked entry.
+    iter.seek(&3).unwrap();
+    assert_eq!(iter.peek_key().unwrap(), Some(&3));
+    assert_eq!(iter.next().unwrap().unwrap(), (3, "3".to_string()));
+    assert_eq!(iter.peek().unwrap(), None);
+    assert!(iter.next().is_none());
+
+    let mut rev_iter = db.reversed_safe_iter_with_bounds(None, None).unwrap();
+    assert_eq!(rev_iter.peek_key().unwrap(), Some(&3));
+    assert_eq!(rev_iter.next().unwrap().unwrap(), (3, "3".to_string()));
+}
+
+#[tokio::test]
+async fn test_safe_iter_peek_merge_join() {
+    let path = temp_dir();
+    let rocks = open_rocksdb(path, &["evens", "odds"]);
+    let open = |cf| {
+        DBMap::<u32, String>::reopen(&rocks, Some(cf), &ReadWriteOptions::default(), false)
+            .expect("Failed to open storage")
+    };
+    let (evens, odds) = (open("evens"), open("odds"));
+    evens
+        .multi_insert((0..50).map(|i| (i * 2, "even".to_string())))
+        .unwrap();
+    odds.multi_insert((0..50).map(|i| (i * 2 + 1, "odd".to_string())))
+        .unwrap();
+
+    // Merge both maps in key order by always advancing the iterator with the smaller next key.
+    let mut iters = [evens.safe_iter().unwrap(), odds.safe_iter().unwrap()];
+    let mut merged = Vec::new();
+    loop {
+        let mut next = None;
+        for (i, iter) in iters.iter_mut().enumerate() {
+            if let Some(key) = iter.peek_key().unwrap()
+                && next.is_none_or(|(_, next_key)| *key < next_key)
+            {
+                next = Some((i, *key));
+            }
+        }
+        let Some((i, _)) = next else { break };
+        merged.push(iters[i].next().unwrap().unwrap());
+    }
+
+    assert_eq!(merged.len(), 100);
+    for (i, (key, value)) in merged.into_iter().enumerate() {
+        assert_eq!(key, i as u32);
+        assert_eq!(value, if i % 2 == 0 { "even" } else { "odd" });
+    }
+}
+
+#[tokio::test]
+async fn test_scan_page() {
+    let db: DBMap<u32, String> = open_map(temp_dir(), None);
+    assert_eq!(db.scan_page(None, 10).unwrap(), (vec![], None));
+
+    let expected: Vec<_> = (0..95).map(|i| (i * 2, i.to_string())).collect();
+    db.multi_insert(expected.clone())
+        .expect("Failed to multi-insert");
+
+    // Page through the map, reassembling it.
+    let mut entries = Vec::new();
+    let mut after = None;
+    let mut pages = 0;
+    loop {
+        let (page, continuation) = db.scan_page(after, 10).expect("Failed to scan page");
+        assert!(page.len() <= 10);
+        entries.extend(page);
+        pages += 1;
+        match continuation {
+            Some(key) => after = Some(key),
+            None => break,
+        }
+    }
+    assert_eq!(pages, 10);
+    assert_eq!(entries, expected);
+
+    // An exactly filled last page has no continuation key.
+    let (page, continuation) = db.scan_page(Some(178), 5).unwrap();
+    assert_eq!(page.len(), 5);
+    assert_eq!(continuation, None);
+
+    // Resuming after a removed or absent key continues with the next present key.
+    db.remove(&20).unwrap();
+    let (page, continuation) = db.scan_page(Some(20), 2).unwrap();
+    assert_eq!(page, vec![(22, "11".to_string()), (24, "12".to_string())]);
+    assert_eq!(continuation, Some(24));
+    let (page, _) = db.scan_page(Some(25), 1).unwrap();
+    assert_eq!(page, vec![(26, "13".to_string())]);
+
+    assert_eq!(db.scan_page(Some(u32::MAX), 10).unwrap(), (vec![], None));
+    assert_eq!(db.scan_page(None, 0).unwrap(), (vec![], None));
+}
+
+#[tokio::test]
+async fn test_operations_after_shutdown() {
+    let rocks = open_rocksdb(temp_dir(), &["table"]);
+    let db =
+        DBMap::<u32, String>::reopen(&rocks, Some("table"), &ReadWriteOptions::default(), true)
+            .expect("Failed to open storage");
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+    let mut batch = db.batch();
+    batch.insert_batch(&db, [(2, "2".to_string())]).unwrap();
+    assert!(!rocks.is_closed());
+
+    rocks.shutdown();
+    assert!(rocks.is_closed());
+    assert_eq!(db.get(&1), Err(TypedStoreError::DatabaseClosed));
+    assert_eq!(db.safe_iter().err(), Some(TypedStoreError::DatabaseClosed));
+    assert_eq!(
+        db.insert(&3, &"3".to_string()),
+        Err(TypedStoreError::DatabaseClosed)
+    );
+    // Batches created before the shutdown can no longer be written either.
+    assert_eq!(batch.write(), Err(TypedStoreError::DatabaseClosed));
+    // Checking for emptiness cannot fail, so it reports a non-empty map instead of panicking.
+    assert!(!db.is_empty());
+    let set = crate::rocks::set::DBSet::new(
+        DBMap::<u32, ()>::reopen(&rocks, Some("table"), &ReadWriteOptions::default(), true)
+            .expect("Failed to open set"),
+    );
+    assert!(!set.is_empty());
+}
+
+#[tokio::test]
+async fn test_close_and_reopen() {
+    let path = temp_dir();
+    let open = || {
+        let rocks = open_rocksdb(&path, &["table"]);
+        let db =
+            DBMap::<u32, String>::reopen(&rocks, Some("table"), &ReadWriteOptions::default(), true)
+                .expect("Failed to open storage");
+        (rocks, db)
+    };
+
+    let (rocks, db) = open();
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+
+    // The map still holds a handle to the database.
+    assert_eq!(
+        rocks.clone().close(),
+        Err(TypedStoreError::DatabaseInUse(2))
+    );
+    assert!(!rocks.is_closed());
+
+    drop(db);
+    rocks.close().expect("Failed to close");
+
+    // The directory lock was released, so the database can be reopened right away.
+    let (rocks, db) = open();
+    assert_eq!(db.get(&1).unwrap(), Some("1".to_string()));
+    drop(db);
+    rocks.close().expect("Failed to close");
+    let (_rocks, db) = open();
+    assert_eq!(db.get(&1).unwrap(), Some("1".to_string()));
+}
+
+#[tokio::test]
+async fn test_close_with_metrics_task() {
+    let path = temp_dir();
+    let rocks = open_rocksdb(&path, &["table"]);
+    // Not deprecated, so the map spawns its metrics reporting task.
+    let db =
+        DBMap::<u32, String>::reopen(&rocks, Some("table"), &ReadWriteOptions::default(), false)
+            .expect("Failed to open storage");
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+    drop(db);
+    rocks.close().expect("Failed to close");
+
+    let rocks = open_rocksdb(&path, &["table"]);
+    let db =
+        DBMap::<u32, String>::reopen(&rocks, Some("table"), &ReadWriteOptions::default(), false)
+            .expect("Failed to reopen storage");
+    assert_eq!(db.get(&1).unwrap(), Some("1".to_string()));
+}
+
+#[tokio::test]
+async fn test_verify_checksum() {
+    let path = temp_dir();
+    let open = || {
+        let rocks = open_rocksdb(&path, &["table"]);
+        let db = DBMap::<u32, Vec<u8>>::reopen(
+            &rocks,
+            Some("table"),
+            &ReadWriteOptions::default(),
+            true,
+        )
+        .expect("Failed to open storage");
+        (rocks, db)
+    };
+
+    let (rocks, db) = open();
+    db.multi_insert((0..1_000u32).map(|i| (i, i.to_le_bytes().repeat(25))))
+        .expect("Failed to insert");
+    db.flush().expect("Failed to flush");
+    db.verify_checksum()
+        .expect("Healthy column family should verify");
+    rocks.verify_all().expect("Healthy database should verify");
+    drop(db);
+    rocks.close().expect("Failed to close");
+
+    // Flip a byte in the first data block of the flushed SST file.
+    let sst_file = fs::read_dir(&path)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|file| file.extension().is_some_and(|ext| ext == "sst"))
+        .expect("Flushed SST file should exist");
+    let mut bytes = fs::read(&sst_file).unwrap();
+    bytes[16] ^= 0xff;
+    fs::write(&sst_file, bytes).unwrap();
+
+    let (rocks, db) = open();
+    let file_name = sst_file.file_name().unwrap().to_str().unwrap();
+    match db.verify_checksum() {
+        Err(TypedStoreError::Corruption(message)) => {
+            assert!(message.contains(file_name), "{message}")
+        }
+        result => panic!("Expected corruption, got {result:?}"),
+    }
+    assert!(matches!(
+        rocks.verify_all(),
+        Err(TypedStoreError::Corruption(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_repair() {
+    let path = temp_dir();
+    let rocks = open_rocksdb(&path, &["table"]);
+    let db =
+        DBMap::<u32, String>::reopen(&rocks, Some("table"), &ReadWriteOptions::default(), true)
+            .expect("Failed to open storage");
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+    drop(db);
+    rocks.close().expect("Failed to close");
+
+    RocksDB::repair(&path, &["table"]).expect("Failed to repair");
+    assert!(RocksDB::repair(&path, &["table", "missing"]).is_err());
+
+    let rocks = open_rocksdb(&path, &["table"]);
+    let db =
+        DBMap::<u32, String>::reopen(&rocks, Some("table"), &ReadWriteOptions::default(), true)
+            .expect("Failed to open storage");
+    assert_eq!(db.get(&1).unwrap(), Some("1".to_string()));
+}
+
+#[tokio::test]
+async fn test_ndjson_round_trip() {
+    let db = open_map::<_, u32, Vec<String>>(temp_dir(), None);
+    let entries: Vec<_> = (0..100u32)
+        .map(|i| (i, vec![i.to_string(), "\"quoted\"\n".to_string()]))
+        .collect();
+    db.multi_insert(entries.clone()).expect("Failed to insert");
+
+    let mut buffer = Vec::new();
+    assert_eq!(db.export_ndjson(&mut buffer).unwrap(), 100);
+    let exported = String::from_utf8(buffer.clone()).unwrap();
+    assert_eq!(exported.lines().count(), 100);
+    assert_eq!(
+        exported.lines().next().unwrap(),
+        r#"{"key":0,"value":["0","\"quoted\"\n"]}"#
+    );
+
+    db.unsafe_clear().expect("Failed to clear");
+    assert!(db.is_empty());
+    assert_eq!(db.import_ndjson(buffer.as_slice()).unwrap(), 100);
+    let imported: Vec<_> = db.safe_iter().unwrap().map(Result::unwrap).collect();
+    assert_eq!(imported, entries);
+
+    assert!(matches!(
+        db.import_ndjson(&b"{\"key\":1}\n"[..]),
+        Err(TypedStoreError::SerializationError(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_raw_bytes_keys_and_values() {
+    let db = open_map::<_, RawBytes, RawBytes>(temp_dir(), None);
+    let mut keys: Vec<Vec<u8>> = vec![
+        vec![0xff],
+        vec![0x00, 0x01],
+        vec![],
+        vec![0x00],
+        vec![0x01, 0x00, 0x00],
+        vec![0x80, 0x7f],
+        vec![0x80],
+        b"key".to_vec(),
+        b"ke".to_vec(),
+    ];
+    for key in &keys {
+        let value = key.iter().rev().copied().collect::<Vec<_>>();
+        db.insert(&RawBytes(key.clone()), &RawBytes(value))
+            .expect("Failed to insert");
+    }
+
+    // Keys and values are stored without any encoding.
+    let stored = db
+        .rocksdb
+        .get_pinned_cf_opt(&db.cf().unwrap(), [0x00, 0x01], &ReadOptions::default())
+        .unwrap()
+        .expect("Key should be stored as-is");
+    assert_eq!(&*stored, &[0x01, 0x00]);
+    drop(stored);
+    assert_eq!(
+        db.get(&RawBytes(vec![0x80, 0x7f])).unwrap(),
+        Some(RawBytes(vec![0x7f, 0x80]))
+    );
+    assert_eq!(db.get(&RawBytes(vec![])).unwrap(), Some(RawBytes(vec![])));
+
+    // Iteration follows the lexicographic order of the raw bytes.
+    keys.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+    let iterated: Vec<_> = db
+        .safe_iter()
+        .unwrap()
+        .map(|entry| entry.unwrap().0.into_inner())
+        .collect();
+    assert_eq!(iterated, keys);
+    let range: Vec<_> = db
+        .safe_range_iter(RawBytes(vec![0x00, 0x01])..RawBytes(vec![0x80, 0x7f]))
+        .unwrap()
+        .map(|entry| entry.unwrap().0.into_inner())
+        .collect();
+    assert_eq!(
+        range,
+        [
+            vec![0x00, 0x01],
+            vec![0x01, 0x00, 0x00],
+            b"ke".to_vec(),
+            b"key".to_vec(),
+            vec![0x80]
+        ]
+    );
+
+    // Self-describing encodings report invalid elements instead of truncating the bytes.
+    assert_eq!(
+        serde_json::from_str::<RawBytes>("[1,2]").unwrap(),
+        RawBytes(vec![1, 2])
+    );
+    assert!(serde_json::from_str::<RawBytes>("[1,300]").is_err());
+}
+
+/// Encodes values as JSON, to be distinguishable from the default BCS encoding.
+struct JsonCodec;
+
+impl<T: Serialize + DeserializeOwned> Codec<T> for JsonCodec {
+    fn encode(&self, value: &T) -> Result<Vec<u8>, TypedStoreError> {
+        serde_json::to_vec(value).map_err(typed_store_err_from_json_err)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<T, TypedStoreError> {
+        serde_json::from_slice(bytes).map_err(typed_store_err_from_json_err)
+    }
+}
+
+#[tokio::test]
+async fn test_custom_codec() {
+    let path = temp_dir();
+    let db = DBMap::<u32, Vec<u64>>::open_with_codec(
+        &path,
+        MetricConf::default(),
+        None,
+        None,
+        &ReadWriteOptions::default(),
+        Arc::new(BigEndianCodec),
+        Arc::new(JsonCodec),
+    )
+    .expect("Failed to open rocksdb");
+    for i in 0..10u32 {
+        db.insert(&i, &vec![u64::from(i); i as usize])
+            .expect("Failed to insert");
+    }
+
+    assert_eq!(db.get(&3).unwrap(), Some(vec![3, 3, 3]));
+    assert_eq!(
+        db.multi_get([1, 2, 42]).unwrap(),
+        vec![Some(vec![1]), Some(vec![2, 2]), None]
+    );
+    assert_eq!(
+        db.get_pinned_value(&2)
+            .unwrap()
+            .unwrap()
+            .deserialize()
+            .unwrap(),
+        vec![2, 2]
+    );
+    let entries: Vec<_> = db.safe_iter().unwrap().map(Result::unwrap).collect();
+    assert_eq!(entries.len(), 10);
+    assert_eq!(entries[4], (4, vec![4; 4]));
+
+    // The stored bytes are those of the custom codec, not of the default one.
+    let stored = db.get_pinned(&2).unwrap().expect("Value should be stored");
+    assert_eq!(&*stored, b"[2,2]");
+    assert_ne!(&*stored, bcs::to_bytes(&vec![2u64, 2]).unwrap().as_slice());
+}
+
+/// Encodes keys like the default key codec, but fails to encode `u32::MAX`.
+struct RejectingMaxCodec;
+
+impl Codec<u32> for RejectingMaxCodec {
+    fn encode(&self, value: &u32) -> Result<Vec<u8>, TypedStoreError> {
+        if *value == u32::MAX {
+            return Err(TypedStoreError::SerializationError(
+                "u32::MAX is rejected".into(),
+            ));
+        }
+        BigEndianCodec.encode(value)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<u32, TypedStoreError> {
+        BigEndianCodec.decode(bytes)
+    }
+}
+
+#[tokio::test]
+async fn test_range_bounds_codec_error() {
+    let db = DBMap::<u32, String>::open_with_codec(
+        temp_dir(),
+        MetricConf::default(),
+        None,
+        None,
+        &ReadWriteOptions::default(),
+        Arc::new(RejectingMaxCodec),
+        Arc::new(BcsCodec),
+    )
+    .expect("Failed to open rocksdb");
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+
+    // Failing to encode a bound is reported instead of panicking.
+    assert!(matches!(
+        db.safe_range_iter(..=u32::MAX),
+        Err(TypedStoreError::SerializationError(_))
+    ));
+    assert!(matches!(
+        db.safe_iter_with_bounds(None, Some(u32::MAX)),
+        Err(TypedStoreError::SerializationError(_))
+    ));
+    assert!(db.count_in_range(u32::MAX..).is_err());
+    assert_eq!(db.count_in_range(..42).unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_compression() {
+    let open_compressed = |compression| {
+        let rocks = open_cf_with_compression(
+            temp_dir(),
+            None,
+            MetricConf::default(),
+            &["table", "other"],
+            &[("table", compression)],
+        )
+        .expect("Failed to open rocksdb");
+        assert_eq!(rocks.compression("other"), None);
+        DBMap::<u64, String>::reopen(&rocks, Some("table"), &ReadWriteOptions::default(), true)
+            .expect("Failed to open table")
+    };
+    let uncompressed = open_compressed(Compression::None);
+    let compressed = open_compressed(Compression::Zstd { level: 3 });
+    assert_eq!(uncompressed.compression(), Some(Compression::None));
+    assert_eq!(
+        compressed.compression(),
+        Some(Compression::Zstd { level: 3 })
+    );
+    assert_eq!(
+        open_map::<_, u64, u64>(temp_dir(), None).compression(),
+        None
+    );
+    assert!(
+        open_cf_with_compression(
+            temp_dir(),
+            None,
+            MetricConf::default(),
+            &["table"],
+            &[("missing", Compression::None)],
+        )
+        .is_err()
+    );
+
+    for db in [&uncompressed, &compressed] {
+        let mut batch = db.batch();
+        batch
+            .insert_batch(
+                db,
+                (0..10_000u64).map(|i| (i, format!(r#"{{"id":{i},"status":"certified"}}"#))),
+            )
+            .expect("Failed to batch insert");
+        batch.write().expect("Failed to write batch");
+        db.flush().expect("Failed to flush");
+    }
+
+    assert_eq!(
+        compressed.get(&7).unwrap().unwrap(),
+        r#"{"id":7,"status":"certified"}"#
+    );
+    let uncompressed_size = uncompressed.total_sst_files_size().unwrap();
+    let compressed_size = compressed.total_sst_files_size().unwrap();
+    assert!(
+        compressed_size < uncompressed_size,
+        "compressed size {compressed_size} not below uncompressed size {uncompressed_size}"
+    );
+}
+
+#[tokio::test]
+async fn test_bloom_filter() {
+    // Returns the number of data blocks read for lookups of absent keys.
+    let absent_lookup_reads = |bloom_filter: Option<BloomFilter>| {
+        let mut options = default_db_options().options;
+        options.enable_statistics();
+        // Table options without any filter.
+        options.set_block_based_table_factory(&BlockBasedOptions::default());
+        let rocks = open_cf_with_bloom_filters(
+            temp_dir(),
+            Some(options),
+            MetricConf::default(),
+            &["table", "other"],
+            bloom_filter
+                .map(|filter| ("table", filter, BlockBasedOptions::default()))
+                .into_iter()
+                .collect(),
+        )
+        .expect("Failed to open rocksdb");
+        let db =
+            DBMap::<u64, u64>::reopen(&rocks, Some("table"), &ReadWriteOptions::default(), true)
+                .expect("Failed to open table");
+        assert_eq!(db.bloom_filter(), bloom_filter);
+        // Only the named column family gets the filter.
+        assert_eq!(rocks.bloom_filter("other"), None);
+
+        // Spread the even keys over several overlapping SST files.
+        for file in 0..4u64 {
+            let mut batch = db.batch();
+            batch
+                .insert_batch(&db, (0..1_000u64).map(|i| (8 * i + 2 * file, i)))
+                .expect("Failed to batch insert");
+            batch.write().expect("Failed to write batch");
+            db.flush().expect("Failed to flush");
+        }
+
+        let data_block_reads = || {
+            let options = db.rocksdb.db_options();
+            options.get_ticker_count(Ticker::BlockCacheDataHit)
+                + options.get_ticker_count(Ticker::BlockCacheDataMiss)
+        };
+        let before = data_block_reads();
+        let found = db
+            .multi_contains_keys((0..1_000u64).map(|i| 2 * i + 1))
+            .expect("Failed to look up keys");
+        assert!(found.iter().all(|found| !found));
+        data_block_reads() - before
+    };
+
+    let reads_without_filter = absent_lookup_reads(None);
+    let reads_with_filter = absent_lookup_reads(Some(BloomFilter::full(10.0)));
+    assert!(
+        reads_with_filter < reads_without_filter / 2,
+        "{reads_with_filter} reads with filter, {reads_without_filter} without"
+    );
+}
+
+#[tokio::test]
+async fn test_prefix_bloom_filter_iteration() {
+    let block_cache = BlockCache::new(1 << 20);
+    let rocks = open_cf_with_bloom_filters(
+        temp_dir(),
+        None,
+        MetricConf::default(),
+        &["table"],
+        vec![(
+            "table",
+            BloomFilter::prefix(10.0, 4),
+            block_cache.block_options(None, None),
+        )],
+    )
+    .expect("Failed to open rocksdb");
+    let db = DBMap::<u64, u64>::reopen(&rocks, Some("table"), &ReadWriteOptions::default(), true)
+        .expect("Failed to open table");
+    assert_eq!(db.bloom_filter(), Some(BloomFilter::prefix(10.0, 4)));
+
+    // Keys with different prefixes, spread over several SST files.
+    let keys: Vec<u64> = (0..100u64).map(|i| (i << 40) | i).collect();
+    for chunk in keys.chunks(30) {
+        db.multi_insert(chunk.iter().map(|key| (*key, *key)))
+            .expect("Failed to insert");
+        db.flush().expect("Failed to flush");
+    }
+
+    // Iterators cross prefixes and see all keys.
+    let mut iterated: Vec<_> = db
+        .safe_iter()
+        .expect("Failed to create iterator")
+        .map(|result| result.expect("Failed to iterate").0)
+        .collect();
+    assert_eq!(iterated.len(), keys.len());
+    iterated.sort_unstable();
+    assert_eq!(iterated, keys);
+    // The filtered column family keeps the given block cache.
+    assert!(block_cache.usage() > 0);
+}
+
+#[tokio::test]
+async fn test_comparator_reverse() {
+    let path = temp_dir();
+    let open_reversed = || {
+        let rocks = open_cf_with_comparators(
+            &path,
+            None,
+            MetricConf::default(),
+            &["reversed"],
+            &[("reversed", Comparator::reverse_bytewise())],
+        )
+        .expect("Failed to open rocksdb");
+        DBMap::<u32, String>::reopen(
+            &rocks,
+            Some("reversed"),
+            &ReadWriteOptions::default(),
+            false,
+        )
+        .expect("Failed to open table")
+    };
+    let keys = |db: &DBMap<u32, String>, range| {
+        db.safe_range_iter(range)
+            .expect("Failed to create iterator")
+            .map(|entry| entry.expect("Failed to read entry").0)
+            .collect::<Vec<_>>()
+    };
+
+    let db = open_reversed();
+    assert_eq!(
+        db.rocksdb
+            .comparator("reversed")
+            .map(|c| c.name().to_owned()),
+        Some("typed_store.ReverseBytewise".to_owned())
+    );
+    assert!(db.rocksdb.comparator("default").is_none());
+    db.multi_insert((1..=5u32).map(|i| (i, i.to_string())))
+        .expect("Failed to multi-insert");
+
+    // Big-endian keys in descending byte order are in descending numeric order.
+    assert_eq!(
+        keys(&db, (Bound::Unbounded, Bound::Unbounded)),
+        [5, 4, 3, 2, 1]
+    );
+    assert_eq!(
+        keys(&db, (Bound::Included(4), Bound::Excluded(1))),
+        [4, 3, 2]
+    );
+    assert_eq!(db.get(&3).expect("Failed to get"), Some("3".to_string()));
+
+    // Skipping follows the comparator's order and never moves backwards.
+    let mut iter = db.safe_iter().expect("Failed to create iterator");
+    iter.skip_to(&3).expect("Failed to skip");
+    assert_eq!(iter.next().unwrap().unwrap().0, 3);
+    iter.skip_to(&4).expect("Failed to skip");
+    assert_eq!(iter.next().unwrap().unwrap().0, 2);
+    drop(iter);
+
+    // Bounds and operations that assume bytewise order fail instead of silently misbehaving.
+    for range in [
+        (Bound::Excluded(4), Bound::Unbounded),
+        (Bound::Unbounded, Bound::Included(2)),
+        (Bound::Excluded(4), Bound::Included(2)),
+    ] {
+        assert!(matches!(
+            db.safe_range_iter(range),
+            Err(TypedStoreError::RocksDBError(_))
+        ));
+        assert!(db.count_in_range(range).is_err());
+    }
+    assert!(db.safe_iter_with_bounds_inclusive(None, Some(2)).is_err());
+    assert!(db.delete_prefix(&3u32).is_err());
+    assert!(db.clear_via_range_delete().is_err());
+    assert!(db.multi_contains_prefix([3u32]).is_err());
+    assert_eq!(
+        keys(&db, (Bound::Unbounded, Bound::Unbounded)),
+        [5, 4, 3, 2, 1]
+    );
+
+    // The order survives reopening with the same comparator.
+    db.flush().expect("Failed to flush");
+    drop(db);
+    let db = open_reversed();
+    assert_eq!(
+        keys(&db, (Bound::Unbounded, Bound::Unbounded)),
+        [5, 4, 3, 2, 1]
+    );
+
+    // Reopening without the comparator fails, as RocksDB persists its name.
+    drop(db);
+    assert!(open_cf(&path, None, MetricConf::default(), &["reversed"]).is_err());
+}
+
+#[tokio::test]
+async fn test_statistics_snapshot() {
+    assert_eq!(
+        open_map::<_, u64, u64>(temp_dir(), None)
+            .rocksdb
+            .statistics_snapshot(),
+        None
+    );
+
+    let db = DBMap::<u64, Vec<u8>>::open_with_statistics(
+        temp_dir(),
+        MetricConf::default(),
+        None,
+        None,
+        &ReadWriteOptions::default(),
+        StatsLevel::All,
+    )
+    .expect("Failed to open rocksdb");
+    for i in 0..100u64 {
+        db.insert(&i, &vec![1; 100]).expect("Failed to insert");
+    }
+    db.flush().expect("Failed to flush");
+    for i in 0..100u64 {
+        assert!(db.get(&i).expect("Failed to get").is_some());
+    }
+
+    let stats = db
+        .rocksdb
+        .statistics_snapshot()
+        .expect("Statistics should be enabled");
+    assert!(stats.bytes_written > 100 * 100);
+    assert!(stats.bytes_read >= 100 * 100);
+    assert_eq!(stats.keys_written, 100);
+    assert_eq!(stats.keys_read, 100);
+    assert!(stats.flush_bytes_written > 0);
+    assert_eq!(stats.get_latency.count, 100);
+    assert!(stats.write_latency.count > 0);
+}
+
+#[tokio::test]
+async fn test_block_cache_stats() {
+    assert!(
+        open_map::<_, u64, u64>(temp_dir(), None)
+            .block_cache_stats()
+            .is_err()
+    );
+
+    let db = DBMap::<u64, Vec<u8>>::open_with_statistics(
+        temp_dir(),
+        MetricConf::default(),
+        None,
+        None,
+        &ReadWriteOptions::default(),
+        StatsLevel::ExceptDetailedTimers,
+    )
+    .expect("Failed to open rocksdb");
+    db.multi_insert((0..100u64).map(|i| (i, vec![1; 100])))
+        .expect("Failed to insert");
+    // Reads from the memtables do not go through the block cache.
+    db.flush().expect("Failed to flush");
+
+    // The first reads of the flushed blocks miss the cache and load them into it.
+    for i in 0..100u64 {
+        assert!(db.get(&i).expect("Failed to get").is_some());
+    }
+    let cold = db.block_cache_stats().expect("Failed to get cache stats");
+    assert!(cold.misses > 0);
+
+    for _ in 0..10 {
+        for i in 0..100u64 {
+            assert!(db.get(&i).expect("Failed to get").is_some());
+        }
+    }
+    let warm = db.block_cache_stats().expect("Failed to get cache stats");
+    assert!(warm.hits >= cold.hits + 1_000, "{cold:?} -> {warm:?}");
+    assert_eq!(warm.misses, cold.misses);
+    assert!(warm.hit_ratio > cold.hit_ratio);
+    assert_eq!(
+        warm.hit_ratio,
+        warm.hits as f64 / (warm.hits + warm.misses) as f64
+    );
+}
+
+#[tokio::test]
+async fn test_get_keys_in_range() {
+    let db = open_map::<_, u32, String>(temp_dir(), None);
+    db.multi_insert((0..100u32).map(|i| (2 * i, i.to_string())))
+        .expect("Failed to insert");
+
+    let expected = |range: (Bound<u32>, Bound<u32>), limit: usize| -> Vec<u32> {
+        db.safe_range_iter(range)
+            .unwrap()
+            .take(limit)
+            .map(|entry| entry.unwrap().0)
+            .collect()
+    };
+    for (range, limit) in [
+        ((Bound::Included(10), Bound::Excluded(30)), 5),
+        ((Bound::Excluded(10), Bound::Included(30)), 100),
+        ((Bound::Unbounded, Bound::Unbounded), 7),
+        ((Bound::Included(11), Bound::Unbounded), 1_000),
+        ((Bound::Included(500), Bound::Unbounded), 3),
+        ((Bound::Unbounded, Bound::Excluded(50)), 0),
+    ] {
+        let keys = db.get_keys_in_range(range, limit).unwrap();
+        assert_eq!(
+            keys,
+            expected(range, limit),
+            "range {range:?}, limit {limit}"
+        );
+    }
+    assert_eq!(db.get_keys_in_range(10..30, 3).unwrap(), vec![10, 12, 14]);
+}
+
+#[tokio::test]
+async fn test_multi_get_dedup() {
+    let db = open_map::<_, u32, String>(temp_dir(), None);
+    db.multi_insert((0..10u32).map(|i| (i, i.to_string())))
+        .expect("Failed to insert");
+
+    let keys = [3, 7, 3, 42, 0, 7, 7, 42, 9, 3];
+    let values = db.multi_get_dedup(keys).expect("Failed to multi get");
+    assert_eq!(values.len(), keys.len());
+    assert_eq!(values, db.multi_get(keys).expect("Failed to multi get"));
+    assert_eq!(values[2], Some("3".to_owned()));
+    assert_eq!(values[3], None);
+    assert!(db.multi_get_dedup(Vec::<u32>::new()).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_bulk_load_guard() {
+    let db = open_map::<_, u64, Vec<u8>>(temp_dir(), Some("table"));
+    let l0_files = |db: &DBMap<u64, Vec<u8>>| {
+        db.get_int_property("rocksdb.num-files-at-level0")
+            .unwrap()
+            .expect("Property should be known")
+    };
+    // Writes keys overlapping with all other chunks, so that each flush creates an L0 file.
+    let write_chunk = |db: &DBMap<u64, Vec<u8>>, chunk: u64| {
+        db.multi_insert((0..1_000u64).map(|i| (10 * i + chunk, vec![1; 100])))
+            .expect("Failed to insert");
+        db.flush().expect("Failed to flush");
+    };
+
+    let guard = db.bulk_load_guard().expect("Failed to start bulk load");
+    // Releasing a nested guard neither restores the options nor compacts.
+    let nested = db
+        .bulk_load_guard()
+        .expect("Failed to start nested bulk load");
+    for chunk in 0..4 {
+        write_chunk(&db, chunk);
+    }
+    nested.finish().expect("Failed to finish nested bulk load");
+    for chunk in 4..8 {
+        write_chunk(&db, chunk);
+    }
+    db.rocksdb
+        .wait_for_compactions()
+        .expect("Failed to wait for compactions");
+    // No compaction was triggered by the L0 files.
+    assert_eq!(l0_files(&db), 8);
+    guard.finish().expect("Failed to finish bulk load");
+    assert_eq!(l0_files(&db), 0);
+    assert_eq!(db.safe_iter().unwrap().count(), 8_000);
+    assert!(
+        db.multi_get([0, 7_997, 9_991])
+            .unwrap()
+            .iter()
+            .all(Option::is_some)
+    );
+
+    // Automatic compactions are enabled again.
+    for chunk in 8..10 {
+        write_chunk(&db, chunk);
+    }
+    for chunk in 0..4 {
+        write_chunk(&db, chunk);
+    }
+    db.rocksdb
+        .wait_for_compactions()
+        .expect("Failed to wait for compactions");
+    assert!(l0_files(&db) < 6);
+
+    // Dropping the guards restores the options without compacting.
+    let guard = db.bulk_load_guard().expect("Failed to start bulk load");
+    let nested = db
+        .bulk_load_guard()
+        .expect("Failed to start nested bulk load");
+    drop(guard);
+    drop(nested);
+    let options = read_cf_options(db.rocksdb.path(), "table").unwrap();
+    assert_eq!(options["disable_auto_compactions"], "false");
+    assert!(db.rocksdb.bulk_loads().lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_live_files() {
+    let rocks = open_rocksdb(temp_dir(), &["table", "other"]);
+    let db = DBMap::<u64, u64>::reopen(&rocks, Some("table"), &ReadWriteOptions::default(), true)
+        .expect("Failed to open table");
+    let other =
+        DBMap::<u64, u64>::reopen(&rocks, Some("other"), &ReadWriteOptions::default(), true)
+            .expect("Failed to open other");
+    assert!(db.live_files().unwrap().is_empty());
+
+    db.multi_insert((10..100u64).map(|i| (i, i)))
+        .expect("Failed to insert");
+    db.flush().expect("Failed to flush");
+    other.insert(&1, &1).expect("Failed to insert");
+    other.flush().expect("Failed to flush");
+
+    let files = db.live_files().expect("Failed to list live files");
+    assert_eq!(files.len(), 1);
+    let file = &files[0];
+    assert!(file.size > 0);
+    assert!(file.file_number > 0);
+    assert!(file.name.ends_with(".sst"));
+    assert_eq!(file.num_entries, 90);
+    assert_eq!(file.num_deletions, 0);
+    assert_eq!(file.decode_key_range(&db).unwrap(), (Some(10), Some(99)));
+
+    db.compact_all().expect("Failed to compact");
+    let files = db.live_files().expect("Failed to list live files");
+    assert_eq!(files.iter().map(|file| file.num_entries).sum::<u64>(), 90);
+}
+
+#[tokio::test]
+async fn test_seek_range_by_first() {
+    let db = open_map::<_, (u64, u64), String>(temp_dir(), None);
+    let firsts = [0, 1, 7, 255, 256, u64::MAX - 1, u64::MAX];
+    for first in firsts {
+        db.multi_insert(
+            [0, 1, 42, u64::MAX].map(|second| ((first, second), format!("{first}-{second}"))),
+        )
+        .expect("Failed to insert");
+    }
+
+    for first in firsts {
+        let keys: Vec<_> = db
+            .seek_range_by_first(&first)
+            .unwrap()
+            .map(|entry| entry.unwrap().0)
+            .collect();
+        assert_eq!(
+            keys,
+            [0, 1, 42, u64::MAX].map(|second| (first, second)),
+            "first component {first}"
+        );
+    }
+    assert_eq!(db.seek_range_by_first(&2).unwrap().count(), 0);
+    assert_eq!(
+        db.seek_range_by_first(&u64::MAX)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap(),
+        ((u64::MAX, 0), format!("{}-0", u64::MAX))
+    );
+}
+
+#[tokio::test]
+async fn test_safe_iter_with_range_bounds() {
+    let db = open_map::<_, u32, String>(temp_dir(), None);
+    let keys = [0, 1, 2, 5, 8, 9, u32::MAX];
+    db.multi_insert(keys.map(|i| (i, i.to_string())))
+        .expect("Failed to insert");
+    let iter_keys = |lower, upper| -> Vec<u32> {
+        db.safe_iter_with_range_bounds(lower, upper)
+            .unwrap()
+            .map(|entry| entry.unwrap().0)
+            .collect()
+    };
+
+    use Bound::{Excluded, Included, Unbounded};
+    let cases = [
+        (Unbounded, Unbounded, vec![0, 1, 2, 5, 8, 9, u32::MAX]),
+        (Unbounded, Included(5), vec![0, 1, 2, 5]),
+        (Unbounded, Excluded(5), vec![0, 1, 2]),
+        (Included(0), Unbounded, vec![0, 1, 2, 5, 8, 9, u32::MAX]),
+        (Included(2), Included(8), vec![2, 5, 8]),
+        (Included(2), Excluded(8), vec![2, 5]),
+        (Excluded(0), Unbounded, vec![1, 2, 5, 8, 9, u32::MAX]),
+        (Excluded(2), Included(8), vec![5, 8]),
+        (Excluded(2), Excluded(8), vec![5]),
+        (Excluded(3), Excluded(4), vec![]),
+        (Included(9), Included(u32::MAX), vec![9, u32::MAX]),
+        (Excluded(9), Unbounded, vec![u32::MAX]),
+        (Excluded(u32::MAX), Unbounded, vec![]),
+    ];
+    for (lower, upper, expected) in cases {
+        assert_eq!(
+            iter_keys(lower, upper),
+            expected,
+            "bounds {lower:?}, {upper:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_range_bounds_with_variable_length_keys() {
+    let db = open_map::<_, RawBytes, u32>(temp_dir(), None);
+    let keys: [&[u8]; 5] = [b"a", b"ab", b"b", b"\xff", b"\xff\x00"];
+    db.multi_insert(keys.map(|key| (RawBytes(key.to_vec()), 0)))
+        .expect("Failed to insert");
+    let iter_keys = |lower: Bound<&[u8]>, upper: Bound<&[u8]>| -> Vec<Vec<u8>> {
+        db.safe_iter_with_range_bounds(
+            lower.map(|key| RawBytes(key.to_vec())),
+            upper.map(|key| RawBytes(key.to_vec())),
+        )
+        .unwrap()
+        .map(|entry| entry.unwrap().0.into_inner())
+        .collect()
+    };
+
+    // Keys extending a bound sort after it.
+    assert_eq!(
+        iter_keys(Bound::Excluded(&b"a"[..]), Bound::Unbounded),
+        [
+            b"ab".to_vec(),
+            b"b".to_vec(),
+            b"\xff".to_vec(),
+            b"\xff\x00".to_vec()
+        ]
+    );
+    assert_eq!(
+        iter_keys(Bound::Unbounded, Bound::Included(&b"a"[..])),
+        [b"a".to_vec()]
+    );
+    assert_eq!(
+        iter_keys(Bound::Excluded(&b"\xff"[..]), Bound::Unbounded),
+        [b"\xff\x00".to_vec()]
+    );
+}
+
+#[tokio::test]
+async fn test_skip_to() {
+    let db = open_map::<_, u32, String>(temp_dir(), None);
+    db.multi_insert((0..100u32).map(|i| (2 * i, i.to_string())))
+        .expect("Failed to insert");
+
+    let mut iter = db.safe_iter().unwrap();
+    assert_eq!(iter.next().unwrap().unwrap().0, 0);
+    assert_eq!(iter.next().unwrap().unwrap().0, 2);
+    iter.skip_to(&51).unwrap();
+    assert_eq!(iter.next().unwrap().unwrap().0, 52);
+    assert_eq!(iter.next().unwrap().unwrap().0, 54);
+    // Skipping to a key behind the iterator does not move it backwards.
+    iter.skip_to(&10).unwrap();
+    assert_eq!(iter.peek_key().unwrap(), Some(&56));
+    iter.skip_to(&56).unwrap();
+    assert_eq!(iter.next().unwrap().unwrap().0, 56);
+    iter.skip_to(&190).unwrap();
+    let rest: Vec<_> = iter.by_ref().map(|entry| entry.unwrap().0).collect();
+    assert_eq!(rest, [190, 192, 194, 196, 198]);
+    iter.skip_to(&0).unwrap();
+    assert!(iter.next().is_none());
+
+    // Skipping before the first call to `next` starts at the key.
+    let mut iter = db.safe_range_iter(..100).unwrap();
+    iter.skip_to(&97).unwrap();
+    let rest: Vec<_> = iter.map(|entry| entry.unwrap().0).collect();
+    assert_eq!(rest, [98]);
+}
+
+#[tokio::test]
+async fn test_set_options() {
+    let db = open_map::<_, u64, Vec<u8>>(temp_dir(), Some("table"));
+    let l0_files = |db: &DBMap<u64, Vec<u8>>| {
+        db.get_int_property("rocksdb.num-files-at-level0")
+            .unwrap()
+            .expect("Property should be known")
+    };
+
+    assert!(db.set_options(&[("no_such_option", "1")]).is_err());
+    assert!(
+        db.set_options(&[("write_buffer_size", "not a number")])
+            .is_err()
+    );
+
+    db.set_options(&[("disable_auto_compactions", "true")])
+        .expect("Failed to set options");
+    // Writes keys overlapping with all other chunks, so that each flush creates an L0 file.
+    for chunk in 0..8u64 {
+        db.multi_insert((0..1_000u64).map(|i| (10 * i + chunk, vec![1; 100])))
+            .expect("Failed to insert");
+        db.flush().expect("Failed to flush");
+    }
+    db.rocksdb
+        .wait_for_compactions()
+        .expect("Failed to wait for compactions");
+    assert_eq!(l0_files(&db), 8);
+
+    db.set_options(&[("disable_auto_compactions", "false")])
+        .expect("Failed to set options");
+    db.rocksdb
+        .wait_for_compactions()
+        .expect("Failed to wait for compactions");
+    assert!(l0_files(&db) < 8);
+}
+
+#[tokio::test]
+async fn test_open_with_integrity_checks() {
+    let path = temp_dir();
+    let open = |path: &Path| -> Result<DBMap<u32, String>, TypedStoreError> {
+        DBMap::open_with_integrity_checks(
+            path,
+            MetricConf::default(),
+            None,
+            Some("table"),
+            &ReadWriteOptions::default(),
+            IntegrityChecks::enabled(),
+        )
+    };
+    let db = open(&path).expect("Failed to open storage");
+    db.insert(&1, &"one".to_owned()).expect("Failed to insert");
+    db.flush().expect("Failed to flush");
+    drop(db);
+
+    let db = open(&path).expect("Failed to reopen healthy storage");
+    assert_eq!(db.get(&1).unwrap(), Some("one".to_owned()));
+    drop(db);
+
+    // A missing SST file makes the database inconsistent.
+    for entry in std::fs::read_dir(&path).expect("Failed to list database files") {
+        let file_path = entry.expect("Failed to read directory entry").path();
+        if file_path
+            .extension()
+            .is_some_and(|extension| extension == "sst")
+        {
+            std::fs::remove_file(file_path).expect("Failed to remove SST file");
+        }
+    }
+    let error = open(&path).expect_err("Opened inconsistent storage");
+    assert!(
+        matches!(
+            &error,
+            TypedStoreError::Corruption(message) | TypedStoreError::RocksDBError(message)
+                if message.contains("integrity checks")
+        ),
+        "unexpected error: {error}"
+    );
+}
+
+#[tokio::test]
+async fn test_rate_limiter() {
+    const BYTES_PER_SEC: u64 = 4 << 20;
+    let rate_limiter = RateLimiter::new(BYTES_PER_SEC);
+    assert_eq!(rate_limiter.bytes_per_sec(), BYTES_PER_SEC);
+    let open = |rate_limiter: &RateLimiter| -> DBMap<u32, Vec<u8>> {
+        DBMap::open_with_rate_limiter(
+            temp_dir(),
+            MetricConf::default(),
+            None,
+            &ReadWriteOptions::default(),
+            rate_limiter,
+        )
+        .expect("Failed to open storage")
+    };
+    let db = open(&rate_limiter);
+    // A second database sharing the limiter.
+    let other_db = open(&rate_limiter.clone());
+
+    // Random values do not compress, so the flush writes about `BYTES_PER_SEC` bytes.
+    let values: Vec<Vec<u8>> = (0..1024)
+        .map(|_| (0..4096).map(|_| rand::random::<u8>()).collect())
+        .collect();
+    db.multi_insert(
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (i as u32, value)),
+    )
+    .expect("Failed to insert");
+    other_db.insert(&0, &values[0]).expect("Failed to insert");
+    let start = std::time::Instant::now();
+    db.flush().expect("Failed to flush");
+    other_db.flush().expect("Failed to flush");
+    // The limit is enforced loosely, as the limiter allows bursts.
+    assert!(start.elapsed() >= Duration::from_millis(250));
+
+    assert_eq!(db.count().unwrap(), values.len());
+    assert_eq!(db.get(&1023).unwrap().as_ref(), values.last());
+    assert_eq!(other_db.get(&0).unwrap().as_ref(), values.first());
+
+    // A limiter can be added to options without the default block cache.
+    let custom = RateLimiter::with_db_options(BYTES_PER_SEC, rocksdb::Options::default());
+    assert_eq!(custom.bytes_per_sec(), BYTES_PER_SEC);
+    let custom_db = open(&custom);
+    custom_db.insert(&0, &values[0]).expect("Failed to insert");
+    custom_db.flush().expect("Failed to flush");
+    assert_eq!(custom_db.get(&0).unwrap().as_ref(), values.first());
+}
+
+#[tokio::test]
+async fn test_indexed_map() {
+    use crate::rocks::indexed_map::IndexedMap;
+
+    // Records of an owner and a size, indexed by owner and by size for large records only.
+    type Record = (String, u32);
+    let rocks = open_rocksdb(temp_dir(), &["records", "by_owner", "by_size"]);
+    fn reopen<K, V>(rocks: &Arc<RocksDB>, cf: &str) -> DBMap<K, V> {
+        DBMap::reopen(rocks, Some(cf), &ReadWriteOptions::default(), false)
+            .expect("Failed to open storage")
+    }
+    let by_owner: DBMap<(String, u64), ()> = reopen(&rocks, "by_owner");
+    let by_size: DBMap<(u32, u64), ()> = reopen(&rocks, "by_size");
+    let records = IndexedMap::<u64, Record>::new(reopen(&rocks, "records"))
+        .with_index("owner", by_owner.clone(), |(owner, _)| Some(owner.clone()))
+        .and_then(|map| {
+            map.with_index("size", by_size.clone(), |(_, size)| {
+                (*size >= 100).then_some(*size)
+            })
+        })
+        .expect("Failed to add indexes");
+    let record = |owner: &str, size| (owner.to_string(), size);
+
+    records.insert(&1, &record("alice", 10)).unwrap();
+    records.insert(&2, &record("bob", 100)).unwrap();
+    records.insert(&3, &record("alice", 100)).unwrap();
+    assert_eq!(
+        records.get_by_index("owner", &"alice".to_string()).unwrap(),
+        vec![record("alice", 10), record("alice", 100)]
+    );
+    assert_eq!(
+        records.get_keys_by_index("size", &100u32).unwrap(),
+        vec![2, 3]
+    );
+    assert_eq!(by_size.safe_iter().unwrap().count(), 2);
+
+    // Overwriting removes the old index entries.
+    records.insert(&3, &record("bob", 5)).unwrap();
+    assert_eq!(
+        records
+            .get_keys_by_index("owner", &"alice".to_string())
+            .unwrap(),
+        vec![1]
+    );
+    assert_eq!(
+        records
+            .get_keys_by_index("owner", &"bob".to_string())
+            .unwrap(),
+        vec![2, 3]
+    );
+    assert_eq!(records.get_keys_by_index("size", &100u32).unwrap(), vec![2]);
+    assert_eq!(by_owner.safe_iter().unwrap().count(), 3);
+
+    // Removing removes the index entries.
+    assert_eq!(records.remove(&2).unwrap(), Some(record("bob", 100)));
+    assert_eq!(records.remove(&2).unwrap(), None);
+    assert_eq!(records.get(&2).unwrap(), None);
+    assert_eq!(
+        records.get_by_index("owner", &"bob".to_string()).unwrap(),
+        vec![record("bob", 5)]
+    );
+    assert!(
+        records
+            .get_keys_by_index("size", &100u32)
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(by_owner.safe_iter().unwrap().count(), 2);
+    assert_eq!(by_size.safe_iter().unwrap().count(), 0);
+
+    // Unknown indexes and index key types are rejected.
+    assert!(matches!(
+        records.get_by_index("color", &"red".to_string()),
+        Err(TypedStoreError::IndexError(_))
+    ));
+    assert!(matches!(
+        records.get_by_index("size", &100u64),
+        Err(TypedStoreError::IndexError(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_schema_migration() {
+    let rocks = open_rocksdb(temp_dir(), &["prices", METADATA_CF_NAME]);
+    let db = DBMap::<u32, u64>::reopen(&rocks, Some("prices"), &ReadWriteOptions::default(), false)
+        .expect("Failed to open storage");
+    assert_eq!(db.schema_version().unwrap(), 0);
+
+    // Version 1 stores prices in dollars.
+    db.migrate_schema(1, |_, _| Ok(()))
+        .expect("Failed to migrate");
+    db.multi_insert([(1, 3), (2, 5)]).expect("Failed to insert");
+
+    // Version 2 stores prices in cents.
+    let mut migrations = 0;
+    db.migrate_schema(2, |db, batch| {
+        migrations += 1;
+        assert_eq!(db.schema_version()?, 1);
+        let entries: Vec<_> = db.safe_iter()?.collect::<Result<_, _>>()?;
+        batch.insert_batch(
+            db,
+            entries.into_iter().map(|(key, value)| (key, value * 100)),
+        )?;
+        Ok(())
+    })
+    .expect("Failed to migrate");
+    assert_eq!(migrations, 1);
+    assert_eq!(db.schema_version().unwrap(), 2);
+    assert_eq!(db.multi_get([1, 2]).unwrap(), vec![Some(300), Some(500)]);
+
+    // The migration runs only once.
+    db.migrate_schema(2, |_, _| panic!("must not migrate again"))
+        .expect("Failed to migrate");
+    let migrations = std::sync::atomic::AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                db.migrate_schema(3, |_, _| {
+                    migrations.fetch_add(1, Ordering::Relaxed);
+                    Ok(())
+                })
+                .expect("Failed to migrate")
+            });
+        }
+    });
+    assert_eq!(migrations.load(Ordering::Relaxed), 1);
+    assert_eq!(db.schema_version().unwrap(), 3);
+
+    // A failed migration leaves the data and the version unchanged, and newer versions are
+    // rejected.
+    assert!(
+        db.migrate_schema(4, |db, batch| {
+            batch.insert_batch(db, [(1, 0)])?;
+            Err(TypedStoreError::RocksDBError("failed".into()))
+        })
+        .is_err()
+    );
+    assert_eq!(db.schema_version().unwrap(), 3);
+    assert_eq!(db.get(&1).unwrap(), Some(300));
+    assert_eq!(
+        db.migrate_schema(2, |_, _| Ok(())),
+        Err(TypedStoreError::SchemaVersionTooNew(3, 2))
+    );
+
+    // Opening a new database creates the metadata column family.
+    let db = DBMap::<u32, u64>::open_with_migration(
+        temp_dir(),
+        MetricConf::default(),
+        None,
+        None,
+        &ReadWriteOptions::default(),
+        1,
+        |db, _| {
+            assert!(db.is_empty());
+            Ok(())
+        },
+    )
+    .expect("Failed to open storage");
+    assert_eq!(db.schema_version().unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_cached_map() {
+    use crate::rocks::cached_map::CachedDBMap;
+
+    let db = CachedDBMap::new(
+        open_map::<_, u32, String>(temp_dir(), None),
+        std::num::NonZeroUsize::new(2).unwrap(),
+    );
+    // Absent keys are cached as well.
+    assert_eq!(db.get(&1).unwrap(), None);
+    assert_eq!(db.cached_len(), 1);
+
+    // Writes through the wrapper are visible to cached reads.
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+    assert_eq!(db.get(&1).unwrap(), Some("1".to_string()));
+    db.insert(&1, &"one".to_string()).expect("Failed to insert");
+    assert_eq!(db.get(&1).unwrap(), Some("one".to_string()));
+
+    // Writes bypassing the wrapper require an explicit invalidation.
+    db.inner()
+        .insert(&1, &"bypass".to_string())
+        .expect("Failed to insert");
+    assert_eq!(db.get(&1).unwrap(), Some("one".to_string()));
+    db.invalidate(&1).unwrap();
+    assert_eq!(db.get(&1).unwrap(), Some("bypass".to_string()));
+
+    db.multi_insert([(2, "2".to_string()), (3, "3".to_string())])
+        .expect("Failed to insert");
+    assert_eq!(db.get(&2).unwrap(), Some("2".to_string()));
+    db.multi_remove([2]).expect("Failed to remove");
+    assert_eq!(db.get(&2).unwrap(), None);
+    assert_eq!(db.inner().get(&2).unwrap(), None);
+
+    // The least recently used entry is evicted once the cache is full.
+    db.invalidate_all();
+    db.insert(&1, &"1".to_string()).expect("Failed to insert");
+    db.insert(&2, &"2".to_string()).expect("Failed to insert");
+    assert_eq!(db.get(&1).unwrap(), Some("1".to_string()));
+    db.insert(&3, &"3".to_string()).expect("Failed to insert");
+    assert_eq!(db.cached_len(), 2);
+    for key in [1, 2] {
+        db.inner()
+            .insert(&key, &"bypass".to_string())
+            .expect("Failed to insert");
+    }
+    assert_eq!(db.get(&1).unwrap(), Some("1".to_string()));
+    assert_eq!(db.get(&2).unwrap(), Some("bypass".to_string()));
+}
+
+#[tokio::test]
+async fn test_db_set() {
+    use crate::rocks::set::DBSet;
+
+    let path = temp_dir();
+    let set = DBSet::<u32>::open(
+        &path,
+        MetricConf::default(),
+        None,
+        None,
+        &ReadWriteOptions::default(),
+    )
+    .expect("Failed to open storage");
+    assert!(set.is_empty());
+    assert!(!set.contains(&1).expect("Failed to check membership"));
+
+    for key in [5, 1, 3, 1] {
+        set.insert(&key).expect("Failed to insert");
+    }
+    assert!(!set.is_empty());
+    assert!(set.contains(&1).expect("Failed to check membership"));
+    assert!(!set.contains(&2).expect("Failed to check membership"));
+    let keys: Vec<_> = set.iter().unwrap().map(Result::unwrap).collect();
+    assert_eq!(keys, vec![1, 3, 5]);
+    let keys: Vec<_> = set.range_iter(2..).unwrap().map(Result::unwrap).collect();
+    assert_eq!(keys, vec![3, 5]);
+
+    // Members are stored with zero-length values and readable through the map.
+    assert_eq!(set.inner().get_raw(&3).unwrap(), Some(vec![]));
+    assert_eq!(set.inner().get(&3).unwrap(), Some(()));
+    set.inner().insert(&7, &()).expect("Failed to insert");
+    assert!(set.contains(&7).expect("Failed to check membership"));
+
+    set.remove(&3).expect("Failed to remove");
+    set.remove(&4).expect("Failed to remove a missing key");
+    assert!(!set.contains(&3).expect("Failed to check membership"));
+    let keys: Vec<_> = set.iter().unwrap().map(Result::unwrap).collect();
+    assert_eq!(keys, vec![1, 5, 7]);
+}
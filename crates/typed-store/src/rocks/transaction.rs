@@ -0,0 +1,170 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use rocksdb::Transaction;
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::{BatchKeyLocks, DBMap, RocksDB};
+use crate::{TypedStoreError, rocks::errors::typed_store_err_from_rocks_err};
+
+/// A typed optimistic transaction spanning the column families of an `OptimisticTransactionDB`.
+///
+/// Writes are buffered until [`Self::commit`], which applies them atomically. Keys read with
+/// [`Self::get_for_update`] are checked for conflicting writes at commit time; on a conflict the
+/// commit fails with [`TypedStoreError::RetryableTransactionError`] and the whole transaction
+/// should be retried, e.g., with [`retry_transaction!`](crate::retry_transaction).
+///
+/// The commit takes the locks of all written keys, so it is atomic with respect to the atomic
+/// read-modify-write operations of [`DBMap`], such as [`DBMap::compare_and_swap`].
+pub struct OptimisticTransaction<'a> {
+    db: &'a Arc<RocksDB>,
+    transaction: Transaction<'a, rocksdb::OptimisticTransactionDB>,
+    /// The key locks of the written keys.
+    key_locks: Mutex<BatchKeyLocks>,
+}
+
+impl fmt::Debug for OptimisticTransaction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "OptimisticTransaction {{ db: {:?} }}", self.db.path())
+    }
+}
+
+impl<'a> OptimisticTransaction<'a> {
+    /// Starts a transaction on the given database, reading from a snapshot taken now.
+    ///
+    /// The transaction is created with `OptimisticTransactionOptions::set_snapshot`, so reads of
+    /// the transaction do not see writes committed by others after it started, and conflicts are
+    /// checked against the snapshot. Fails if the database was not opened as an
+    /// `OptimisticTransactionDB`.
+    pub fn new(db: &'a Arc<RocksDB>) -> Result<Self, TypedStoreError> {
+        let handle = db.as_optimistic().ok_or_else(|| {
+            TypedStoreError::RocksDBError(
+                "optimistic transactions require an OptimisticTransactionDB".into(),
+            )
+        })?;
+        Ok(Self {
+            db,
+            transaction: handle.transaction(),
+            key_locks: Mutex::default(),
+        })
+    }
+
+    /// Returns the value for the given key as of the transaction's snapshot, including writes made
+    /// earlier in this transaction.
+    pub fn get<K, V>(&self, map: &DBMap<K, V>, key: &K) -> Result<Option<V>, TypedStoreError>
+    where
+        K: Serialize,
+        V: DeserializeOwned,
+    {
+        self.check_map(map)?;
+        let key_buf = map.codec.encode_key(key)?;
+        let snapshot = self.transaction.snapshot();
+        let mut readopts = map.opts.readopts();
+        readopts.set_snapshot(&snapshot);
+        let value = self
+            .transaction
+            .get_pinned_cf_opt(&map.cf()?, key_buf, &readopts)
+            .map_err(typed_store_err_from_rocks_err)?;
+        value
+            .map(|bytes| map.codec.decode_value(&bytes))
+            .transpose()
+    }
+
+    /// Returns the value for the given key as of the transaction's snapshot, like [`Self::get`],
+    /// and tracks the key for conflicts.
+    ///
+    /// The commit fails if the key is written by someone else after this transaction's snapshot.
+    pub fn get_for_update<K, V>(
+        &self,
+        map: &DBMap<K, V>,
+        key: &K,
+    ) -> Result<Option<V>, TypedStoreError>
+    where
+        K: Serialize,
+        V: DeserializeOwned,
+    {
+        self.check_map(map)?;
+        let key_buf = map.codec.encode_key(key)?;
+        let snapshot = self.transaction.snapshot();
+        let mut readopts = map.opts.readopts();
+        readopts.set_snapshot(&snapshot);
+        let value = self
+            .transaction
+            .get_for_update_cf_opt(&map.cf()?, key_buf, /* exclusive */ true, &readopts)
+            .map_err(typed_store_err_from_rocks_err)?;
+        value
+            .map(|bytes| map.codec.decode_value(&bytes))
+            .transpose()
+    }
+
+    /// Buffers an insertion of the given key-value pair.
+    pub fn put<K, V>(&self, map: &DBMap<K, V>, key: &K, value: &V) -> Result<(), TypedStoreError>
+    where
+        K: Serialize,
+        V: Serialize,
+    {
+        self.check_map(map)?;
+        let key_buf = map.codec.encode_key(key)?;
+        let value_buf = map.codec.encode_value(value)?;
+        self.record_key(map, &key_buf);
+        self.transaction
+            .put_cf(&map.cf()?, key_buf, value_buf)
+            .map_err(typed_store_err_from_rocks_err)
+    }
+
+    /// Buffers a removal of the given key.
+    pub fn delete<K, V>(&self, map: &DBMap<K, V>, key: &K) -> Result<(), TypedStoreError>
+    where
+        K: Serialize,
+    {
+        self.check_map(map)?;
+        let key_buf = map.codec.encode_key(key)?;
+        self.record_key(map, &key_buf);
+        self.transaction
+            .delete_cf(&map.cf()?, key_buf)
+            .map_err(typed_store_err_from_rocks_err)
+    }
+
+    /// Atomically applies all buffered writes.
+    ///
+    /// Returns [`TypedStoreError::RetryableTransactionError`] if a key read for update was
+    /// modified concurrently; in that case none of the writes are applied.
+    pub fn commit(self) -> Result<(), TypedStoreError> {
+        let key_locks = self
+            .key_locks
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        let _guards = self.db.lock_batch_keys(&key_locks);
+        self.transaction
+            .commit()
+            .map_err(typed_store_err_from_rocks_err)
+    }
+
+    /// Discards all buffered writes.
+    pub fn rollback(self) -> Result<(), TypedStoreError> {
+        self.transaction
+            .rollback()
+            .map_err(typed_store_err_from_rocks_err)
+    }
+
+    fn record_key<K, V>(&self, map: &DBMap<K, V>, key: &[u8]) {
+        let stripe = self.db.key_stripe(&map.cf, key);
+        self.key_locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .record_key(&map.cf, stripe);
+    }
+
+    fn check_map<K, V>(&self, map: &DBMap<K, V>) -> Result<(), TypedStoreError> {
+        if Arc::ptr_eq(&map.rocksdb, self.db) {
+            Ok(())
+        } else {
+            Err(TypedStoreError::CrossDBBatch)
+        }
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright (c) Walrus Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Counts the heap allocations of pinned multi-gets.
+//!
+//! This test lives in its own binary, as it installs a counting global allocator that would
+//! otherwise apply to all tests of the crate.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use typed_store::{
+    Map,
+    rocks::{DBMap, MetricConf, ReadWriteOptions},
+};
+
+/// Counts the heap allocations of the current thread, so that tests running in parallel do not
+/// affect each other's counts. Allocations made by RocksDB itself are not counted.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+// SAFETY: all allocations are delegated to the system allocator.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        // SAFETY: the caller upholds the contract of `GlobalAlloc::alloc`.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: the caller upholds the contract of `GlobalAlloc::dealloc`.
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(|count| count.get());
+    let result = f();
+    (result, ALLOCATIONS.with(|count| count.get()) - before)
+}
+
+#[tokio::test]
+async fn test_multi_get_pinned_allocations() {
+    let path = tempfile::tempdir().expect("Failed to open temporary directory");
+    let db = DBMap::<u32, Vec<u8>>::open(
+        path.path(),
+        MetricConf::default(),
+        None,
+        None,
+        &ReadWriteOptions::default(),
+    )
+    .expect("Failed to open storage");
+    let num_keys = 1_000u32;
+    db.multi_insert((0..num_keys).map(|i| (i, i.to_be_bytes().repeat(1024))))
+        .expect("Failed to multi-insert");
+    let keys: Vec<_> = (0..num_keys + 10).collect();
+
+    let (pinned, pinned_allocations) =
+        count_allocations(|| db.multi_get_pinned(&keys).expect("Failed to multi get"));
+    let (values, value_allocations) =
+        count_allocations(|| db.multi_get(&keys).expect("Failed to multi get"));
+
+    assert_eq!(pinned.len(), keys.len());
+    for (pinned, value) in pinned.iter().zip(&values) {
+        assert_eq!(
+            pinned
+                .as_ref()
+                .map(|data| bcs::from_bytes::<Vec<u8>>(data).expect("Failed to decode value")),
+            *value
+        );
+    }
+    assert!(pinned[num_keys as usize].is_none());
+    // Decoding allocates a buffer for each found value, which the pinned lookup avoids. Sampled
+    // metrics may allocate in either lookup, so only half of the difference is required.
+    assert!(
+        pinned_allocations + num_keys as usize / 2 <= value_allocations,
+        "pinned: {pinned_allocations}, decoded: {value_allocations}"
+    );
+}
@@ -22,7 +22,7 @@ use sui_types::{event::EventID, messages_checkpoint::CheckpointSequenceNumber};
 use typed_store::{
     Map,
     rocks,
-    rocks::{DBBatch, DBMap, MetricConf, ReadWriteOptions, errors::typed_store_err_from_rocks_err},
+    rocks::{DBBatch, DBMap, MetricConf, ReadWriteOptions},
 };
 use walrus_core::{
     BlobId,
@@ -377,24 +377,16 @@ impl EventBlobWriterFactory {
             ],
         )?;
         if database.cf_handle(CERTIFIED).is_none() {
-            database
-                .create_cf(CERTIFIED, &db_config.certified().to_options())
-                .map_err(typed_store_err_from_rocks_err)?;
+            database.create_cf(CERTIFIED, &db_config.certified().to_options())?;
         }
         if database.cf_handle(ATTESTED).is_none() {
-            database
-                .create_cf(ATTESTED, &db_config.attested().to_options())
-                .map_err(typed_store_err_from_rocks_err)?;
+            database.create_cf(ATTESTED, &db_config.attested().to_options())?;
         }
         if database.cf_handle(PENDING).is_none() {
-            database
-                .create_cf(PENDING, &db_config.pending().to_options())
-                .map_err(typed_store_err_from_rocks_err)?;
+            database.create_cf(PENDING, &db_config.pending().to_options())?;
         }
         if database.cf_handle(FAILED_TO_ATTEST).is_none() {
-            database
-                .create_cf(FAILED_TO_ATTEST, &db_config.failed_to_attest().to_options())
-                .map_err(typed_store_err_from_rocks_err)?;
+            database.create_cf(FAILED_TO_ATTEST, &db_config.failed_to_attest().to_options())?;
         }
         let certified: DBMap<(), CertifiedEventBlobMetadata> = DBMap::reopen(
             &database,
@@ -818,8 +818,7 @@ impl Storage {
         tracing::info!("clear metadata in test");
         self.metadata.schedule_delete_all()?;
         self.metadata.flush()?;
-        self.metadata
-            .compact_range(&BlobId([0; 32]), &BlobId([255; 32]))?;
+        self.metadata.compact_all()?;
         Ok(())
     }
 
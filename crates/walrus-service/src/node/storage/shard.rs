@@ -21,14 +21,7 @@ use tokio::sync::RwLock;
 use typed_store::{
     Map,
     TypedStoreError,
-    rocks::{
-        DBBatch,
-        DBMap,
-        ReadWriteOptions,
-        RocksDB,
-        be_fix_int_ser as to_rocks_db_key,
-        errors::typed_store_err_from_rocks_err,
-    },
+    rocks::{DBBatch, DBMap, ReadWriteOptions, RocksDB, be_fix_int_ser as to_rocks_db_key},
 };
 use walrus_core::{
     BlobId,
@@ -292,8 +285,7 @@ macro_rules! reopen_cf {
         if $db.cf_handle(&cf_name).is_none() {
             #[cfg(msim)]
             sui_macros::fail_point!("create-cf-before");
-            $db.create_cf(&cf_name, &cf_db_option)
-                .map_err(typed_store_err_from_rocks_err)?;
+            $db.create_cf(&cf_name, &cf_db_option)?;
         }
         DBMap::reopen($db, Some(&cf_name), &$rw_options, false)?
     }};
@@ -1424,21 +1416,11 @@ impl ShardStorage {
         let rocksdb = self.primary_slivers.rocksdb.clone();
 
         // Drop column families in reverse order of creation in ShardStorage::create_or_reopen.
-        rocksdb
-            .drop_cf(&self.cf_names.secondary_slivers)
-            .map_err(typed_store_err_from_rocks_err)?;
-        rocksdb
-            .drop_cf(&self.cf_names.primary_slivers)
-            .map_err(typed_store_err_from_rocks_err)?;
-        rocksdb
-            .drop_cf(&self.cf_names.pending_recover_slivers)
-            .map_err(typed_store_err_from_rocks_err)?;
-        rocksdb
-            .drop_cf(&self.cf_names.shard_sync_progress)
-            .map_err(typed_store_err_from_rocks_err)?;
-        rocksdb
-            .drop_cf(&self.cf_names.shard_status)
-            .map_err(typed_store_err_from_rocks_err)?;
+        rocksdb.drop_cf(&self.cf_names.secondary_slivers)?;
+        rocksdb.drop_cf(&self.cf_names.primary_slivers)?;
+        rocksdb.drop_cf(&self.cf_names.pending_recover_slivers)?;
+        rocksdb.drop_cf(&self.cf_names.shard_sync_progress)?;
+        rocksdb.drop_cf(&self.cf_names.shard_status)?;
         Ok(())
     }
 